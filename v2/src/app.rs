@@ -1,21 +1,35 @@
+use crate::assets::bundled_hljs_themes;
 use crate::cli::Options;
-use crate::config::{Config, SearchMatcher};
+use crate::command::{Command, Handler, COMMANDS};
+use crate::config::{Config, FileExtensions, SearchMatcher};
 use crate::dialog::Dialog;
-use crate::markdown::{DisplayText, MarkdownParseTarget, MarkdownParser};
+use crate::external::{self, Placeholders};
+use crate::file_tree::FileTree;
+use crate::finder::find_files;
+use crate::markdown::{
+    DisplayText, Headings, LocalResources, MarkdownParseTarget, MarkdownParser, SearchMatches,
+    SearchOptions, SearchScope,
+};
 use crate::opener::Opener;
-use crate::persistent::DataDir;
+use crate::persistent::{
+    unix_time, Bookmark, Bookmarks, DataDir, RecentFiles, SearchQuery, Session, ViewState,
+    ViewStates,
+};
 use crate::renderer::{
-    App, AppControl, MenuItem, MenuItems, MessageFromRenderer, MessageToRenderer, Renderer,
-    UserEvent, Zoom,
+    App, AppControl, EventChannel, EventLoop, MenuItem, MenuItems, MessageFromRenderer,
+    MessageToRenderer, Renderer, Theme, UserEvent, Zoom,
 };
-use crate::watcher::{PathFilter, Watcher};
+use crate::search_index::SearchIndex;
+use crate::watcher::{PathFilter, ResourcePaths, Watcher};
 use anyhow::{Context as _, Result};
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::fs;
 use std::marker::PhantomData;
 use std::mem;
 use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::thread;
+use std::time::Duration;
 
 struct History {
     max_items: usize,
@@ -24,8 +38,6 @@ struct History {
 }
 
 impl History {
-    const DEFAULT_MAX_HISTORY_SIZE: usize = 20;
-
     fn new(max_items: usize) -> Self {
         Self { max_items, index: 0, items: VecDeque::new() }
     }
@@ -91,23 +103,217 @@ impl History {
         }
     }
 
-    fn iter(&self) -> impl Iterator<Item = &'_ Path> {
+    fn iter(&self) -> impl DoubleEndedIterator<Item = &'_ Path> {
         self.items.iter().map(PathBuf::as_path)
     }
+
+    // The current item is not removed since it is being previewed
+    fn remove(&mut self, path: &Path) {
+        let current = self.index;
+        let mut index = 0;
+        let mut removed_before_current = 0;
+        self.items.retain(|item| {
+            let keep = index == current || item != path;
+            if !keep && index < current {
+                removed_before_current += 1;
+            }
+            index += 1;
+            keep
+        });
+        self.index -= removed_before_current;
+    }
+
+    // Restore the history saved in the previous session. Files which no longer exist are dropped.
+    fn restore(&mut self, items: Vec<PathBuf>, index: usize) {
+        self.items.clear();
+        self.index = 0;
+        for (i, item) in items.into_iter().enumerate() {
+            if !item.exists() {
+                continue;
+            }
+            if i <= index {
+                self.index = self.items.len();
+            }
+            self.items.push_back(item);
+        }
+        self.set_max_items(self.max_items);
+        log::debug!("Restored history at {}: {:?}", self.index, self.items);
+    }
+
+    // Items are dropped from the end farther from the current item when the history is larger than the new size so
+    // that the current item is kept
+    fn set_max_items(&mut self, max_items: usize) {
+        self.max_items = max_items;
+        while self.items.len() > max_items.max(1) {
+            let after = self.items.len() - 1 - self.index;
+            if self.index >= after {
+                self.items.pop_front();
+                self.index -= 1;
+            } else {
+                self.items.pop_back();
+            }
+        }
+    }
+}
+
+// Local files referred from the current preview. They are watched while the preview is open and the renderer is
+// notified to reload them when they are modified. The config files are also watched as resources to reload them.
+// Their parent directories are watched instead of the files since editors saving files atomically replace them.
+struct Resources {
+    shared: ResourcePaths,
+    urls: HashMap<PathBuf, String>,
+    user_css: Option<PathBuf>,
+    config_files: Vec<PathBuf>,
+    extensions: FileExtensions,
+}
+
+impl Resources {
+    const USER_CSS_URL: &'static str = "/github-markdown.css";
+    const HLJS_CSS_URL: &'static str = "/hljs-theme.css";
+
+    fn new<W: Watcher>(shared: ResourcePaths, config: &Config, watcher: &mut W) -> Self {
+        let extensions = config.watch().file_extensions().clone();
+        let mut resources =
+            Self { shared, urls: HashMap::new(), user_css: None, config_files: vec![], extensions };
+        resources.update_config(config, watcher);
+        resources
+    }
+
+    fn share_paths(&self) {
+        let mut paths: HashSet<_> = self.urls.keys().cloned().collect();
+        paths.extend(self.config_files.iter().cloned());
+        self.shared.replace(paths);
+    }
+
+    fn update_config_files<W: Watcher>(&mut self, config: &Config, watcher: &mut W) {
+        // Only the parent directory is canonicalized since the file may be a symlink replaced on save
+        let files: Vec<_> = config
+            .config_files()
+            .filter_map(|p| Some(p.parent()?.canonicalize().ok()?.join(p.file_name()?)))
+            .collect();
+        let dirs = |files: &[PathBuf]| -> HashSet<PathBuf> {
+            files.iter().filter_map(|p| p.parent()).map(Path::to_path_buf).collect()
+        };
+        let (prev_dirs, next_dirs) = (dirs(&self.config_files), dirs(&files));
+        for dir in prev_dirs.difference(&next_dirs) {
+            log::debug!("Unwatching directory of config file no longer used: {:?}", dir);
+            if let Err(err) = watcher.unwatch(dir) {
+                log::debug!("Could not unwatch directory of config file {:?}: {}", dir, err);
+            }
+        }
+        for dir in next_dirs.difference(&prev_dirs) {
+            log::debug!("Watching directory of config file: {:?}", dir);
+            if let Err(err) = watcher.watch_non_recursive(dir) {
+                log::error!("Could not watch directory of config file {:?}: {}", dir, err);
+            }
+        }
+        self.config_files = files;
+        self.share_paths();
+    }
+
+    fn update_user_css<W: Watcher>(&mut self, user_css: Option<PathBuf>, watcher: &mut W) {
+        if let Some(path) = self.user_css.as_ref().and_then(|p| p.canonicalize().ok()) {
+            if self.urls.remove(&path).is_some() {
+                log::debug!("Unwatching previous user CSS file: {:?}", path);
+                if let Err(err) = watcher.unwatch(&path) {
+                    log::debug!("Could not unwatch user CSS file {:?}: {}", path, err);
+                }
+            }
+        }
+        if let Some(path) = user_css.as_ref().and_then(|p| p.canonicalize().ok()) {
+            match watcher.watch(&path) {
+                Ok(()) => {
+                    self.urls.insert(path, Self::USER_CSS_URL.to_string());
+                }
+                Err(err) => log::error!("Could not watch user CSS file {:?}: {}", path, err),
+            }
+        }
+        self.user_css = user_css;
+        self.share_paths();
+    }
+
+    fn update_config<W: Watcher>(&mut self, config: &Config, watcher: &mut W) {
+        self.extensions = config.watch().file_extensions().clone();
+        self.update_config_files(config, watcher);
+        self.update_user_css(config.user_css_path().map(Path::to_path_buf), watcher);
+    }
+
+    fn is_config_file(&self, path: &Path) -> bool {
+        self.config_files.iter().any(|p| p == path)
+    }
+
+    fn update<W: Watcher>(&mut self, local: &LocalResources, watcher: &mut W) {
+        let mut urls = HashMap::new();
+        for url in local.iter() {
+            let Ok(path) = Path::new(url).canonicalize() else {
+                continue;
+            };
+            // Markdown files are handled as documents, not as resources
+            if path.is_file() && !self.extensions.matches(&path) {
+                urls.insert(path, url.to_string());
+            }
+        }
+        if let Some(path) = self.user_css.as_ref().and_then(|p| p.canonicalize().ok()) {
+            urls.insert(path, Self::USER_CSS_URL.to_string());
+        }
+
+        for path in self.urls.keys().filter(|p| !urls.contains_key(*p)) {
+            log::debug!("Unwatching resource no longer referred: {:?}", path);
+            if let Err(err) = watcher.unwatch(path) {
+                log::debug!("Could not unwatch resource {:?}: {}", path, err);
+            }
+        }
+        urls.retain(|path, _| {
+            if self.urls.contains_key(path) {
+                return true;
+            }
+            log::debug!("Watching new resource: {:?}", path);
+            match watcher.watch(path) {
+                Ok(()) => true,
+                Err(err) => {
+                    log::error!("Could not watch resource {:?}: {}", path, err);
+                    false
+                }
+            }
+        });
+
+        self.urls = urls;
+        self.share_paths();
+    }
+
+    fn urls<'a>(&'a self, paths: &[PathBuf]) -> Vec<&'a str> {
+        paths.iter().filter_map(|p| self.urls.get(p)).map(String::as_str).collect()
+    }
+}
+
+// The last search in the preview. Matches are reused while only the current index is changed
+struct LastSearch {
+    query: String,
+    matcher: SearchMatcher,
+    options: SearchOptions,
+    matches: Option<SearchMatches>,
 }
 
 struct PreviewContent {
     home_dir: Option<PathBuf>,
     content: MarkdownParseTarget,
     text: DisplayText,
+    headings: Headings,
+    resources: Resources,
+    view_states: ViewStates,
+    last_search: Option<LastSearch>,
 }
 
-impl Default for PreviewContent {
-    fn default() -> Self {
+impl PreviewContent {
+    fn new(resources: Resources, view_states: ViewStates) -> Self {
         Self {
             home_dir: dirs::home_dir(),
             content: MarkdownParseTarget::default(),
             text: DisplayText::default(),
+            headings: Headings::default(),
+            resources,
+            view_states,
+            last_search: None,
         }
     }
 }
@@ -122,7 +328,13 @@ impl PreviewContent {
         format!("Shiba: {}", path.display())
     }
 
-    pub fn show<R: Renderer>(&mut self, path: &Path, renderer: &R, reload: bool) -> Result<bool> {
+    pub fn show<R: Renderer, W: Watcher>(
+        &mut self,
+        path: &Path,
+        renderer: &R,
+        watcher: &mut W,
+        reload: bool,
+    ) -> Result<bool> {
         log::debug!("Opening markdown preview for {:?}", path);
         let source = match fs::read_to_string(path) {
             Ok(source) => source,
@@ -135,49 +347,138 @@ impl PreviewContent {
             }
         };
 
+        // The scroll position is restored before sending the new content so that the renderer can apply it on rendering
+        if reload {
+            if let Some(state) = self.view_states.get(path) {
+                if state.is_applicable(source.len()) {
+                    log::debug!("Restoring view state of {:?}: {:?}", path, state);
+                    renderer.send_message(MessageToRenderer::RestoreScroll {
+                        scroll: state.scroll,
+                        heading: state.heading.as_deref(),
+                        offset: state.offset,
+                    })?;
+                }
+            }
+        }
+
         let new_content = MarkdownParseTarget::new(source, path.parent());
         let prev_content = std::mem::replace(&mut self.content, new_content);
         let offset = if reload { None } else { prev_content.modified_offset(&self.content) };
         log::debug!("Last modified offset: {:?}", offset);
 
-        self.text = renderer.send_message_raw(MarkdownParser::new(&self.content, offset, ()))?;
+        let local;
+        ((self.text, self.headings), local) =
+            renderer.send_message_raw(MarkdownParser::new(&self.content, offset))?;
+        self.last_search = None;
+        self.resources.update(&local, watcher);
 
         if reload {
             renderer.set_title(&self.title(path));
+            renderer.send_message(MessageToRenderer::CurrentFile { path })?;
         }
 
         Ok(true)
     }
 
-    pub fn rerender<R: Renderer>(&mut self, renderer: &R) -> Result<()> {
-        renderer.send_message_raw(MarkdownParser::new(&self.content, None, ()))
+    // The position of the previous file is reported after the next file was opened. Its length is read from the file
+    // since the content was already replaced.
+    pub fn update_view_state(
+        &mut self,
+        path: PathBuf,
+        scroll: f64,
+        heading: Option<String>,
+        offset: f64,
+        is_current: bool,
+    ) {
+        let len = if is_current {
+            self.content.source().len()
+        } else {
+            match fs::metadata(&path) {
+                Ok(metadata) => metadata.len() as usize,
+                Err(err) => {
+                    log::debug!("Could not read length of {:?} for view state: {}", path, err);
+                    return;
+                }
+            }
+        };
+        self.view_states.update(ViewState { path, scroll, len, heading, offset });
+    }
+
+    // 1-based line number at the source offset. The first line is returned when the offset is not given.
+    pub fn line_at(&self, offset: Option<usize>) -> usize {
+        let Some(offset) = offset else {
+            return 1;
+        };
+        let source = self.content.source().as_bytes();
+        memchr::memchr_iter(b'\n', &source[..offset.min(source.len())]).count() + 1
     }
 
+    pub fn reload_resources<R: Renderer>(&self, renderer: &R, paths: &[PathBuf]) -> Result<()> {
+        let urls = self.resources.urls(paths);
+        if urls.is_empty() {
+            return Ok(());
+        }
+        log::debug!("Reloading resources: {:?}", urls);
+        renderer.send_message(MessageToRenderer::ReloadResources { urls })
+    }
+
+    // Matches are sent as positions in text spans of the render tree. The document is not re-rendered on searching.
+    // When only the index is changed, the previous matches are reused and search results are not sent again.
     pub fn search<R: Renderer>(
         &mut self,
         renderer: &R,
         query: &str,
         index: Option<usize>,
         matcher: SearchMatcher,
+        options: SearchOptions,
     ) -> Result<()> {
-        log::debug!("Re-rendering content with query {:?} and current index {:?}", query, index);
-        if query.is_empty() {
-            return self.rerender(renderer);
+        const MAX_SEARCH_RESULTS: usize = 500;
+
+        log::debug!("Searching content with query {:?} and current index {:?}", query, index);
+        let source = self.content.source();
+        let is_same = self.last_search.as_ref().is_some_and(|last| {
+            last.query == query && last.matcher == matcher && last.options == options
+        });
+        if !is_same {
+            let matches = self.text_search(query, matcher, options);
+            let results = matches
+                .iter()
+                .flat_map(|m| m.iter().enumerate())
+                .take(MAX_SEARCH_RESULTS)
+                .map(|(i, range)| self.text.search_result(source, &self.headings, i, range))
+                .collect();
+            renderer.send_message(MessageToRenderer::SearchResults { query, results })?;
+            let query = query.to_string();
+            self.last_search = Some(LastSearch { query, matcher, options, matches });
         }
 
-        let matches = match self.text.search(query, matcher) {
-            Ok(m) => m,
+        let matches = self.last_search.as_ref().and_then(|last| last.matches.as_ref());
+        let (count, matches) = match matches {
+            Some(m) => (m.len(), self.text.span_matches(source, m)),
+            None => (0, vec![]),
+        };
+        renderer.send_message(MessageToRenderer::SearchMatches { query, index, count, matches })
+    }
+
+    fn text_search(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        options: SearchOptions,
+    ) -> Option<SearchMatches> {
+        if query.is_empty() {
+            return None;
+        }
+        match self.text.search_with_options(query, matcher, options) {
+            Ok(m) => {
+                log::debug!("Search hit {} matches", m.len());
+                Some(m)
+            }
             Err(err) => {
                 log::debug!("Could not build {:?} matcher for query {:?}: {}", matcher, query, err);
-                return self.rerender(renderer);
+                None
             }
-        };
-        log::debug!("Search hit {} matches", matches.len());
-
-        let Some(tokenizer) = matches.tokenizer(index) else {
-            return self.rerender(renderer);
-        };
-        renderer.send_message_raw(MarkdownParser::new(&self.content, None, tokenizer))
+        }
     }
 }
 
@@ -189,7 +490,20 @@ pub struct Shiba<R: Renderer, O: Opener, W: Watcher, D: Dialog> {
     watcher: W,
     config: Config,
     preview: PreviewContent,
+    file_tree: FileTree,
+    search_index: SearchIndex,
+    search_history: Vec<SearchQuery>,
+    recent_files: RecentFiles,
+    bookmarks: Bookmarks,
     data_dir: DataDir,
+    session_file: Option<PathBuf>,
+    // Error on loading the project-local config file at startup. It is notified when the window is ready
+    config_error: Option<String>,
+    // Recent files and bookmarks listed in the native menu
+    menu_items: Option<(Vec<PathBuf>, Vec<Bookmark>)>,
+    // Quitting was requested and the application is waiting for the renderer to report the scroll position
+    quitting: bool,
+    channel: <R::EventLoop as EventLoop>::Channel,
     _dialog: PhantomData<D>,
 }
 
@@ -200,23 +514,45 @@ where
     W: Watcher,
     D: Dialog,
 {
+    const MAX_SEARCH_HISTORY: usize = 100;
+    const MAX_MENU_RECENT_FILES: usize = 15;
+    const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
     pub fn new(options: Options, event_loop: &R::EventLoop) -> Result<Self> {
         let config = if options.gen_config_file {
-            if let Some(dir) = &options.config_dir {
+            if let Some(file) = &options.config_file {
+                Config::generate_default_config_file(file.clone())?
+            } else if let Some(dir) = &options.config_dir {
                 Config::generate_default_config_at(dir)?
             } else {
                 Config::generate_default_config()?
             }
+        } else if let Some(file) = &options.config_file {
+            Config::load_file(file)?
         } else if let Some(dir) = &options.config_dir {
             Config::load_dir(dir)?
         } else {
             Config::load()?
         };
-        let config = config.merge_options(&options);
+        // The project-local config file is not trusted as much as the user config. A broken one falls back to the
+        // user config instead of aborting the application
+        let mut config_error = None;
+        let config = match options.project_dir().and_then(Config::find_project_config_file) {
+            Some(file) => match config.with_project_config_file(Some(&file)) {
+                Ok(config) => config,
+                Err(err) => {
+                    log::warn!("Could not load project-local config file {:?}: {:?}", file, err);
+                    config_error = Some(format!("{:#}", err));
+                    config
+                }
+            },
+            None => config,
+        };
+        let config = config.merge_options(&options)?;
 
         log::debug!("Application config: {:?}, options: {:?}", config, options);
 
-        let data_dir = if let Some(dir) = &options.data_dir {
+        let mut data_dir = if let Some(dir) = &options.data_dir {
             DataDir::custom_dir(dir)
         } else {
             DataDir::new()
@@ -224,54 +560,205 @@ where
         let window_state = if config.window().restore { data_dir.load() } else { None };
         let renderer = R::new(&options, &config, event_loop, window_state)?;
 
-        let filter = PathFilter::new(config.watch());
+        let shared_resources = ResourcePaths::default();
+        let filter = PathFilter::new(config.watch(), shared_resources.clone());
         let mut watcher = W::new(event_loop, filter)?;
+        let mut file_tree = FileTree::new(config.watch());
         for path in &options.watch_paths {
             log::debug!("Watching initial path: {:?}", path);
             watcher.watch(path)?;
+            file_tree.add_root(path.clone());
         }
+        let resources = Resources::new(shared_resources, &config, &mut watcher);
 
-        let mut history = History::new(History::DEFAULT_MAX_HISTORY_SIZE);
-        for path in data_dir.load_recent_files(config.max_recent_files()) {
-            history.push(path);
+        let mut history = History::new(config.history_size());
+        let recent_files = data_dir.load_recent_files(config.max_recent_files());
+        let mut session_file = None;
+        let restore_session = config.window().restore_session
+            && options.init_file.is_none()
+            && options.watch_paths.is_empty();
+        if let Some(session) = restore_session.then(|| data_dir.load::<Session>()).flatten() {
+            log::debug!("Restoring the previous session: {:?}", session);
+            for dir in session.watch_dirs {
+                if !dir.is_dir() {
+                    continue;
+                }
+                if let Err(err) = watcher.watch(&dir) {
+                    log::error!("Could not watch directory {:?} in previous session: {}", dir, err);
+                    continue;
+                }
+                file_tree.add_root(dir);
+            }
+            history.restore(session.history, session.index);
+            session_file = history.current().cloned();
+        } else {
+            for file in recent_files.iter() {
+                history.push(file.path.clone());
+            }
         }
+        let search_history = data_dir.load_search_history(Self::MAX_SEARCH_HISTORY);
+        let view_states = data_dir.load().unwrap_or_default();
+        let bookmarks = data_dir.load_bookmarks();
 
-        Ok(Self {
+        let mut app = Self {
             options,
             renderer,
             opener: O::default(),
             history,
             watcher,
             config,
-            preview: PreviewContent::default(),
+            preview: PreviewContent::new(resources, view_states),
+            file_tree,
+            search_index: SearchIndex::new(event_loop.create_channel()),
+            search_history,
+            recent_files,
+            bookmarks,
             data_dir,
+            session_file,
+            config_error,
+            menu_items: None,
+            quitting: false,
+            channel: event_loop.create_channel(),
             _dialog: PhantomData,
-        })
+        };
+        app.update_menu();
+        Ok(app)
     }
 
     fn preview_new(&mut self, path: PathBuf) -> Result<()> {
         self.watcher.watch(&path)?; // Watch path at first since the file may not exist yet
         let is_current = self.history.is_current(&path);
-        if self.preview.show(&path, &self.renderer, !is_current)? {
-            self.renderer.send_message(MessageToRenderer::NewFile { path: &path })?;
-            self.history.push(path);
+        if !is_current {
+            self.switch_project_config(&path)?;
+        }
+        if self.preview.show(&path, &self.renderer, &mut self.watcher, !is_current)? {
+            self.push_history(path)?;
         }
         Ok(())
     }
 
+    fn push_history(&mut self, path: PathBuf) -> Result<()> {
+        if !self.history.is_current(&path) {
+            self.recent_files.visit(&path, unix_time());
+        }
+        self.history.push(path);
+        self.update_menu();
+        self.send_recent_files()
+    }
+
+    fn send_recent_files(&self) -> Result<()> {
+        let files = self.recent_files.ranked(unix_time());
+        self.renderer.send_message(MessageToRenderer::RecentFiles { files })
+    }
+
+    // The native menu is built again since 'Open Recent' submenu lists the recent files and 'Bookmarks' menu lists the
+    // bookmarks. Building the menu is not cheap so it is skipped when they are not changed.
+    fn update_menu(&mut self) {
+        let recent_files: Vec<_> = self
+            .recent_files
+            .ranked(unix_time())
+            .into_iter()
+            .take(Self::MAX_MENU_RECENT_FILES)
+            .map(|file| file.path.clone())
+            .collect();
+        let items = (recent_files, self.bookmarks.as_slice().to_vec());
+        if self.menu_items.as_ref() == Some(&items) {
+            log::debug!("Skipped updating menu since recent files and bookmarks are not changed");
+            return;
+        }
+        let recent_files: Vec<_> = items.0.iter().map(PathBuf::as_path).collect();
+        self.renderer.update_menu(&self.config, &recent_files, &items.1);
+        self.menu_items = Some(items);
+    }
+
+    fn remove_recent_file(&mut self, path: &Path) -> Result<()> {
+        log::debug!("Removing recent file {:?}", path);
+        self.history.remove(path);
+        self.recent_files.remove(path);
+        self.update_menu();
+        self.send_recent_files()
+    }
+
+    // Pinned files are kept. The back/forward history is not affected
+    fn clear_recent_files(&mut self) -> Result<()> {
+        log::debug!("Clearing recent files");
+        self.recent_files.clear();
+        self.update_menu();
+        self.send_recent_files()
+    }
+
+    fn toggle_pin_recent_file(&mut self, path: &Path) -> Result<()> {
+        let Some(pinned) = self.recent_files.toggle_pin(path) else {
+            log::debug!("File {:?} is not pinned since it is not in recent files", path);
+            return Ok(());
+        };
+        log::debug!("Toggled pin of recent file {:?} (pinned={})", path, pinned);
+        self.update_menu();
+        self.send_recent_files()
+    }
+
+    // Bookmarks are saved on every change so that they are not lost even if the application crashes
+    fn save_bookmarks(&mut self) -> Result<()> {
+        self.data_dir.save_bookmarks(&mut self.bookmarks)?;
+        self.update_menu();
+        let bookmarks = self.bookmarks.as_slice();
+        self.renderer.send_message(MessageToRenderer::Bookmarks { bookmarks })
+    }
+
+    fn toggle_bookmark(&mut self, heading: Option<String>, label: Option<String>) -> Result<()> {
+        let Some(path) = self.history.current() else {
+            log::debug!("Bookmark was not toggled since no file is previewed");
+            return Ok(());
+        };
+        let bookmark = Bookmark { path: path.clone(), heading, label };
+        log::debug!("Toggling bookmark {:?}", bookmark);
+        let added = self.bookmarks.toggle(bookmark);
+        self.save_bookmarks()?;
+        self.renderer.send_message(MessageToRenderer::Bookmarked { added })
+    }
+
+    // Bookmarks are identified by their paths and headings since the list in the renderer or the menu may be stale
+    fn open_bookmark(&mut self, path: &Path, heading: Option<&str>) -> Result<()> {
+        let Some(bookmark) = self.bookmarks.find(path, heading) else {
+            anyhow::bail!("Bookmark of {:?} (heading={:?}) does not exist", path, heading);
+        };
+        log::debug!("Opening bookmark {:?}", bookmark);
+        if !bookmark.path.is_file() {
+            log::debug!("Bookmarked file {:?} does not exist", bookmark.path);
+            return self.renderer.send_message(MessageToRenderer::BookmarkNotFound { bookmark });
+        }
+        let Bookmark { path, heading, .. } = bookmark.clone();
+        // The heading is sent before the content so that the renderer can scroll to it after rendering the content
+        if let Some(heading) = &heading {
+            self.renderer.send_message(MessageToRenderer::ScrollToHeading { heading })?;
+        }
+        self.preview_new(path)
+    }
+
+    fn remove_bookmark(&mut self, path: &Path, heading: Option<&str>) -> Result<()> {
+        let Some(bookmark) = self.bookmarks.remove(path, heading) else {
+            log::debug!("Bookmark of {:?} (heading={:?}) was already removed", path, heading);
+            return Ok(());
+        };
+        log::debug!("Removed bookmark {:?}", bookmark);
+        self.save_bookmarks()
+    }
+
     fn forward(&mut self) -> Result<()> {
-        if let Some(path) = self.history.next() {
+        if let Some(path) = self.history.next().cloned() {
             log::debug!("Forward to next preview page: {:?}", path);
-            self.preview.show(path, &self.renderer, true)?;
+            self.switch_project_config(&path)?;
+            self.preview.show(&path, &self.renderer, &mut self.watcher, true)?;
             self.history.forward();
         }
         Ok(())
     }
 
     fn back(&mut self) -> Result<()> {
-        if let Some(path) = self.history.prev() {
+        if let Some(path) = self.history.prev().cloned() {
             log::debug!("Back to previous preview page: {:?}", path);
-            self.preview.show(path, &self.renderer, true)?;
+            self.switch_project_config(&path)?;
+            self.preview.show(&path, &self.renderer, &mut self.watcher, true)?;
             self.history.back();
         }
         Ok(())
@@ -286,7 +773,7 @@ where
         }
         if let Some(path) = self.history.current() {
             log::debug!("Reload current preview page: {:?}", path);
-            self.preview.show(path, &self.renderer, true)?;
+            self.preview.show(path, &self.renderer, &mut self.watcher, true)?;
             self.renderer.send_message(MessageToRenderer::Reload)?;
         }
         Ok(())
@@ -320,11 +807,73 @@ where
         if let Some(dir) = dir {
             log::debug!("Watching directory chosen by dialog: {:?}", dir);
             self.watcher.watch(&dir)?;
+            if self.file_tree.add_root(dir) {
+                self.send_file_tree()?;
+            }
         }
 
         Ok(())
     }
 
+    fn send_file_tree(&self) -> Result<()> {
+        self.renderer.send_message(MessageToRenderer::FileTree { roots: self.file_tree.entries() })
+    }
+
+    fn update_file_tree(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let mut changed = false;
+        for path in paths {
+            self.search_index.invalidate(path);
+            changed |= self.file_tree.update(path);
+        }
+        if changed {
+            log::debug!("File tree was updated by changes in {:?}", paths);
+            self.send_file_tree()?;
+        }
+        Ok(())
+    }
+
+    fn find_files(&self, query: &str) -> Result<()> {
+        const MAX_FOUND_FILES: usize = 100;
+
+        let mut candidates: HashMap<&Path, Option<usize>> =
+            self.file_tree.files().map(|path| (path, None)).collect();
+        // Recent files are candidates even if they are not in watched directories
+        for (rank, file) in self.recent_files.ranked(unix_time()).into_iter().enumerate() {
+            candidates
+                .entry(&file.path)
+                .and_modify(|r| *r = r.or(Some(rank)))
+                .or_insert(Some(rank));
+        }
+
+        let files = find_files(query, candidates, MAX_FOUND_FILES);
+        log::debug!("Found {} files for query {:?}", files.len(), query);
+        self.renderer.send_message(MessageToRenderer::FoundFiles { query, files })
+    }
+
+    fn search_workspace(&self, query: &str, matcher: SearchMatcher, scope: SearchScope) {
+        // The result is notified with `UserEvent::WorkspaceSearched`
+        let options = self.search_options(scope);
+        self.search_index.search(query, matcher, options, self.file_tree.files());
+    }
+
+    fn open_search_match(
+        &mut self,
+        path: PathBuf,
+        query: &str,
+        index: usize,
+        matcher: SearchMatcher,
+        scope: SearchScope,
+    ) -> Result<()> {
+        log::debug!("Opening search match #{} of {:?} in {:?}", index, query, path);
+        self.preview_new(path)?;
+        let options = self.search_options(scope);
+        self.preview.search(&self.renderer, query, Some(index), matcher, options)
+    }
+
+    fn search_options(&self, scope: SearchScope) -> SearchOptions {
+        SearchOptions { ignore_accents: self.config.search().ignore_accents(), scope }
+    }
+
     fn zoom(&mut self, zoom: Zoom) -> Result<()> {
         let level = match zoom {
             Zoom::In => self.renderer.zoom_level().zoom_in(),
@@ -343,6 +892,118 @@ where
         Ok(())
     }
 
+    // The latest query is put at the end. The same query is moved to the end instead of being duplicated.
+    fn add_search_history(&mut self, item: SearchQuery) {
+        if item.query.is_empty() {
+            return;
+        }
+        log::debug!("Add search query to history: {:?}", item);
+        self.search_history.retain(|q| q != &item);
+        self.search_history.push(item);
+        let len = self.search_history.len();
+        if len > Self::MAX_SEARCH_HISTORY {
+            self.search_history.drain(0..len - Self::MAX_SEARCH_HISTORY);
+        }
+    }
+
+    fn send_config(&self) -> Result<()> {
+        self.renderer.send_message(MessageToRenderer::Config {
+            keymaps: self.config.keymaps(),
+            commands: COMMANDS,
+            external_commands: self.config.commands(),
+            search: self.config.search(),
+            theme: self.renderer.theme(),
+            recent: self.recent_files.ranked(unix_time()),
+            search_history: &self.search_history,
+        })
+    }
+
+    // Settings of the file watcher are not applied since they are owned by the watcher thread. They are applied on
+    // the next launch.
+    fn apply_config(&mut self, config: Result<Config>) -> Result<bool> {
+        let config = match config {
+            Ok(config) => config, // Overrides by options are kept in the config
+            Err(err) => {
+                log::error!(
+                    "Could not load config file. Keep using the previous config: {:?}",
+                    err
+                );
+                let message = format!("{:#}", err);
+                self.renderer.send_message(MessageToRenderer::ConfigError { message: &message })?;
+                return Ok(false);
+            }
+        };
+        log::debug!("Applying new application config: {:?}", config);
+        self.config = config;
+
+        self.preview.resources.update_config(&self.config, &mut self.watcher);
+        self.renderer.reload_assets(&self.config);
+        self.history.set_max_items(self.config.history_size());
+        self.menu_items = None; // Accelerators in the menu may be changed
+        self.update_menu();
+        self.send_config()?;
+        let urls = vec![Resources::USER_CSS_URL, Resources::HLJS_CSS_URL];
+        self.renderer.send_message(MessageToRenderer::ReloadResources { urls })?;
+        Ok(true)
+    }
+
+    fn reload_config(&mut self) -> Result<()> {
+        let config = self.config.reload();
+        // The document needs to be rendered again only when the config for parsing it was changed. Stylesheets are
+        // reloaded without rendering it.
+        let rerender = matches!(&config, Ok(c) if c.watch() != self.config.watch());
+        if self.apply_config(config)? && rerender {
+            self.reload()?;
+        }
+        Ok(())
+    }
+
+    // The effective config switches when the previewed file is in another project
+    fn switch_project_config(&mut self, path: &Path) -> Result<()> {
+        let project = path.parent().and_then(Config::find_project_config_file);
+        if project.as_deref() == self.config.project_config_file() {
+            return Ok(());
+        }
+        log::debug!("Switching project-local config file to {:?}", project);
+        let config = self.config.with_project_config_file(project.as_deref());
+        self.apply_config(config)?;
+        Ok(())
+    }
+
+    fn toggle_theme(&mut self) -> Result<()> {
+        let theme = match self.renderer.theme() {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        };
+        log::debug!("Toggle window theme to {:?}", theme);
+        self.renderer.set_theme(theme);
+        self.renderer.send_message(MessageToRenderer::Theme { theme })
+    }
+
+    // User themes are listed after the bundled themes
+    fn send_highlight_themes(&self) -> Result<()> {
+        // User themes overriding bundled ones are listed once
+        let mut themes = bundled_hljs_themes();
+        for name in self.config.user_themes().names() {
+            if !themes.contains(&name) {
+                themes.push(name);
+            }
+        }
+        let highlight = self.config.preview().highlight();
+        let current = match self.renderer.theme() {
+            Theme::Light => &highlight.light,
+            Theme::Dark => &highlight.dark,
+        };
+        self.renderer.send_message(MessageToRenderer::HighlightThemes { themes, current })
+    }
+
+    // The highlight theme is previewed without rendering the document again. It is used until the config is reloaded.
+    fn preview_highlight_theme(&self, name: Option<&str>) -> Result<()> {
+        self.renderer.set_highlight_theme(&self.config, name);
+        let urls = vec![Resources::HLJS_CSS_URL];
+        self.renderer.send_message(MessageToRenderer::ReloadResources { urls })
+    }
+
     fn toggle_always_on_top(&mut self) -> Result<()> {
         let pinned = !self.renderer.always_on_top();
         log::debug!("Toggle always-on-top (pinned={})", pinned);
@@ -350,6 +1011,86 @@ where
         self.renderer.send_message(MessageToRenderer::AlwaysOnTop { pinned })
     }
 
+    // The renderer reports the scroll position of the current file before exiting. The application exits without the
+    // report when the renderer does not reply in time
+    fn quit(&mut self) -> Result<AppControl> {
+        if self.quitting {
+            return Ok(AppControl::Exit);
+        }
+        if let Err(err) = self.renderer.send_message(MessageToRenderer::Quit) {
+            log::error!("Could not ask the renderer to quit: {}", err);
+            return Ok(AppControl::Exit);
+        }
+        self.quitting = true;
+        let channel = self.channel.clone();
+        let timeout = Self::QUIT_TIMEOUT;
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            channel.send_event(UserEvent::Quit);
+        });
+        Ok(AppControl::Continue)
+    }
+
+    fn run_external_command(
+        &self,
+        index: usize,
+        selection: &str,
+        offset: Option<usize>,
+    ) -> Result<()> {
+        let Some(command) = self.config.commands().get(index) else {
+            anyhow::bail!("External command at index {} does not exist", index);
+        };
+        let Some(path) = self.history.current() else {
+            log::debug!(
+                "External command {:?} was not run since no file is previewed",
+                command.name
+            );
+            return Ok(());
+        };
+        let line = self.preview.line_at(offset);
+        let placeholders = Placeholders { path, line, selection };
+        let channel = self.channel.clone();
+        if let Err(message) =
+            external::spawn(&command.name, &command.run, &placeholders, command.reload, channel)
+        {
+            let message = format!("{}: {}", command.name, message);
+            self.renderer
+                .send_message(MessageToRenderer::ExternalCommandError { message: &message })?;
+        }
+        Ok(())
+    }
+
+    fn run_command(&mut self, command: Command) -> Result<AppControl> {
+        log::debug!("Running command: {:?}", command);
+        if command.handler() == Handler::Renderer {
+            self.renderer.send_message(MessageToRenderer::Command { command })?;
+            return Ok(AppControl::Continue);
+        }
+        match command {
+            Command::Quit => return self.quit(),
+            Command::Forward => self.forward()?,
+            Command::Back => self.back()?,
+            Command::Reload => self.reload()?,
+            Command::OpenFile => self.open_file()?,
+            Command::OpenDir => self.open_dir()?,
+            Command::ClearRecent => self.clear_recent_files()?,
+            Command::PinRecent => {
+                if let Some(path) = self.history.current().cloned() {
+                    self.toggle_pin_recent_file(&path)?;
+                }
+            }
+            Command::Print => self.renderer.print()?,
+            Command::ZoomIn => self.zoom(Zoom::In)?,
+            Command::ZoomOut => self.zoom(Zoom::Out)?,
+            Command::ToggleTheme => self.toggle_theme()?,
+            Command::HighlightThemes => self.send_highlight_themes()?,
+            Command::ToggleAlwaysOnTop => self.toggle_always_on_top()?,
+            Command::OpenRepo => self.opener.open("https://github.com/rhysd/Shiba")?,
+            _ => unreachable!("{:?} is not handled by the main", command),
+        }
+        Ok(AppControl::Continue)
+    }
+
     fn handle_ipc_message(&mut self, message: MessageFromRenderer) -> Result<AppControl> {
         match message {
             MessageFromRenderer::Init => {
@@ -357,41 +1098,83 @@ where
                     self.renderer.send_message(MessageToRenderer::Debug)?;
                 }
 
-                self.renderer.send_message(MessageToRenderer::Config {
-                    keymaps: self.config.keymaps(),
-                    search: self.config.search(),
-                    theme: self.renderer.theme(),
-                    recent: self.history.iter().collect(),
-                })?;
+                self.send_config()?;
+                if let Some(message) = &self.config_error.take() {
+                    self.renderer.send_message(MessageToRenderer::ConfigError { message })?;
+                }
+                let bookmarks = self.bookmarks.as_slice();
+                self.renderer.send_message(MessageToRenderer::Bookmarks { bookmarks })?;
+
+                if !self.file_tree.is_empty() {
+                    self.send_file_tree()?;
+                }
 
                 // Open window when the content is ready. Otherwise a white window flashes when dark theme.
                 self.renderer.show();
 
                 if let Some(path) = mem::take(&mut self.options.init_file) {
                     self.preview_new(path)?;
+                } else if let Some(path) = self.session_file.take() {
+                    // The file is already in the history restored from the previous session
+                    log::debug!("Previewing the file in the previous session: {:?}", path);
+                    self.watcher.watch(&path)?;
+                    self.switch_project_config(&path)?;
+                    self.preview.show(&path, &self.renderer, &mut self.watcher, true)?;
                 } else {
                     self.renderer.send_message(MessageToRenderer::Welcome)?;
                 }
             }
-            MessageFromRenderer::Search { query, index, matcher } => {
-                self.preview.search(&self.renderer, &query, index, matcher)?
+            MessageFromRenderer::Search { query, index, matcher, scope } => {
+                let options = self.search_options(scope);
+                self.preview.search(&self.renderer, &query, index, matcher, options)?
             }
-            MessageFromRenderer::Forward => self.forward()?,
-            MessageFromRenderer::Back => self.back()?,
-            MessageFromRenderer::Reload => self.reload()?,
             MessageFromRenderer::FileDialog => self.open_file()?,
-            MessageFromRenderer::DirDialog => self.open_dir()?,
             MessageFromRenderer::OpenFile { path } => {
                 let path = PathBuf::from(path);
-                if self.preview.show(&path, &self.renderer, true)? {
-                    self.history.push(path);
+                self.switch_project_config(&path)?;
+                if self.preview.show(&path, &self.renderer, &mut self.watcher, true)? {
+                    self.push_history(path)?;
                 }
             }
+            MessageFromRenderer::FindFiles { query } => self.find_files(&query)?,
+            MessageFromRenderer::SearchWorkspace { query, matcher, scope } => {
+                self.search_workspace(&query, matcher, scope)
+            }
+            MessageFromRenderer::OpenSearchMatch { path, query, index, matcher, scope } => {
+                self.open_search_match(PathBuf::from(path), &query, index, matcher, scope)?
+            }
+            MessageFromRenderer::AddSearchHistory { query, matcher } => {
+                self.add_search_history(SearchQuery { query, matcher })
+            }
             MessageFromRenderer::Zoom { zoom } => self.zoom(zoom)?,
-            MessageFromRenderer::Quit => return Ok(AppControl::Exit),
+            MessageFromRenderer::PreviewHighlightTheme { name } => {
+                self.preview_highlight_theme(name.as_deref())?
+            }
+            MessageFromRenderer::ScrollPosition { path, scroll, heading, offset } => {
+                let path = PathBuf::from(path);
+                let is_current = self.history.is_current(&path);
+                self.preview.update_view_state(path, scroll, heading, offset, is_current);
+            }
+            MessageFromRenderer::ToggleBookmark { heading, label } => {
+                self.toggle_bookmark(heading, label)?
+            }
+            MessageFromRenderer::TogglePinRecentFile { path } => {
+                self.toggle_pin_recent_file(Path::new(&path))?
+            }
+            MessageFromRenderer::OpenBookmark { path, heading } => {
+                self.open_bookmark(Path::new(&path), heading.as_deref())?
+            }
+            MessageFromRenderer::RemoveBookmark { path, heading } => {
+                self.remove_bookmark(Path::new(&path), heading.as_deref())?
+            }
+            MessageFromRenderer::Command { command } => return self.run_command(command),
+            MessageFromRenderer::RunExternalCommand { index, selection, offset } => {
+                self.run_external_command(index, &selection, offset)?
+            }
             MessageFromRenderer::Error { message } => {
                 anyhow::bail!("Error reported from renderer: {}", message)
             }
+            MessageFromRenderer::Quit => return Ok(AppControl::Exit),
         }
         Ok(AppControl::Continue)
     }
@@ -417,9 +1200,10 @@ where
             }
             UserEvent::WatchedFilesChanged(mut paths) => {
                 log::debug!("Files changed: {:?}", paths);
+                self.update_file_tree(&paths)?;
                 if let Some(current) = self.history.current() {
                     if paths.contains(current) {
-                        self.preview.show(current, &self.renderer, false)?;
+                        self.preview.show(current, &self.renderer, &mut self.watcher, false)?;
                         return Ok(AppControl::Continue);
                     }
                 }
@@ -428,12 +1212,22 @@ where
                     if !path.is_absolute() {
                         path = path.canonicalize()?;
                     }
-                    if self.preview.show(&path, &self.renderer, true)? {
-                        self.renderer.send_message(MessageToRenderer::NewFile { path: &path })?;
-                        self.history.push(path);
+                    self.switch_project_config(&path)?;
+                    if self.preview.show(&path, &self.renderer, &mut self.watcher, true)? {
+                        self.push_history(path)?;
                     }
                 }
             }
+            UserEvent::WatchedResourcesChanged(mut paths) => {
+                log::debug!("Resources changed: {:?}", paths);
+                let len = paths.len();
+                paths.retain(|p| !self.preview.resources.is_config_file(p));
+                if paths.len() < len {
+                    self.reload_config()?;
+                }
+                self.preview.reload_resources(&self.renderer, &paths)?;
+            }
+            UserEvent::WatchedTreeChanged(paths) => self.update_file_tree(&paths)?,
             UserEvent::OpenLocalPath(mut path) => {
                 if path.is_relative() {
                     if let Some(current_file) = self.history.current() {
@@ -456,45 +1250,173 @@ where
                 log::debug!("Opening external link item clicked in WebView: {:?}", link);
                 self.opener.open(&link).with_context(|| format!("opening link {:?}", &link))?;
             }
+            UserEvent::ThemeChanged(theme) => {
+                // Only the highlight.js stylesheet is swapped. The document is not rendered again.
+                self.renderer.on_theme_changed(theme);
+                self.renderer.send_message(MessageToRenderer::Theme { theme })?;
+            }
+            UserEvent::ExternalCommandExited { name, path, reload, error } => {
+                if let Some(message) = error {
+                    log::error!("External command {:?} failed: {}", name, message);
+                    self.renderer.send_message(MessageToRenderer::ExternalCommandError {
+                        message: &message,
+                    })?;
+                } else if reload && self.history.is_current(&path) {
+                    self.reload()?;
+                } else if reload {
+                    log::debug!("Not reloading {:?} since another file is previewed", path);
+                }
+            }
+            UserEvent::WorkspaceSearched { query, matches } => {
+                let message = MessageToRenderer::WorkspaceMatches { query: &query, matches };
+                self.renderer.send_message(message)?;
+            }
+            UserEvent::Quit => return self.quit(),
             UserEvent::Error(err) => return Err(err),
         }
         Ok(AppControl::Continue)
     }
 
     fn handle_menu_event(&mut self, id: <R::Menu as MenuItems>::ItemId) -> Result<AppControl> {
-        let kind = self.renderer.menu().item_from_id(id)?;
-        log::debug!("Menu item was clicked: {:?}", kind);
-        match kind {
-            MenuItem::Quit => return Ok(AppControl::Exit),
-            MenuItem::Forward => self.forward()?,
-            MenuItem::Back => self.back()?,
-            MenuItem::Reload => self.reload()?,
-            MenuItem::OpenFile => self.open_file()?,
-            MenuItem::WatchDir => self.open_dir()?,
-            MenuItem::Search => self.renderer.send_message(MessageToRenderer::Search)?,
-            MenuItem::SearchNext => self.renderer.send_message(MessageToRenderer::SearchNext)?,
-            MenuItem::SearchPrevious => {
-                self.renderer.send_message(MessageToRenderer::SearchPrevious)?
-            }
-            MenuItem::Outline => self.renderer.send_message(MessageToRenderer::Outline)?,
-            MenuItem::Print => self.renderer.print()?,
-            MenuItem::ZoomIn => self.zoom(Zoom::In)?,
-            MenuItem::ZoomOut => self.zoom(Zoom::Out)?,
-            MenuItem::History => self.renderer.send_message(MessageToRenderer::History)?,
-            MenuItem::ToggleAlwaysOnTop => self.toggle_always_on_top()?,
-            MenuItem::Help => self.renderer.send_message(MessageToRenderer::Help)?,
-            MenuItem::OpenRepo => self.opener.open("https://github.com/rhysd/Shiba")?,
+        let item = self.renderer.menu().item_from_id(id)?;
+        log::debug!("Menu item was clicked: {:?}", item);
+        match item {
+            MenuItem::Command(command) => return self.run_command(command),
+            MenuItem::OpenRecent(path) => self.preview_new(path)?,
+            MenuItem::RemoveRecent(path) => self.remove_recent_file(&path)?,
+            MenuItem::OpenBookmark(b) => self.open_bookmark(&b.path, b.heading.as_deref())?,
+            MenuItem::RemoveBookmark(b) => self.remove_bookmark(&b.path, b.heading.as_deref())?,
         }
         Ok(AppControl::Continue)
     }
 
+    // Every data is saved even if saving some of them failed
     fn handle_exit(&self) -> Result<()> {
+        fn log_error(what: &str, result: Result<()>) {
+            if let Err(err) = result {
+                log::error!("Could not save {} on exit: {:#}", what, err);
+            }
+        }
+
         if self.config.window().restore {
             if let Some(state) = self.renderer.window_state() {
-                self.data_dir.save(&state)?;
+                log_error("window state", self.data_dir.save(&state));
             }
         }
-        self.data_dir.save_recent_files(self.history.iter(), self.config.max_recent_files())?;
+        if self.config.window().restore_session {
+            let session = Session {
+                history: self.history.iter().map(Path::to_path_buf).collect(),
+                index: self.history.index,
+                watch_dirs: self.file_tree.roots().map(Path::to_path_buf).collect(),
+            };
+            log_error("session", self.data_dir.save(&session));
+        }
+        log_error(
+            "recent files",
+            self.data_dir.save_recent_files(&self.recent_files, self.config.max_recent_files()),
+        );
+        log_error("search history", self.data_dir.save_search_history(&self.search_history));
+        log_error("view states", self.data_dir.save(&self.preview.view_states));
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn items(history: &History) -> Vec<&Path> {
+        history.iter().collect()
+    }
+
+    #[test]
+    fn restore_history() {
+        let dir = TempDir::with_files("history-restore", &["a.md", "b.md", "c.md"]);
+        let (a, b, c) = (dir.join("a.md"), dir.join("b.md"), dir.join("c.md"));
+        let missing = dir.join("missing.md");
+
+        // Missing files are dropped and the index follows the current item
+        let mut history = History::new(10);
+        history.restore(vec![a.clone(), missing.clone(), b.clone(), c.clone()], 2);
+        assert_eq!(items(&history), [&a, &b, &c]);
+        assert_eq!(history.current(), Some(&b));
+
+        // The previous item becomes current when the current file was removed
+        history.restore(vec![a.clone(), missing.clone(), b.clone()], 1);
+        assert_eq!(items(&history), [&a, &b]);
+        assert_eq!(history.current(), Some(&a));
+
+        // Old items are dropped when the session is larger than the history
+        let mut history = History::new(2);
+        history.restore(vec![a.clone(), b.clone(), c.clone()], 2);
+        assert_eq!(items(&history), [&b, &c]);
+        assert_eq!(history.current(), Some(&c));
+
+        // The current item is kept even if it is old
+        history.restore(vec![a.clone(), b.clone(), c.clone()], 0);
+        assert_eq!(items(&history), [&a, &b]);
+        assert_eq!(history.current(), Some(&a));
+
+        history.restore(vec![missing], 0);
+        assert!(items(&history).is_empty());
+        assert_eq!(history.current(), None);
+    }
+
+    #[test]
+    fn shrink_history() {
+        let paths: Vec<_> = ["a.md", "b.md", "c.md", "d.md", "e.md"].map(PathBuf::from).into();
+        let history_at = |index: usize| {
+            let mut history = History::new(10);
+            for path in &paths {
+                history.push(path.clone());
+            }
+            for _ in index..paths.len() - 1 {
+                history.back();
+            }
+            history
+        };
+
+        for (index, max, want) in [
+            (4, 2, &paths[3..]),
+            (1, 2, &paths[1..3]),
+            (0, 2, &paths[..2]),
+            (2, 3, &paths[1..4]),
+            (1, 1, &paths[1..2]),
+            (3, 10, &paths[..]),
+        ] {
+            let mut history = history_at(index);
+            history.set_max_items(max);
+            let want: Vec<_> = want.iter().map(PathBuf::as_path).collect();
+            assert_eq!(items(&history), want, "index={index}, max={max}");
+            assert_eq!(history.current(), Some(&paths[index]), "index={index}, max={max}");
+        }
+    }
+
+    #[test]
+    fn remove_history_item() {
+        let (a, b, c) = (PathBuf::from("a.md"), PathBuf::from("b.md"), PathBuf::from("c.md"));
+        let mut history = History::new(10);
+        for item in [&a, &b, &a, &c] {
+            history.push(item.clone());
+        }
+        history.back();
+        assert_eq!(history.current(), Some(&a));
+
+        // The current item is kept and the index moves with it
+        history.remove(&a);
+        assert_eq!(items(&history), [&b, &a, &c]);
+        assert_eq!(history.current(), Some(&a));
+
+        history.remove(&c);
+        assert_eq!(items(&history), [&b, &a]);
+        assert_eq!(history.current(), Some(&a));
+
+        history.remove(&b);
+        assert_eq!(items(&history), [&a]);
+        assert_eq!(history.current(), Some(&a));
+
+        history.remove(&a);
+        assert_eq!(items(&history), [&a]);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::config::Config;
+use crate::config::{is_theme_file, Config, UserThemes};
 use crate::renderer::Theme as WindowTheme;
 use phf::phf_map;
 use std::borrow::Cow;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 #[cfg(debug_assertions)]
 const BUNDLE_JS: &[u8] = include_bytes!("assets/bundle.js");
@@ -121,31 +122,49 @@ const MIME_TABLE: phf::Map<&'static str, &'static str> = phf_map! {
     "map"  => "text/plain;charset=UTF-8",
 };
 
-fn load_hljs_css(theme_name: &str, default: &'static [u8]) -> &'static [u8] {
-    log::debug!("Loading highlight.js theme {:?}", theme_name);
-    if let Some(css) = HLJS_CSS_TABLE.get(theme_name) {
-        css
-    } else {
-        log::error!("Unknown highlight.js theme name {:?}. See https://highlightjs.org/static/demo/ to know the list", theme_name);
-        default
-    }
+pub fn hljs_theme_names() -> impl Iterator<Item = &'static str> {
+    HLJS_CSS_TABLE.keys().copied()
+}
+
+// Bundled theme names without aliases which differ only in case (e.g. "GitHub" and "Github") for listing them
+pub fn bundled_hljs_themes() -> Vec<&'static str> {
+    let mut names: Vec<_> = hljs_theme_names().collect();
+    names.sort_by_cached_key(|n| (n.to_ascii_lowercase(), *n));
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    names
 }
 
-fn load_user_css(config: &Config) -> Option<Vec<u8>> {
-    let config_path = config.config_file()?;
-    let css_path = config.preview().css_path()?;
-    let css_path = config_path.parent()?.join(css_path);
+// Theme is a user theme name in the `themes` directory, a bundled theme name, or a path to CSS file. User themes take
+// precedence over bundled themes of the same name so that users can customize the bundled themes.
+fn load_hljs_css(theme: &str, themes: &UserThemes, default: &'static [u8]) -> Cow<'static, [u8]> {
+    log::debug!("Loading highlight.js theme {:?}", theme);
+    let path = if is_theme_file(theme) { Some(Path::new(theme)) } else { themes.get(theme) };
+    if path.is_none() {
+        if let Some(css) = HLJS_CSS_TABLE.get(theme) {
+            return Cow::Borrowed(css);
+        }
+    }
+
+    let Some(path) = path else {
+        log::error!("Unknown highlight.js theme name {:?}. See https://highlightjs.org/static/demo/ to know the list", theme);
+        return Cow::Borrowed(default);
+    };
+
+    match fs::read(path) {
+        Ok(css) => Cow::Owned(css),
+        Err(err) => {
+            log::error!("Could not load highlight.js theme file {:?}: {}", path, err);
+            Cow::Borrowed(default)
+        }
+    }
+}
 
+fn load_user_css(css_path: &Path) -> Option<Vec<u8>> {
     log::debug!("Loading user CSS at {:?}", css_path);
-    match fs::read(&css_path) {
+    match fs::read(css_path) {
         Ok(css) => Some(css),
         Err(err) => {
-            log::error!(
-                "Could not load CSS file {:?} specified in config file {:?}: {}",
-                css_path,
-                config_path,
-                err,
-            );
+            log::error!("Could not load CSS file {:?} specified in config file: {}", css_path, err);
             None
         }
     }
@@ -162,25 +181,57 @@ fn guess_mime(path: &str) -> &'static str {
 }
 
 pub struct Assets {
-    hljs_css: &'static [u8],
-    markdown_css: Cow<'static, [u8]>,
+    hljs_light_css: Cow<'static, [u8]>,
+    hljs_dark_css: Cow<'static, [u8]>,
+    theme: WindowTheme,
+    user_css_path: Option<PathBuf>,
 }
 
 impl Assets {
     pub fn new(config: &Config, theme: WindowTheme) -> Self {
         let hl = config.preview().highlight();
-        let hljs_css = match theme {
-            WindowTheme::Light => load_hljs_css(&hl.light, HLJS_DEFAULT_LIGHT_CSS),
-            WindowTheme::Dark => load_hljs_css(&hl.dark, HLJS_DEFAULT_DARK_CSS),
-        };
+        let themes = config.user_themes();
+        let hljs_light_css = load_hljs_css(&hl.light, themes, HLJS_DEFAULT_LIGHT_CSS);
+        let hljs_dark_css = load_hljs_css(&hl.dark, themes, HLJS_DEFAULT_DARK_CSS);
+        let user_css_path = config.user_css_path().map(Path::to_path_buf);
+        Self { hljs_light_css, hljs_dark_css, theme, user_css_path }
+    }
 
-        let markdown_css = if let Some(css) = load_user_css(config) {
+    // Both highlight.js themes are loaded in advance so that the stylesheet can be swapped when the window theme changes
+    pub fn set_theme(&mut self, theme: WindowTheme) {
+        self.theme = theme;
+    }
+
+    // Temporarily use the highlight theme for the current window theme. `None` restores the theme in config.
+    pub fn set_highlight_theme(&mut self, config: &Config, theme: Option<&str>) {
+        let hl = config.preview().highlight();
+        let themes = config.user_themes();
+        match self.theme {
+            WindowTheme::Light => {
+                let theme = theme.unwrap_or(&hl.light);
+                self.hljs_light_css = load_hljs_css(theme, themes, HLJS_DEFAULT_LIGHT_CSS);
+            }
+            WindowTheme::Dark => {
+                let theme = theme.unwrap_or(&hl.dark);
+                self.hljs_dark_css = load_hljs_css(theme, themes, HLJS_DEFAULT_DARK_CSS);
+            }
+        }
+    }
+
+    fn hljs_css(&self) -> Cow<'static, [u8]> {
+        match self.theme {
+            WindowTheme::Light => self.hljs_light_css.clone(),
+            WindowTheme::Dark => self.hljs_dark_css.clone(),
+        }
+    }
+
+    // User CSS is loaded every time it is requested so that the changes to the file are reflected on reload
+    fn markdown_css(&self) -> Cow<'static, [u8]> {
+        if let Some(css) = self.user_css_path.as_deref().and_then(load_user_css) {
             Cow::Owned(css)
         } else {
             Cow::Borrowed(GITHUB_MARKDOWN_CSS)
-        };
-
-        Self { hljs_css, markdown_css }
+        }
     }
 
     pub fn load(&self, path: &str) -> (Cow<'static, [u8]>, &'static str) {
@@ -191,8 +242,8 @@ impl Assets {
             "/index.html"          => INDEX_HTML.into(),
             "/bundle.js"           => BUNDLE_JS.into(),
             "/style.css"           => STYLE_CSS.into(),
-            "/github-markdown.css" => self.markdown_css.clone(),
-            "/hljs-theme.css"      => self.hljs_css.into(),
+            "/github-markdown.css" => self.markdown_css(),
+            "/hljs-theme.css"      => self.hljs_css(),
             "/logo.png"            => LOGO_PNG.into(),
             #[cfg(debug_assertions)]
             "/bundle.js.map"       => BUNDLE_JS_MAP.into(),
@@ -13,22 +13,46 @@ pub struct Options {
     pub watch: bool,
     pub theme: Option<WindowTheme>,
     pub gen_config_file: bool,
+    pub check_config: bool,
     pub config_dir: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+    pub overrides: Vec<String>,
     pub data_dir: Option<PathBuf>,
 }
 
 impl Options {
     pub fn from_args(iter: impl Iterator<Item = String>) -> Result<Option<Self>> {
+        Self::parse(iter, |name| env::var(name).ok())
+    }
+
+    // Environment variables are used when the corresponding options are not specified
+    fn parse(
+        iter: impl Iterator<Item = String>,
+        env_var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>> {
         let mut opts = GetOpts::new();
         opts.optflag("h", "help", "print this help");
-        opts.optopt("t", "theme", r#"window theme ("dark", "light" or "system")"#, "THEME");
+        opts.optopt(
+            "t",
+            "theme",
+            r#"window theme ("dark", "light" or "system") ($SHIBA_THEME)"#,
+            "THEME",
+        );
         opts.optflag("", "no-watch", "disable to watch file changes");
         opts.optflag(
             "",
             "generate-config-file",
             "generate default config file at the config directory. this overwrites an existing file",
         );
-        opts.optopt("", "config-dir", "custom config directory path", "PATH");
+        opts.optflag("", "check-config", "check config files and print all problems in them");
+        opts.optopt("", "config-dir", "custom config directory path ($SHIBA_CONFIG_DIR)", "PATH");
+        opts.optopt("", "config", "custom config file path", "FILE");
+        opts.optmulti(
+            "",
+            "set",
+            "override config value by dotted key path like preview.highlight.dark=Nord",
+            "KEY=VALUE",
+        );
         opts.optopt("", "data-dir", "custom data directory path", "PATH");
         opts.optflag("", "debug", "enable debug features");
 
@@ -40,7 +64,7 @@ impl Options {
             return Ok(None);
         }
 
-        let theme = match matches.opt_str("t") {
+        let theme = match matches.opt_str("t").or_else(|| env_var("SHIBA_THEME")) {
             Some(theme) => match theme.as_str() {
                 "dark" | "Dark" => Some(WindowTheme::Dark),
                 "light" | "Light" => Some(WindowTheme::Light),
@@ -54,7 +78,16 @@ impl Options {
         };
         let watch = !matches.opt_present("no-watch");
         let gen_config_file = matches.opt_present("generate-config-file");
-        let config_dir = matches.opt_str("config-dir").map(PathBuf::from);
+        let check_config = matches.opt_present("check-config");
+        let config_dir = matches
+            .opt_str("config-dir")
+            .or_else(|| env_var("SHIBA_CONFIG_DIR"))
+            .map(PathBuf::from);
+        let config_file = matches.opt_str("config").map(PathBuf::from);
+        if config_file.is_some() && matches.opt_present("config-dir") {
+            anyhow::bail!("--config and --config-dir cannot be specified at the same time");
+        }
+        let overrides = matches.opt_strs("set");
         let data_dir = matches.opt_str("data-dir").map(PathBuf::from);
         let debug = matches.opt_present("debug");
 
@@ -92,10 +125,21 @@ impl Options {
             watch,
             theme,
             gen_config_file,
+            check_config,
             config_dir,
+            config_file,
+            overrides,
             data_dir,
         }))
     }
+
+    // Directory to discover the project-local config file
+    pub fn project_dir(&self) -> Option<&Path> {
+        match &self.init_file {
+            Some(file) => file.parent(),
+            None => self.watch_paths.first().map(PathBuf::as_path),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +183,10 @@ mod tests {
                 &["--theme", "dark"][..],
                 Options { watch: true, theme: Some(WindowTheme::Dark), ..Default::default() },
             ),
+            (
+                &["--check-config"][..],
+                Options { watch: true, check_config: true, ..Default::default() },
+            ),
             (
                 &["--config-dir", "some-dir"][..],
                 Options {
@@ -163,6 +211,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn config_options() {
+        let args = ["--config", "foo.yml", "--set", "a.b=1", "--set", "c=d"];
+        let opts =
+            Options::parse(args.iter().map(|&s| String::from(s)), |_| None).unwrap().unwrap();
+        assert_eq!(opts.config_file, Some(PathBuf::from("foo.yml")));
+        assert_eq!(opts.overrides, ["a.b=1", "c=d"]);
+
+        let args = ["--config", "foo.yml", "--config-dir", "bar"];
+        let err = Options::parse(args.iter().map(|&s| String::from(s)), |_| None).unwrap_err();
+        assert!(format!("{}", err).contains("cannot be specified at the same time"), "{:?}", err);
+    }
+
+    #[test]
+    fn environment_variables() {
+        let env_var = |name: &str| match name {
+            "SHIBA_THEME" => Some("light".to_string()),
+            "SHIBA_CONFIG_DIR" => Some("env-dir".to_string()),
+            _ => None,
+        };
+
+        let opts = Options::parse(std::iter::empty(), env_var).unwrap().unwrap();
+        assert_eq!(opts.theme, Some(WindowTheme::Light));
+        assert_eq!(opts.config_dir, Some(PathBuf::from("env-dir")));
+
+        // Options are prioritized over environment variables
+        let args = ["--theme", "dark", "--config-dir", "arg-dir"];
+        let opts = Options::parse(args.iter().map(|&s| String::from(s)), env_var).unwrap().unwrap();
+        assert_eq!(opts.theme, Some(WindowTheme::Dark));
+        assert_eq!(opts.config_dir, Some(PathBuf::from("arg-dir")));
+    }
+
     #[test]
     fn help_option() {
         let args = [String::from("--help")];
@@ -0,0 +1,284 @@
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::str::FromStr;
+
+// Commands invoked from key mappings, the native menu and the command palette. Variant names are used in `keymaps:`
+// section of config files. The order of variants must be the same as `COMMANDS`.
+#[non_exhaustive]
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Command {
+    OpenFile,
+    FileFinder,
+    OpenDir,
+    ClearRecent,
+    Print,
+    Search,
+    NextSearch,
+    PrevSearch,
+    WorkspaceSearch,
+    Outline,
+    CommandPalette,
+    ScrollDown,
+    ScrollUp,
+    ScrollLeft,
+    ScrollRight,
+    ScrollPageDown,
+    ScrollPageUp,
+    ScrollTop,
+    ScrollBottom,
+    ScrollNextSection,
+    ScrollPrevSection,
+    Reload,
+    ZoomIn,
+    ZoomOut,
+    Sidebar,
+    ToggleTheme,
+    HighlightThemes,
+    Forward,
+    Back,
+    History,
+    PinRecent,
+    BookmarkFile,
+    BookmarkHeading,
+    Bookmarks,
+    ToggleAlwaysOnTop,
+    Help,
+    OpenRepo,
+    Quit,
+}
+
+// Where the command is handled. Commands handled by the renderer are sent to the renderer when they are invoked from
+// the menu, and commands handled by the main are sent to the main when they are invoked from key mappings.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum Handler {
+    Main,
+    Renderer,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommandSpec {
+    pub command: Command,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub handler: Handler,
+    // Default key mappings in mousetrap syntax
+    pub keys: &'static [&'static str],
+    // Default accelerator of the menu item. `mod` is Cmd on macOS and Ctrl on other platforms
+    pub accelerator: Option<&'static str>,
+}
+
+const fn spec(
+    command: Command,
+    handler: Handler,
+    label: &'static str,
+    keys: &'static [&'static str],
+    accelerator: Option<&'static str>,
+    description: &'static str,
+) -> CommandSpec {
+    CommandSpec { command, label, description, handler, keys, accelerator }
+}
+
+#[rustfmt::skip]
+pub const COMMANDS: &[CommandSpec] = {
+    use Command::*;
+    use Handler::*;
+    &[
+        spec(OpenFile,          Main,     "Open File…",                &["ctrl+o"],              Some("mod+o"),       "Open a dialog to choose a file to preview."),
+        spec(FileFinder,        Renderer, "Go to File…",               &["ctrl+p"],              Some("mod+p"),       "Open a palette to fuzzily find Markdown files in watched directories and history."),
+        spec(OpenDir,           Main,     "Watch Directory…",          &[],                      Some("mod+shift+o"), "Open a dialog to choose a directory to watch file changes."),
+        spec(ClearRecent,       Main,     "Clear Recent",              &[],                      None,                "Clear the list of recently opened files."),
+        spec(Print,             Main,     "Print…",                    &[],                      None,                "Print the current document."),
+        spec(Search,            Renderer, "Search…",                   &[],                      Some("mod+f"),       "Open an in-page search box."),
+        spec(NextSearch,        Renderer, "Search Next",               &[],                      Some("mod+g"),       "Focus the next match of ongoing text search."),
+        spec(PrevSearch,        Renderer, "Search Previous",           &[],                      Some("mod+shift+g"), "Focus the previous match of the ongoing text search."),
+        spec(WorkspaceSearch,   Renderer, "Search in Watched Files…",  &["ctrl+shift+f"],        Some("mod+shift+f"), "Open a palette to search text in all Markdown files in watched directories."),
+        spec(Outline,           Renderer, "Section Outline…",          &[],                      Some("mod+s"),       "Open a palette to incrementally search the section outline."),
+        spec(CommandPalette,    Renderer, "Command Palette…",          &["ctrl+shift+p"],        Some("mod+shift+p"), "Open a palette to search and run all commands."),
+        spec(ScrollDown,        Renderer, "Scroll Down",               &["j", "down"],           None,                "Scroll down the page by half of window height."),
+        spec(ScrollUp,          Renderer, "Scroll Up",                 &["k", "up"],             None,                "Scroll up the page by half of window height."),
+        spec(ScrollLeft,        Renderer, "Scroll Left",               &["h", "left"],           None,                "Scroll the page to the left by half of window width."),
+        spec(ScrollRight,       Renderer, "Scroll Right",              &["l", "right"],          None,                "Scroll the page to the right by half of window width."),
+        spec(ScrollPageDown,    Renderer, "Scroll Page Down",          &["ctrl+d", "pagedown"],  None,                "Scroll down the page by window height."),
+        spec(ScrollPageUp,      Renderer, "Scroll Page Up",            &["ctrl+u", "pageup"],    None,                "Scroll up the page by window height."),
+        spec(ScrollTop,         Renderer, "Scroll to Top",             &["ctrl+up"],             None,                "Scroll to the top of the page."),
+        spec(ScrollBottom,      Renderer, "Scroll to Bottom",          &["ctrl+down"],           None,                "Scroll to the bottom of the page."),
+        spec(ScrollNextSection, Renderer, "Next Section",              &["ctrl+j"],              None,                "Scroll to the next section header."),
+        spec(ScrollPrevSection, Renderer, "Previous Section",          &["ctrl+k"],              None,                "Scroll to the previous section header."),
+        spec(Reload,            Main,     "Reload",                    &[],                      Some("mod+r"),       "Reload the current document preview."),
+        spec(ZoomIn,            Main,     "Zoom In",                   &[],                      Some("mod+plus"),    "Zoom in on the page."),
+        spec(ZoomOut,           Main,     "Zoom Out",                  &[],                      Some("mod+-"),       "Zoom out on the page."),
+        spec(Sidebar,           Renderer, "Toggle Sidebar",            &["ctrl+e"],              None,                "Toggle the sidebar to show Markdown files in watched directories."),
+        spec(ToggleTheme,       Main,     "Toggle Dark/Light Theme",   &[],                      None,                "Switch the window theme between dark and light."),
+        spec(HighlightThemes,   Main,     "Code Highlight Theme…",     &[],                      None,                "Open a palette to choose a code highlight theme with live preview."),
+        spec(Forward,           Main,     "Forward",                   &["ctrl+f"],              Some("mod+]"),       "Go forward to the next document in preview history."),
+        spec(Back,              Main,     "Back",                      &["ctrl+b"],              Some("mod+["),       "Go backward to the previous document in preview history."),
+        spec(History,           Renderer, "History…",                  &[],                      Some("mod+y"),       "Open a palette to incrementally search files in history."),
+        spec(PinRecent,         Main,     "Pin/Unpin Current File",    &[],                      None,                "Pin the current file at the top of recent files or unpin it."),
+        spec(BookmarkFile,      Renderer, "Bookmark File",             &[],                      Some("mod+shift+d"), "Add the current file to bookmarks or remove it. The selected text is used as the label."),
+        spec(BookmarkHeading,   Renderer, "Bookmark Section",          &[],                      None,                "Add the section at the top of the window to bookmarks or remove it. The selected text is used as the label."),
+        spec(Bookmarks,         Renderer, "Bookmarks…",                &[],                      Some("mod+shift+b"), "Open a palette to incrementally search bookmarks."),
+        spec(ToggleAlwaysOnTop, Main,     "Pin/Unpin On Top",          &[],                      None,                "Toggle whether the window is always on top of other windows."),
+        spec(Help,              Renderer, "Show Guide…",               &["?"],                   None,                "Show the guide of key mappings."),
+        spec(OpenRepo,          Main,     "Open Repository Page",      &[],                      None,                "Open the repository page of this application in a browser."),
+        spec(Quit,              Main,     "Quit",                      &[],                      Some("mod+q"),       "Quit the application."),
+    ]
+};
+
+impl Command {
+    pub fn spec(self) -> &'static CommandSpec {
+        &COMMANDS[self as usize]
+    }
+
+    pub fn handler(self) -> Handler {
+        self.spec().handler
+    }
+
+    pub fn default_accelerator(self) -> Option<Accelerator> {
+        let accel = self.spec().accelerator?;
+        Some(accel.parse().expect("default accelerator must be valid"))
+    }
+}
+
+#[rustfmt::skip]
+const ACCELERATOR_KEYS: &[&str] = &[
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w",
+    "x", "y", "z", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8",
+    "f9", "f10", "f11", "f12", "plus", "-", "=", ",", ".", "/", "\\", ";", "'", "`", "[", "]", "space", "enter", "tab",
+    "esc", "backspace", "del", "up", "down", "left", "right", "home", "end", "pageup", "pagedown",
+];
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+// Accelerator of a menu item written like "mod+shift+o". `mod` is resolved to Cmd on macOS and Ctrl on other
+// platforms. The key is normalized to one of `ACCELERATOR_KEYS`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Accelerator {
+    pub modifiers: Modifiers,
+    pub key: &'static str,
+}
+
+impl FromStr for Accelerator {
+    type Err = String;
+
+    fn from_str(accel: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut parts: Vec<&str> = accel.split('+').collect();
+        let key = parts.pop().unwrap_or_default();
+        for modifier in parts {
+            let enabled = match modifier {
+                "mod" if cfg!(target_os = "macos") => &mut modifiers.meta,
+                "mod" | "ctrl" => &mut modifiers.ctrl,
+                "shift" => &mut modifiers.shift,
+                "alt" | "option" => &mut modifiers.alt,
+                "meta" | "command" | "cmd" => &mut modifiers.meta,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier `{}` in accelerator `{}`",
+                        modifier, accel
+                    ))
+                }
+            };
+            if *enabled {
+                return Err(format!(
+                    "modifier `{}` is duplicated in accelerator `{}`",
+                    modifier, accel
+                ));
+            }
+            *enabled = true;
+        }
+        let key = match key {
+            "return" => "enter",
+            "escape" => "esc",
+            "delete" => "del",
+            k => k,
+        };
+        let Some(key) = ACCELERATOR_KEYS.iter().find(|k| **k == key).copied() else {
+            return Err(format!("unknown key `{}` in accelerator `{}`", key, accel));
+        };
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl<'de> Deserialize<'de> for Accelerator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+pub fn default_key_mappings() -> impl Iterator<Item = (&'static str, Command)> {
+    COMMANDS.iter().flat_map(|spec| spec.keys.iter().map(|key| (*key, spec.command)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn commands_table_order() {
+        for (idx, spec) in COMMANDS.iter().enumerate() {
+            assert_eq!(spec.command as usize, idx, "{:?} is at wrong position", spec.command);
+            assert_eq!(spec.command.spec().command, spec.command);
+        }
+        assert_eq!(COMMANDS.len(), Command::Quit as usize + 1);
+    }
+
+    #[test]
+    fn default_key_mappings_do_not_conflict() {
+        let mut m = HashMap::new();
+        for (bind, c1) in default_key_mappings() {
+            if let Some(c2) = m.get(bind) {
+                panic!("default mapping {} conflicts: {:?} vs {:?}", bind, c1, c2);
+            }
+            if let Some(i) = bind.find('+') {
+                let modifier = &bind[..i];
+                assert!(matches!(modifier, "ctrl" | "alt"), "invalid modifier {:?}", modifier);
+            }
+            m.insert(bind, c1);
+        }
+    }
+
+    #[test]
+    fn default_accelerators_do_not_conflict() {
+        let mut m = HashMap::new();
+        for spec in COMMANDS {
+            if let Some(accel) = spec.command.default_accelerator() {
+                if let Some(c) = m.insert(accel, spec.command) {
+                    panic!("accelerator {:?} conflicts: {:?} vs {:?}", accel, spec.command, c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_accelerator() {
+        let ctrl_or_cmd = if cfg!(target_os = "macos") {
+            Modifiers { meta: true, ..Default::default() }
+        } else {
+            Modifiers { ctrl: true, ..Default::default() }
+        };
+        let accel: Accelerator = "mod+o".parse().unwrap();
+        assert_eq!(accel, Accelerator { modifiers: ctrl_or_cmd, key: "o" });
+        let accel: Accelerator = "ctrl+shift+alt+cmd+escape".parse().unwrap();
+        let modifiers = Modifiers { ctrl: true, shift: true, alt: true, meta: true };
+        assert_eq!(accel, Accelerator { modifiers, key: "esc" });
+        let accel: Accelerator = "f5".parse().unwrap();
+        assert_eq!(accel, Accelerator { modifiers: Modifiers::default(), key: "f5" });
+
+        for (input, want) in [
+            ("", "unknown key `` in accelerator ``"),
+            ("mod+", "unknown key `` in accelerator `mod+`"),
+            ("mod+foo", "unknown key `foo` in accelerator `mod+foo`"),
+            ("hyper+o", "unknown modifier `hyper` in accelerator `hyper+o`"),
+            ("shift+shift+o", "modifier `shift` is duplicated in accelerator `shift+shift+o`"),
+        ] {
+            assert_eq!(input.parse::<Accelerator>().unwrap_err(), want, "input={:?}", input);
+        }
+    }
+}
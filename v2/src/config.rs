@@ -1,68 +1,24 @@
 use crate::cli::Options;
+use crate::command::{default_key_mappings, Accelerator, Command};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-const DEFAULT_CONFIG_FILE: &str = include_str!("default_config.yml");
+mod check;
 
-#[non_exhaustive]
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub enum KeyAction {
-    Forward,
-    Back,
-    Reload,
-    OpenFile,
-    OpenDir,
-    ScrollDown,
-    ScrollUp,
-    ScrollLeft,
-    ScrollRight,
-    ScrollPageDown,
-    ScrollPageUp,
-    ScrollTop,
-    ScrollBottom,
-    Search,
-    NextSearch,
-    PrevSearch,
-    ScrollNextSection,
-    ScrollPrevSection,
-    Outline,
-    History,
-    Help,
-    ZoomIn,
-    ZoomOut,
-    Quit,
-}
-
-#[rustfmt::skip]
-const DEFAULT_KEY_MAPPINGS: &[(&str, KeyAction)] = {
-    use KeyAction::*;
-    &[
-        ("j",         ScrollDown),
-        ("k",         ScrollUp),
-        ("h",         ScrollLeft),
-        ("l",         ScrollRight),
-        ("ctrl+b",    Back),
-        ("ctrl+f",    Forward),
-        ("ctrl+o",    OpenFile),
-        ("ctrl+d",    ScrollPageDown),
-        ("ctrl+u",    ScrollPageUp),
-        ("down",      ScrollDown),
-        ("up",        ScrollUp),
-        ("left",      ScrollLeft),
-        ("right",     ScrollRight),
-        ("pagedown",  ScrollPageDown),
-        ("pageup",    ScrollPageUp),
-        ("ctrl+down", ScrollBottom),
-        ("ctrl+up",   ScrollTop),
-        ("ctrl+j",    ScrollNextSection),
-        ("ctrl+k",    ScrollPrevSection),
-        ("?",         Help),
-    ]
-};
+pub use check::ConfigProblem;
+
+const DEFAULT_CONFIG_FILE: &str = include_str!("default_config.yml");
+const USER_CONFIG_FILES: &[&str] = &["config.yml", "config.yaml"];
+const PROJECT_CONFIG_FILES: &[&str] =
+    &[".shiba.yml", ".shiba.yaml", ".shiba/config.yml", ".shiba/config.yaml"];
+const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn", ".jj"];
 
 #[repr(transparent)]
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -90,14 +46,20 @@ impl FileExtensions {
 
 #[non_exhaustive]
 #[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct Watch {
     file_extensions: FileExtensions,
     debounce_throttle: u32,
+    ignore_dirs: Vec<String>,
 }
 
 impl Default for Watch {
     fn default() -> Self {
-        Self { file_extensions: Default::default(), debounce_throttle: 50 }
+        Self {
+            file_extensions: Default::default(),
+            debounce_throttle: 50,
+            ignore_dirs: vec![".git".into(), "node_modules".into(), "target".into()],
+        }
     }
 }
 
@@ -109,6 +71,10 @@ impl Watch {
     pub fn file_extensions(&self) -> &FileExtensions {
         &self.file_extensions
     }
+
+    pub fn ignore_dirs(&self) -> &[String] {
+        &self.ignore_dirs
+    }
 }
 
 #[non_exhaustive]
@@ -118,6 +84,10 @@ pub enum SearchMatcher {
     CaseSensitive,
     CaseInsensitive,
     CaseSensitiveRegex,
+    CaseInsensitiveRegex,
+    SmartCaseRegex,
+    WholeWord,
+    Fuzzy,
 }
 
 impl Default for SearchMatcher {
@@ -126,10 +96,29 @@ impl Default for SearchMatcher {
     }
 }
 
+#[non_exhaustive]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SavedSearch {
+    name: String,
+    query: String,
+    matcher: SearchMatcher,
+    key: Option<String>,
+}
+
 #[non_exhaustive]
 #[derive(Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct Search {
     matcher: SearchMatcher,
+    ignore_accents: bool,
+    saved: Vec<SavedSearch>,
+}
+
+impl Search {
+    pub fn ignore_accents(&self) -> bool {
+        self.ignore_accents
+    }
 }
 
 #[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
@@ -146,6 +135,7 @@ impl Default for WindowTheme {
 }
 
 #[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct WindowSize {
     pub width: u32,
     pub height: u32,
@@ -153,14 +143,17 @@ pub struct WindowSize {
 
 #[non_exhaustive]
 #[derive(Default, Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct Window {
     pub restore: bool,
+    pub restore_session: bool,
     pub theme: WindowTheme,
     pub always_on_top: bool,
     pub default_size: Option<WindowSize>,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct PreviewHighlight {
     pub dark: String,
     pub light: String,
@@ -174,15 +167,23 @@ impl Default for PreviewHighlight {
 
 #[non_exhaustive]
 #[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct Preview {
     highlight: PreviewHighlight,
     css: Option<PathBuf>,
     recent_files: usize,
+    // Zero is rejected since the current file is always in the history
+    history_size: NonZeroUsize,
 }
 
 impl Default for Preview {
     fn default() -> Self {
-        Self { highlight: PreviewHighlight::default(), css: None, recent_files: 100 }
+        Self {
+            highlight: PreviewHighlight::default(),
+            css: None,
+            recent_files: 100,
+            history_size: NonZeroUsize::new(20).unwrap(),
+        }
     }
 }
 
@@ -204,21 +205,22 @@ fn resolve_path<'de, D: Deserializer<'de>>(
     #[cfg(target_os = "windows")]
     const PREFIX: &str = "~\\";
 
-    let s = String::deserialize(deserializer)?;
-    if &s == "null" {
-        return Ok(None);
-    }
-    if !s.starts_with(PREFIX) {
-        return Ok(Some(PathBuf::from(s)));
-    }
-
-    let Some(mut path) = dirs::home_dir() else {
+    let Some(s) = Option::<String>::deserialize(deserializer)? else {
         return Ok(None);
     };
+    let path = if let Some(rest) = s.strip_prefix(PREFIX) {
+        let Some(mut path) = dirs::home_dir() else {
+            return Ok(None);
+        };
+        path.push(rest);
+        path
+    } else {
+        PathBuf::from(s)
+    };
 
-    path.push(&s[2..]);
+    // The directory may be on a removable or network drive which is not mounted now. Fall back to the default
     if !path.is_dir() {
-        log::error!("Path {:?} in config is not a directory", path);
+        log::warn!("Path {:?} in config is not a directory. It is ignored", path);
         return Ok(None);
     }
 
@@ -226,6 +228,7 @@ fn resolve_path<'de, D: Deserializer<'de>>(
 }
 
 #[derive(Default, Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct Dialog {
     #[serde(deserialize_with = "resolve_path")]
     default_dir: Option<PathBuf>,
@@ -237,70 +240,302 @@ impl Dialog {
     }
 }
 
+// External command run from key mappings or the command palette. `run` is a command line template
+#[non_exhaustive]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalCommand {
+    pub name: String,
+    pub run: String,
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub reload: bool,
+}
+
+#[derive(Default, Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Menu {
+    accelerators: HashMap<Command, Option<Accelerator>>,
+}
+
+impl Menu {
+    // `None` means the menu item has no accelerator
+    pub fn accelerator(&self, command: Command) -> Option<Accelerator> {
+        match self.accelerators.get(&command) {
+            Some(accel) => *accel,
+            None => command.default_accelerator(),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct ConfigData {
     watch: Watch,
-    keymaps: HashMap<String, KeyAction>,
+    keymaps: HashMap<String, Command>,
     search: Search,
     window: Window,
     preview: Preview,
     dialog: Dialog,
+    menu: Menu,
+    commands: Vec<ExternalCommand>,
 }
 
 impl Default for ConfigData {
     fn default() -> Self {
         Self {
             watch: Watch::default(),
-            keymaps: DEFAULT_KEY_MAPPINGS.iter().map(|(b, a)| (b.to_string(), *a)).collect(),
+            keymaps: default_key_mappings().map(|(b, c)| (b.to_string(), c)).collect(),
             search: Search::default(),
             window: Window::default(),
             preview: Preview::default(),
             dialog: Dialog::default(),
+            menu: Menu::default(),
+            commands: vec![],
         }
     }
 }
 
-impl ConfigData {
-    fn load(path: &Path) -> Option<Result<Self>> {
-        match fs::read(path) {
-            Ok(bytes) => Some(
-                serde_yaml::from_slice(&bytes)
-                    .with_context(|| format!("Could not parse config file as YAML: {:?}. To reset config file, try --generate-config-file", path)),
-            ),
-            Err(err) => {
-                log::debug!("Could not read config file from {:?}: {}", path, err);
+// highlight.js theme CSS files put in `themes` directory in the config directory. The file stem is the theme name.
+#[derive(Clone, Default, Debug)]
+pub struct UserThemes {
+    dir: Option<PathBuf>,
+    themes: BTreeMap<String, PathBuf>,
+}
+
+impl UserThemes {
+    fn scan(config_dir: &Path) -> Self {
+        let dir = config_dir.join("themes");
+        let mut themes = BTreeMap::new();
+        match fs::read_dir(&dir) {
+            Ok(entries) => {
+                for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                    if path.extension().map(|e| e == "css").unwrap_or(false) {
+                        if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                            log::debug!("Found user highlight theme {:?} at {:?}", name, path);
+                            themes.insert(name.to_string(), path.clone());
+                        }
+                    }
+                }
+            }
+            Err(err) => log::debug!("Could not read user themes directory {:?}: {}", dir, err),
+        }
+        Self { dir: Some(dir), themes }
+    }
+
+    // Theme files may be added or removed while running
+    fn rescan(&self) -> Self {
+        match self.dir.as_deref().and_then(Path::parent) {
+            Some(config_dir) => Self::scan(config_dir),
+            None => Self::default(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Path> {
+        self.themes.get(name).map(PathBuf::as_path)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'_ str> {
+        self.themes.keys().map(String::as_str)
+    }
+}
+
+fn merge_mapping(base: &mut Mapping, layer: &Mapping) {
+    for (key, value) in layer {
+        match (base.get_mut(key), value) {
+            (Some(Value::Mapping(base)), Value::Mapping(value)) => merge_mapping(base, value),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+// Config file which overrides some keys of the default config. Config files can be partial. The user config file is
+// layered over the default config, and a project-local config file is layered over them.
+#[derive(Clone, Debug)]
+struct ConfigLayer {
+    path: PathBuf,
+    value: Mapping,
+}
+
+impl ConfigLayer {
+    fn read(path: &Path) -> Option<Result<String>> {
+        match fs::read_to_string(path) {
+            Ok(source) => Some(Ok(source)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                log::debug!("Config file does not exist at {:?}", path);
                 None
             }
+            Err(err) => Some(
+                Err(err).with_context(|| format!("Could not read config file from {:?}", path)),
+            ),
         }
     }
+
+    fn load(path: &Path, themes: &UserThemes) -> Option<Result<Self>> {
+        let source = match Self::read(path)? {
+            Ok(source) => source,
+            Err(err) => return Some(Err(err)),
+        };
+        let value = match check::check_config_source(path, &source, themes) {
+            Ok(value) => value,
+            Err(problems) => {
+                let problems: Vec<_> = problems.iter().map(ToString::to_string).collect();
+                return Some(Err(anyhow::anyhow!(
+                    "Found {} problem(s) in config file. Run with --check-config to see the details. To reset config file, try --generate-config-file\n{}",
+                    problems.len(),
+                    problems.join("\n"),
+                )));
+            }
+        };
+        let mut layer = Self { path: path.to_path_buf(), value };
+        layer.resolve_css_paths();
+        Some(Ok(layer))
+    }
+
+    // Project-local config file comes with the repository, which may not be trusted. External commands run any program
+    // so they are only available in the user config file. `watch` is ignored as well since the file watcher and the file
+    // tree are shared by all previewed files and are not switched with the project.
+    fn load_project(path: &Path, themes: &UserThemes) -> Option<Result<Self>> {
+        let layer = Self::load(path, themes)?.map(|mut layer| {
+            for key in ["commands", "watch"] {
+                if layer.value.remove(key).is_some() {
+                    log::warn!("`{}` in project-local config file {:?} is ignored", key, path);
+                }
+            }
+            layer
+        });
+        Some(layer)
+    }
+
+    // Returns all problems in the config file. `None` means the file does not exist
+    fn check(path: &Path, themes: &UserThemes) -> Option<Result<Vec<ConfigProblem>>> {
+        let source = match Self::read(path)? {
+            Ok(source) => source,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(Ok(check::check_config_source(path, &source, themes).err().unwrap_or_default()))
+    }
+
+    // CSS paths in config (user CSS and highlight theme files) are relative to the directory of the config file
+    fn resolve_css_paths(&mut self) {
+        let Some(dir) = self.path.parent() else {
+            return;
+        };
+        let Some(Value::Mapping(preview)) = self.value.get_mut("preview") else {
+            return;
+        };
+        if let Some(Value::String(css)) = preview.get_mut("css") {
+            *css = dir.join(&*css).to_string_lossy().into_owned();
+        }
+        let Some(Value::Mapping(highlight)) = preview.get_mut("highlight") else {
+            return;
+        };
+        for key in ["dark", "light"] {
+            if let Some(Value::String(theme)) = highlight.get_mut(key) {
+                if is_theme_file(theme) {
+                    *theme = dir.join(&*theme).to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+
+    // Mappings are merged per key and other values are overwritten. Only key mappings are overwritten as a whole so
+    // that the user config file can remove the default key mappings. The project-local config file is merged with
+    // `merge_mapping` so that its key mappings are added to the user's ones.
+    fn merge_into(&self, base: &mut Mapping) {
+        for (key, value) in &self.value {
+            match (base.get_mut(key), value) {
+                (Some(Value::Mapping(base)), Value::Mapping(value))
+                    if key.as_str() != Some("keymaps") =>
+                {
+                    merge_mapping(base, value)
+                }
+                _ => {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+// Highlight theme ending with `.css` is a path to the theme file instead of a theme name
+pub fn is_theme_file(theme: &str) -> bool {
+    theme.ends_with(".css")
 }
 
+// Config values overridden by command line options are merged per key over all config files
 #[derive(Default, Debug)]
 pub struct Config {
     data: ConfigData,
-    path: Option<PathBuf>,
+    user: Option<ConfigLayer>,
+    project: Option<ConfigLayer>,
+    overrides: Mapping,
+    themes: UserThemes,
 }
 
 impl Config {
+    fn with_layers(
+        user: Option<ConfigLayer>,
+        project: Option<ConfigLayer>,
+        overrides: Mapping,
+        themes: UserThemes,
+    ) -> Result<Self> {
+        let mut merged: Mapping = serde_yaml::from_str(DEFAULT_CONFIG_FILE)?;
+        if let Some(layer) = &user {
+            layer.merge_into(&mut merged);
+        }
+        if let Some(layer) = &project {
+            merge_mapping(&mut merged, &layer.value);
+        }
+        merge_mapping(&mut merged, &overrides);
+        let data = serde_yaml::from_value(Value::Mapping(merged)).with_context(|| {
+            let paths: Vec<_> = user.iter().chain(project.iter()).map(|l| &l.path).collect();
+            format!("Invalid config in config files {:?}. To reset config file, try --generate-config-file", paths)
+        })?;
+        Ok(Self { data, user, project, overrides, themes })
+    }
+
+    // User themes are put in the same directory as the config file
+    fn file_themes(path: &Path) -> UserThemes {
+        path.parent().map(UserThemes::scan).unwrap_or_default()
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let themes = Self::file_themes(path);
+        match ConfigLayer::load(path, &themes) {
+            Some(layer) => Self::with_layers(Some(layer?), None, Mapping::new(), themes),
+            None => anyhow::bail!("Config file {:?} does not exist", path),
+        }
+    }
+
     pub fn load_dir(path: impl Into<PathBuf>) -> Result<Self> {
         let mut path = path.into();
+        let themes = UserThemes::scan(&path);
         if path.is_dir() {
-            for file in ["config.yml", "config.yaml"] {
+            for file in USER_CONFIG_FILES {
                 path.push(file);
-                if let Some(data) = ConfigData::load(&path) {
-                    return Ok(Config { data: data?, path: Some(path) });
+                if let Some(layer) = ConfigLayer::load(&path, &themes) {
+                    return Self::with_layers(Some(layer?), None, Mapping::new(), themes);
                 }
                 path.pop();
             }
         }
         log::debug!("config.yml nor config.yaml was found in {path:?}. Using the default config");
-        Ok(Self::default())
+        Ok(Self { themes, ..Default::default() })
+    }
+
+    fn default_config_dir() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("Shiba");
+        Some(path)
     }
 
     pub fn load() -> Result<Self> {
-        if let Some(mut path) = dirs::config_dir() {
-            path.push("Shiba");
+        if let Some(path) = Self::default_config_dir() {
             Self::load_dir(path)
         } else {
             log::debug!("Config directory does not exist. Using the default config");
@@ -310,43 +545,160 @@ impl Config {
 
     pub fn generate_default_config_at(config_path: impl Into<PathBuf>) -> Result<Self> {
         let mut config_path = config_path.into();
+        config_path.push("config.yml");
+        Self::generate_default_config_file(config_path)
+    }
 
-        fs::create_dir_all(&config_path).with_context(|| {
-            format!("Could not create directory for generating config file at {:?}", &config_path)
-        })?;
+    pub fn generate_default_config_file(config_path: PathBuf) -> Result<Self> {
+        if let Some(dir) = config_path.parent() {
+            fs::create_dir_all(dir).with_context(|| {
+                format!("Could not create directory for generating config file at {:?}", dir)
+            })?;
+        }
 
-        config_path.push("config.yml");
         fs::write(&config_path, DEFAULT_CONFIG_FILE)
             .with_context(|| format!("Could not generate config file at {:?}", &config_path))?;
 
         log::info!("Generated the default config file at {:?}", config_path);
-        Ok(Config { path: Some(config_path), ..Default::default() })
+        let themes = Self::file_themes(&config_path);
+        let user = ConfigLayer { path: config_path, value: Mapping::new() };
+        Ok(Config { user: Some(user), themes, ..Default::default() })
     }
 
     pub fn generate_default_config() -> Result<Self> {
-        let Some(mut config_path) = dirs::config_dir() else {
+        let Some(config_path) = Self::default_config_dir() else {
             anyhow::bail!("Config directory cannot be determined on this system. Config file is not available");
         };
-        config_path.push("Shiba");
         Self::generate_default_config_at(config_path)
     }
 
-    pub fn merge_options(mut self, options: &Options) -> Self {
+    // Check the user config file and the project-local config file found from the project directory. Returns all
+    // problems for each config file which was found.
+    pub fn check_files(
+        options: &Options,
+        project_dir: &Path,
+    ) -> Result<Vec<(PathBuf, Vec<ConfigProblem>)>> {
+        let mut checked = vec![];
+        let mut themes = UserThemes::default();
+        if let Some(path) = &options.config_file {
+            themes = Self::file_themes(path);
+            let Some(problems) = ConfigLayer::check(path, &themes) else {
+                anyhow::bail!("Config file {:?} does not exist", path);
+            };
+            checked.push((path.clone(), problems?));
+        } else if let Some(dir) = options.config_dir.clone().or_else(Self::default_config_dir) {
+            themes = UserThemes::scan(&dir);
+            for file in USER_CONFIG_FILES {
+                let path = dir.join(file);
+                if let Some(problems) = ConfigLayer::check(&path, &themes) {
+                    checked.push((path, problems?));
+                    break;
+                }
+            }
+        }
+        if let Some(path) = Self::find_project_config_file(project_dir) {
+            if let Some(problems) = ConfigLayer::check(&path, &themes) {
+                checked.push((path, problems?));
+            }
+        }
+        Ok(checked)
+    }
+
+    // Find the nearest project-local config file (`.shiba.yml` or `.shiba/config.yml`) from the directory. The search
+    // stops at the root of the repository or the home directory so that unrelated config files are not picked up.
+    pub fn find_project_config_file(dir: &Path) -> Option<PathBuf> {
+        let home = dirs::home_dir();
+        for dir in dir.ancestors() {
+            let found =
+                PROJECT_CONFIG_FILES.iter().map(|file| dir.join(file)).find(|p| p.is_file());
+            if found.is_some() {
+                return found;
+            }
+            if home.as_deref() == Some(dir) || VCS_DIRS.iter().any(|d| dir.join(d).exists()) {
+                log::debug!("Project-local config file was not found until {:?}", dir);
+                return None;
+            }
+        }
+        None
+    }
+
+    // Switch the project-local config file layered over the user config
+    pub fn with_project_config_file(&self, path: Option<&Path>) -> Result<Self> {
+        let project = path.and_then(|p| ConfigLayer::load_project(p, &self.themes)).transpose()?;
+        Self::with_layers(self.user.clone(), project, self.overrides.clone(), self.themes.clone())
+    }
+
+    // Load the config files again to reflect their changes. Config files which are no longer available are kept as-is.
+    pub fn reload(&self) -> Result<Self> {
+        fn reload_layer(
+            layer: &Option<ConfigLayer>,
+            themes: &UserThemes,
+            load: fn(&Path, &UserThemes) -> Option<Result<ConfigLayer>>,
+        ) -> Result<Option<ConfigLayer>> {
+            let Some(layer) = layer else {
+                return Ok(None);
+            };
+            match load(&layer.path, themes) {
+                Some(loaded) => loaded.map(Some),
+                None => Ok(Some(layer.clone())),
+            }
+        }
+        let themes = self.themes.rescan();
+        let user = reload_layer(&self.user, &themes, ConfigLayer::load)?;
+        let project = reload_layer(&self.project, &themes, ConfigLayer::load_project)?;
+        Self::with_layers(user, project, self.overrides.clone(), themes)
+    }
+
+    // Apply `--set KEY=VALUE` options. `--theme` is a shorthand of `--set window.theme=...`. The overrides are kept
+    // on reloading config files.
+    pub fn merge_options(self, options: &Options) -> Result<Self> {
+        let mut overrides = Mapping::new();
+        for arg in &options.overrides {
+            let Some((path, value)) = arg.split_once('=') else {
+                anyhow::bail!("Value for --set must be in KEY=VALUE format but got {:?}", arg);
+            };
+            // Key binding may contain '.'
+            let keys: Vec<&str> = match path.strip_prefix("keymaps.") {
+                Some(binding) => vec!["keymaps", binding],
+                None => path.split('.').collect(),
+            };
+            // Value is parsed as YAML. Invalid YAML value is treated as a string
+            let value = serde_yaml::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+            let layer = check::check_override(&keys, value)
+                .map_err(|msg| anyhow::anyhow!("Invalid value for --set {:?}: {}", arg, msg))?;
+            merge_mapping(&mut overrides, &layer);
+        }
         if let Some(theme) = options.theme {
-            self.data.window.theme = theme;
+            let layer = check::check_override(&["window", "theme"], serde_yaml::to_value(theme)?)
+                .map_err(anyhow::Error::msg)?;
+            merge_mapping(&mut overrides, &layer);
         }
-        self
+        log::debug!("Config values overridden by options: {:?}", overrides);
+        Self::with_layers(self.user, self.project, overrides, self.themes)
+    }
+
+    pub fn project_config_file(&self) -> Option<&Path> {
+        self.project.as_ref().map(|l| l.path.as_path())
+    }
+
+    pub fn config_files(&self) -> impl Iterator<Item = &'_ Path> {
+        self.user.iter().chain(self.project.iter()).map(|l| l.path.as_path())
+    }
+
+    // Relative CSS path was already resolved on loading the config file
+    pub fn user_css_path(&self) -> Option<&Path> {
+        self.data.preview.css_path()
     }
 
-    pub fn config_file(&self) -> Option<&Path> {
-        self.path.as_deref()
+    pub fn user_themes(&self) -> &UserThemes {
+        &self.themes
     }
 
     pub fn watch(&self) -> &Watch {
         &self.data.watch
     }
 
-    pub fn keymaps(&self) -> &HashMap<String, KeyAction> {
+    pub fn keymaps(&self) -> &HashMap<String, Command> {
         &self.data.keymaps
     }
 
@@ -366,14 +718,27 @@ impl Config {
         &self.data.dialog
     }
 
+    pub fn menu(&self) -> &Menu {
+        &self.data.menu
+    }
+
+    pub fn commands(&self) -> &[ExternalCommand] {
+        &self.data.commands
+    }
+
     pub fn max_recent_files(&self) -> usize {
         self.data.preview.recent_files
     }
+
+    pub fn history_size(&self) -> usize {
+        self.data.preview.history_size.get()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
 
     #[test]
     fn generated_default_config() {
@@ -382,17 +747,193 @@ mod tests {
     }
 
     #[test]
-    fn default_key_mappings() {
-        let mut m = HashMap::new();
-        for (bind, a1) in DEFAULT_KEY_MAPPINGS {
-            if let Some(a2) = m.get(bind) {
-                panic!("default mapping {} conflicts: {:?} vs {:?}", bind, *a1, *a2);
-            }
-            if let Some(i) = bind.find('+') {
-                let modifier = &bind[..i];
-                assert!(matches!(modifier, "ctrl" | "alt"), "invalid modifier {:?}", modifier);
-            }
-            m.insert(*bind, *a1);
+    fn saved_searches() {
+        let yml = [
+            "matcher: SmartCase",
+            "ignore_accents: false",
+            "saved:",
+            "  - name: todo",
+            "    query: TODO",
+            "    matcher: CaseSensitive",
+            "    key: ctrl+t",
+            "  - name: fixme",
+            "    query: FIXME",
+            "    matcher: WholeWord",
+        ]
+        .join("\n");
+        let search: Search = serde_yaml::from_str(&yml).unwrap();
+        let want = [
+            SavedSearch {
+                name: "todo".into(),
+                query: "TODO".into(),
+                matcher: SearchMatcher::CaseSensitive,
+                key: Some("ctrl+t".into()),
+            },
+            SavedSearch {
+                name: "fixme".into(),
+                query: "FIXME".into(),
+                matcher: SearchMatcher::WholeWord,
+                key: None,
+            },
+        ];
+        assert_eq!(search.saved, want);
+    }
+
+    #[test]
+    fn reload_config_file() {
+        let dir = TempDir::new("config-reload");
+        let config = Config::generate_default_config_at(dir.path()).unwrap();
+        let path = config.config_files().next().unwrap().to_path_buf();
+        assert_eq!(config.reload().unwrap().data, ConfigData::default());
+
+        fs::write(&path, "preview:\n  recent_files: 10\n").unwrap();
+        let reloaded = config.reload().unwrap();
+        assert_eq!(reloaded.max_recent_files(), 10);
+        assert_eq!(reloaded.config_files().collect::<Vec<_>>(), [path.as_path()]);
+
+        fs::write(&path, "watch: [").unwrap();
+        assert!(config.reload().is_err());
+
+        // Missing default directory falls back to the default instead of an error
+        fs::write(&path, "dialog:\n  default_dir: /this/directory/does/not/exist\n").unwrap();
+        assert_eq!(config.reload().unwrap().dialog().default_dir(), None);
+
+        // Removed config file keeps the previous config
+        fs::remove_dir_all(dir.path()).unwrap();
+        assert_eq!(reloaded.reload().unwrap().max_recent_files(), 10);
+        assert_eq!(Config::default().reload().unwrap().data, ConfigData::default());
+    }
+
+    #[test]
+    fn user_highlight_themes() {
+        let dir = TempDir::new("config-themes");
+        fs::create_dir_all(dir.join("themes")).unwrap();
+        fs::write(dir.join("themes").join("Corporate.css"), ".hljs {}").unwrap();
+        fs::write(dir.join("themes").join("README.md"), "not a theme").unwrap();
+        fs::write(dir.join("light.css"), ".hljs {}").unwrap();
+        let source = "preview:\n  highlight:\n    dark: Corporate\n    light: light.css\n";
+        fs::write(dir.join("config.yml"), source).unwrap();
+
+        let config = Config::load_dir(dir.path()).unwrap();
+        let themes: Vec<_> = config.user_themes().names().collect();
+        assert_eq!(themes, ["Corporate"]);
+        assert_eq!(
+            config.user_themes().get("Corporate"),
+            Some(dir.join("themes").join("Corporate.css").as_path()),
+        );
+        assert_eq!(config.preview().highlight().dark, "Corporate");
+        assert_eq!(config.preview().highlight().light, dir.join("light.css").to_string_lossy(),);
+
+        // Removed theme is reported on reload
+        fs::remove_file(dir.join("themes").join("Corporate.css")).unwrap();
+        let err = config.reload().unwrap_err();
+        assert!(format!("{}", err).contains("unknown highlight theme `Corporate`"), "{:?}", err);
+    }
+
+    #[test]
+    fn layered_config_files() {
+        let root = TempDir::new("config-layers");
+        let user_dir = root.join("user");
+        let project_dir = root.join("project");
+        let nested_dir = project_dir.join("docs").join("nested");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::create_dir_all(&nested_dir).unwrap();
+
+        let user = [
+            "preview:",
+            "  highlight:",
+            "    dark: Nord",
+            "  css: user.css",
+            "keymaps:",
+            "  j: ScrollDown",
+        ];
+        fs::write(user_dir.join("config.yml"), user.join("\n")).unwrap();
+        let project = [
+            "watch:",
+            "  file_extensions: [txt]",
+            "preview:",
+            "  highlight:",
+            "    light: Xcode",
+            "  css: style/project.css",
+            "keymaps:",
+            "  k: ScrollUp",
+            "commands:",
+            "  - name: Evil",
+            "    run: rm -rf {dir}",
+            "    key: j",
+        ];
+        fs::write(project_dir.join(".shiba.yml"), project.join("\n")).unwrap();
+
+        let config = Config::load_dir(&user_dir).unwrap();
+        assert_eq!(config.preview().highlight().dark, "Nord");
+        assert_eq!(config.preview().highlight().light, "GitHub");
+        assert_eq!(config.user_css_path(), Some(user_dir.join("user.css").as_path()));
+        assert_eq!(config.keymaps().len(), 1);
+        assert_eq!(config.watch(), &Watch::default());
+
+        let found = Config::find_project_config_file(&nested_dir);
+        assert_eq!(found, Some(project_dir.join(".shiba.yml")));
+        let config = config.with_project_config_file(found.as_deref()).unwrap();
+        assert_eq!(config.preview().highlight().dark, "Nord");
+        assert_eq!(config.preview().highlight().light, "Xcode");
+        assert_eq!(
+            config.user_css_path(),
+            Some(project_dir.join("style").join("project.css").as_path()),
+        );
+        assert_eq!(config.watch(), &Watch::default());
+        assert_eq!(config.reload().unwrap().watch(), &Watch::default());
+        assert_eq!(config.keymaps().len(), 2);
+        assert_eq!(config.keymaps().get("j"), Some(&Command::ScrollDown));
+        assert_eq!(config.keymaps().get("k"), Some(&Command::ScrollUp));
+        assert!(config.commands().is_empty());
+        assert!(config.reload().unwrap().commands().is_empty());
+        assert_eq!(config.config_files().count(), 2);
+
+        let config = config.with_project_config_file(None).unwrap();
+        assert_eq!(config.preview().highlight().light, "GitHub");
+        assert_eq!(config.project_config_file(), None);
+
+        // Config files outside the repository are not found
+        let repo_dir = project_dir.join("repo");
+        fs::create_dir_all(repo_dir.join(".git")).unwrap();
+        fs::create_dir_all(repo_dir.join("docs")).unwrap();
+        assert_eq!(Config::find_project_config_file(&repo_dir.join("docs")), None);
+    }
+
+    #[test]
+    fn override_by_options() {
+        let options = Options {
+            theme: Some(WindowTheme::Dark),
+            overrides: vec![
+                "preview.highlight.dark=Nord".into(),
+                "watch.debounce_throttle=200".into(),
+                "watch.file_extensions=[md, txt]".into(),
+                "keymaps.ctrl+q=Quit".into(),
+            ],
+            ..Default::default()
+        };
+        let config = Config::default().merge_options(&options).unwrap();
+        assert_eq!(config.window().theme, WindowTheme::Dark);
+        assert_eq!(config.preview().highlight().dark, "Nord");
+        assert_eq!(config.preview().highlight().light, "GitHub");
+        assert_eq!(config.watch().debounce_throttle(), Duration::from_millis(200));
+        assert!(config.watch().file_extensions().matches(Path::new("foo.txt")));
+        assert_eq!(config.keymaps().get("ctrl+q"), Some(&Command::Quit));
+        assert_eq!(config.keymaps().get("j"), Some(&Command::ScrollDown));
+
+        // Overrides are kept after reloading config
+        let config = config.reload().unwrap();
+        assert_eq!(config.preview().highlight().dark, "Nord");
+
+        for arg in [
+            "preview.highlight",
+            "preview.higlight.dark=Nord",
+            "watch.debounce_throttle=fast",
+            "preview.history_size=0",
+        ] {
+            let options = Options { overrides: vec![arg.into()], ..Default::default() };
+            let err = Config::default().merge_options(&options).unwrap_err();
+            assert!(format!("{}", err).contains(arg), "{:?}", err);
         }
     }
 
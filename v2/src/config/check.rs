@@ -0,0 +1,791 @@
+use super::{is_theme_file, ConfigData, UserThemes, DEFAULT_CONFIG_FILE};
+use crate::assets::hljs_theme_names;
+use crate::command::{Accelerator, Command, COMMANDS};
+use crate::external::split_command_line;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const MODIFIERS: &[&str] = &["ctrl", "alt", "shift", "meta", "mod", "command", "option"];
+// Keys of these mappings are arbitrary
+const FREE_FORM_KEYS: &[&[&str]] = &[&["keymaps"], &["menu", "accelerators"]];
+#[rustfmt::skip]
+const NAMED_KEYS: &[&str] = &[
+    "backspace", "tab", "enter", "return", "capslock", "esc", "escape", "space", "pageup", "pagedown", "end", "home",
+    "left", "up", "right", "down", "ins", "del", "plus",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigProblem {
+    path: PathBuf,
+    position: Option<(usize, usize)>, // 1-based line and column
+    message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs: Vec<char> = rhs.chars().collect();
+    let mut prev: Vec<usize> = (0..=rhs.len()).collect();
+    for (i, l) in lhs.chars().enumerate() {
+        let mut cur = vec![i + 1; rhs.len() + 1];
+        for (j, r) in rhs.iter().enumerate() {
+            let cost = usize::from(!l.eq_ignore_ascii_case(r));
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[rhs.len()]
+}
+
+fn did_you_mean<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> String {
+    let threshold = (input.chars().count() / 3).max(1);
+    let nearest = candidates
+        .map(|c| (edit_distance(input, c), c))
+        .filter(|(d, _)| *d <= threshold)
+        .min_by_key(|(d, _)| *d);
+    match nearest {
+        Some((_, c)) => format!(". Did you mean `{}`?", c),
+        None => String::new(),
+    }
+}
+
+// Get the variant names of the enum from its `Deserialize` implementation
+fn variant_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    struct Capture<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de, 'a> Deserializer<'de> for Capture<'a> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not an enum"))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            variants: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = variants;
+            Err(de::Error::custom("variants were captured"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+        }
+    }
+
+    let mut variants: &'static [&'static str] = &[];
+    let _ = T::deserialize(Capture(&mut variants));
+    variants
+}
+
+// Key of the mapping entry in the line whose indentation was trimmed
+fn line_key(line: &str) -> Option<&str> {
+    let (key, rest) = if let Some(s) = line.strip_prefix('"') {
+        s.split_once('"')?
+    } else if let Some(s) = line.strip_prefix('\'') {
+        s.split_once('\'')?
+    } else {
+        let idx = line.find(':')?;
+        (line[..idx].trim_end(), &line[idx..])
+    };
+    rest.trim_start().starts_with(':').then_some(key)
+}
+
+// Find the position of the key at the path in block-style YAML source. Keys in flow-style mappings are not found.
+fn key_position(source: &str, path: &[&str]) -> Option<(usize, usize)> {
+    if path.is_empty() {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut parent_indent = None;
+    let mut child_indent = None;
+    for (idx, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if let Some(parent) = parent_indent {
+            if indent <= parent {
+                return None; // Reached the end of the parent mapping
+            }
+        }
+        if *child_indent.get_or_insert(indent) != indent {
+            continue;
+        }
+        if line_key(trimmed) == Some(path[depth]) {
+            depth += 1;
+            if depth == path.len() {
+                return Some((idx + 1, indent + 1));
+            }
+            parent_indent = Some(indent);
+            child_indent = None;
+        }
+    }
+    None
+}
+
+// Find the position of the key in the nth item of the block-style sequence at the path. When the key is not found in
+// the item, the position of the item is returned.
+fn item_key_position(
+    source: &str,
+    path: &[&str],
+    index: usize,
+    key: &str,
+) -> Option<(usize, usize)> {
+    let (line, column) = key_position(source, path)?;
+    let parent_indent = column - 1;
+    let mut item_indent = None;
+    let mut key_indent = None;
+    let mut item_position = None;
+    let mut count = 0;
+    for (idx, line) in source.lines().enumerate().skip(line) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let is_item = trimmed == "-" || trimmed.starts_with("- ");
+        // Items of the sequence may have the same indentation as its parent key
+        if indent < parent_indent || indent == parent_indent && !is_item {
+            break;
+        }
+        if is_item && *item_indent.get_or_insert(indent) == indent {
+            if item_position.is_some() {
+                break; // Reached the next item
+            }
+            count += 1;
+            if count <= index {
+                continue;
+            }
+            item_position = Some((idx + 1, indent + 1));
+            let rest = trimmed[1..].trim_start();
+            if !rest.is_empty() {
+                let indent = line.len() - rest.len();
+                if line_key(rest) == Some(key) {
+                    return Some((idx + 1, indent + 1));
+                }
+                key_indent = Some(indent);
+            }
+        } else if item_position.is_some()
+            && *key_indent.get_or_insert(indent) == indent
+            && line_key(trimmed) == Some(key)
+        {
+            return Some((idx + 1, indent + 1));
+        }
+    }
+    item_position
+}
+
+// Mousetrap key binding like "ctrl+shift+f" or "g g". Returns the normalized key binding to detect conflicts.
+fn normalize_key_binding(binding: &str) -> Result<String, String> {
+    let mut normalized = vec![];
+    for combo in binding.split(' ').filter(|s| !s.is_empty()) {
+        let mut keys: Vec<&str> = combo.split('+').collect();
+        let key = keys.pop().unwrap_or_default();
+        let mut modifiers = vec![];
+        for modifier in keys {
+            let normalized = match modifier {
+                "command" => "meta",
+                "option" => "alt",
+                m if MODIFIERS.contains(&m) => m,
+                m => {
+                    let suggestion = did_you_mean(m, MODIFIERS.iter().copied());
+                    return Err(format!(
+                        "unknown modifier `{}` in key binding `{}`{}",
+                        m, binding, suggestion
+                    ));
+                }
+            };
+            if modifiers.contains(&normalized) {
+                return Err(format!(
+                    "modifier `{}` is duplicated in key binding `{}`",
+                    modifier, binding
+                ));
+            }
+            modifiers.push(normalized);
+        }
+        let key = match key {
+            "return" => "enter",
+            "escape" => "esc",
+            k if k.chars().count() == 1 || NAMED_KEYS.contains(&k) => k,
+            k if k
+                .strip_prefix('f')
+                .and_then(|n| n.parse::<u8>().ok())
+                .is_some_and(|n| (1..=19).contains(&n)) =>
+            {
+                k
+            }
+            k => {
+                let suggestion = did_you_mean(k, NAMED_KEYS.iter().copied());
+                return Err(format!(
+                    "unknown key `{}` in key binding `{}`{}",
+                    k, binding, suggestion
+                ));
+            }
+        };
+        modifiers.sort_unstable();
+        modifiers.push(key);
+        normalized.push(modifiers.join("+"));
+    }
+    if normalized.is_empty() {
+        return Err("key binding is empty".into());
+    }
+    Ok(normalized.join(" "))
+}
+
+struct Checker<'a> {
+    path: &'a Path,
+    source: &'a str,
+    themes: &'a UserThemes,
+    problems: Vec<ConfigProblem>,
+}
+
+impl<'a> Checker<'a> {
+    fn report(&mut self, keys: &[&str], message: String) {
+        let position = key_position(self.source, keys);
+        self.problems.push(ConfigProblem { path: self.path.to_path_buf(), position, message });
+    }
+
+    fn report_item(&mut self, keys: &[&str], index: usize, key: &str, message: String) {
+        let position = item_key_position(self.source, keys, index, key);
+        self.problems.push(ConfigProblem { path: self.path.to_path_buf(), position, message });
+    }
+
+    fn report_yaml_error(&mut self, err: serde_yaml::Error) {
+        let position = err.location().map(|l| (l.line(), l.column()));
+        let mut message = err.to_string();
+        if let Some((line, column)) = position {
+            // Location is shown separately
+            let location = format!(" at line {} column {}", line, column);
+            message = message.replacen(&location, "", 1);
+        }
+        self.problems.push(ConfigProblem { path: self.path.to_path_buf(), position, message });
+    }
+
+    fn check_keys<'k>(&mut self, value: &'k Mapping, schema: &Mapping, keys: &mut Vec<&'k str>) {
+        for (key, value) in value {
+            let Some(key) = key.as_str() else {
+                self.report(keys, format!("key must be a string but got {:?}", key));
+                continue;
+            };
+            keys.push(key);
+            match schema.get(key) {
+                None => {
+                    let suggestion = did_you_mean(key, schema.keys().filter_map(Value::as_str));
+                    let message = format!("unknown key `{}`{}", keys.join("."), suggestion);
+                    self.report(keys, message);
+                }
+                Some(Value::Mapping(schema)) if !FREE_FORM_KEYS.contains(&keys.as_slice()) => {
+                    if let Value::Mapping(value) = value {
+                        self.check_keys(value, schema, keys);
+                    }
+                }
+                _ => {}
+            }
+            keys.pop();
+        }
+    }
+
+    // Returns the problem of the key binding. The caller reports it at the position of the binding
+    fn key_binding_problem(binding: &str, bound: &mut HashMap<String, String>) -> Option<String> {
+        match normalize_key_binding(binding) {
+            Ok(normalized) => {
+                if let Some(prev) = bound.get(&normalized) {
+                    Some(format!("key binding `{}` conflicts with `{}`", binding, prev))
+                } else {
+                    bound.insert(normalized, binding.to_string());
+                    None
+                }
+            }
+            Err(message) => Some(message),
+        }
+    }
+
+    fn check_keymaps(&mut self, config: &Mapping) {
+        let mut bound = HashMap::new();
+        if let Some(Value::Mapping(keymaps)) = config.get("keymaps") {
+            let actions = variant_names::<Command>();
+            for (binding, action) in keymaps {
+                let Some(binding) = binding.as_str() else {
+                    continue; // Reported as type error
+                };
+                let keys = ["keymaps", binding];
+                if let Some(message) = Self::key_binding_problem(binding, &mut bound) {
+                    self.report(&keys, message);
+                }
+                if let Some(action) = action.as_str() {
+                    if !actions.contains(&action) {
+                        let suggestion = did_you_mean(action, actions.iter().copied());
+                        self.report(
+                            &keys,
+                            format!("unknown key action `{}`{}", action, suggestion),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Sequence(commands)) = config.get("commands") {
+            for (idx, item) in commands.iter().enumerate() {
+                if let Some(key) = item.get("key").and_then(Value::as_str) {
+                    if let Some(message) = Self::key_binding_problem(key, &mut bound) {
+                        self.report_item(&["commands"], idx, "key", message);
+                    }
+                }
+            }
+        }
+
+        let Some(Value::Mapping(search)) = config.get("search") else {
+            return;
+        };
+        let Some(Value::Sequence(saved)) = search.get("saved") else {
+            return;
+        };
+        for (idx, item) in saved.iter().enumerate() {
+            if let Some(key) = item.get("key").and_then(Value::as_str) {
+                if let Some(message) = Self::key_binding_problem(key, &mut bound) {
+                    self.report_item(&["search", "saved"], idx, "key", message);
+                }
+            }
+        }
+    }
+
+    fn check_external_commands(&mut self, config: &Mapping) {
+        let Some(Value::Sequence(commands)) = config.get("commands") else {
+            return;
+        };
+        for (idx, item) in commands.iter().enumerate() {
+            if let Some(name) = item.get("name").and_then(Value::as_str) {
+                if name.trim().is_empty() {
+                    let message = "name of command must not be empty".into();
+                    self.report_item(&["commands"], idx, "name", message);
+                }
+            }
+            if let Some(run) = item.get("run").and_then(Value::as_str) {
+                if let Err(message) = split_command_line(run) {
+                    self.report_item(&["commands"], idx, "run", message);
+                }
+            }
+        }
+    }
+
+    fn check_accelerators(&mut self, config: &Mapping) {
+        let Some(Value::Mapping(menu)) = config.get("menu") else {
+            return;
+        };
+        let Some(Value::Mapping(accels)) = menu.get("accelerators") else {
+            return;
+        };
+
+        let mut overridden = HashMap::new();
+        for (name, accel) in accels {
+            let Some(name) = name.as_str() else {
+                continue; // Reported as type error
+            };
+            let keys = ["menu", "accelerators", name];
+            let Ok(command) = serde_yaml::from_value::<Command>(Value::String(name.into())) else {
+                let commands = variant_names::<Command>();
+                let suggestion = did_you_mean(name, commands.iter().copied());
+                self.report(&keys, format!("unknown command `{}`{}", name, suggestion));
+                continue;
+            };
+            let accel = match accel {
+                Value::Null => None,
+                Value::String(accel) => match accel.parse::<Accelerator>() {
+                    Ok(parsed) => Some((parsed, accel.as_str())),
+                    Err(message) => {
+                        self.report(&keys, message);
+                        continue;
+                    }
+                },
+                _ => continue, // Reported as type error
+            };
+            overridden.insert(command, accel);
+        }
+
+        // Check conflicts including the default accelerators of other menu items
+        let mut bound = HashMap::new();
+        for spec in COMMANDS {
+            let accel = match overridden.get(&spec.command) {
+                Some(accel) => accel.map(|(a, _)| a),
+                None => spec.command.default_accelerator(),
+            };
+            let Some(accel) = accel else {
+                continue;
+            };
+            let Some(&prev) = bound.get(&accel) else {
+                bound.insert(accel, spec.command);
+                continue;
+            };
+            // Default accelerators never conflict. Report the conflict at the accelerator in this config file
+            let (command, other) = if overridden.contains_key(&spec.command) {
+                (spec.command, prev)
+            } else {
+                (prev, spec.command)
+            };
+            let Some(Some((_, text))) = overridden.get(&command) else {
+                continue;
+            };
+            let name = format!("{:?}", command);
+            let message =
+                format!("accelerator `{}` of `{}` conflicts with `{:?}`", text, name, other);
+            self.report(&["menu", "accelerators", &name], message);
+        }
+    }
+
+    fn check_highlight_themes(&mut self, config: &Mapping) {
+        let Some(Value::Mapping(preview)) = config.get("preview") else {
+            return;
+        };
+        let Some(Value::Mapping(highlight)) = preview.get("highlight") else {
+            return;
+        };
+        for key in ["dark", "light"] {
+            let Some(name) = highlight.get(key).and_then(Value::as_str) else {
+                continue;
+            };
+            if is_theme_file(name) {
+                // Theme file is relative to the directory of the config file
+                let dir = self.path.parent().unwrap_or(Path::new(""));
+                if !dir.join(name).is_file() {
+                    let message = format!("highlight theme file `{}` does not exist", name);
+                    self.report(&["preview", "highlight", key], message);
+                }
+                continue;
+            }
+            let themes = self.themes;
+            let mut names: Vec<&str> = themes.names().collect();
+            for n in hljs_theme_names() {
+                names.push(n);
+            }
+            if !names.contains(&name) {
+                let suggestion = did_you_mean(name, names.into_iter());
+                let message = format!("unknown highlight theme `{}`{}", name, suggestion);
+                self.report(&["preview", "highlight", key], message);
+            }
+        }
+    }
+
+    fn check(mut self) -> Result<Mapping, Vec<ConfigProblem>> {
+        let config = match serde_yaml::from_str(self.source) {
+            Ok(Value::Mapping(config)) => config,
+            Ok(Value::Null) => return Ok(Mapping::new()), // Empty file
+            Ok(_) => {
+                self.report(&[], "config must be a YAML mapping".into());
+                return Err(self.problems);
+            }
+            Err(err) => {
+                self.report_yaml_error(err);
+                return Err(self.problems);
+            }
+        };
+
+        let schema: Mapping = serde_yaml::from_str(DEFAULT_CONFIG_FILE).unwrap();
+        self.check_keys(&config, &schema, &mut vec![]);
+        self.check_keymaps(&config);
+        self.check_accelerators(&config);
+        self.check_external_commands(&config);
+        self.check_highlight_themes(&config);
+
+        // Type errors are checked at last since serde reports only the first error
+        if self.problems.is_empty() {
+            if let Err(err) = serde_yaml::from_str::<ConfigData>(self.source) {
+                self.report_yaml_error(err);
+            }
+        }
+
+        if self.problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(self.problems)
+        }
+    }
+}
+
+// Check the value at the key path against the config schema. Returns the config mapping which only has the value.
+pub fn check_override(keys: &[&str], value: Value) -> Result<Mapping, String> {
+    let schema: Mapping = serde_yaml::from_str(DEFAULT_CONFIG_FILE).unwrap();
+    let mut current = &schema;
+    for (idx, key) in keys.iter().enumerate() {
+        if FREE_FORM_KEYS.contains(&&keys[..idx]) {
+            if keys[0] == "keymaps" {
+                normalize_key_binding(key)?;
+            }
+            break; // Command names of accelerators are checked on deserialization
+        }
+        match current.get(*key) {
+            Some(Value::Mapping(mapping)) => current = mapping,
+            Some(_) if idx + 1 < keys.len() => {
+                return Err(format!("`{}` is not a mapping", keys[..=idx].join(".")));
+            }
+            Some(_) => {}
+            None => {
+                let suggestion = did_you_mean(key, current.keys().filter_map(Value::as_str));
+                return Err(format!("unknown key `{}`{}", keys[..=idx].join("."), suggestion));
+            }
+        }
+    }
+
+    let mut config = value;
+    for key in keys.iter().rev() {
+        let mut mapping = Mapping::new();
+        mapping.insert(Value::String(key.to_string()), config);
+        config = Value::Mapping(mapping);
+    }
+    if let Err(err) = serde_yaml::from_value::<ConfigData>(config.clone()) {
+        return Err(err.to_string());
+    }
+    match config {
+        Value::Mapping(config) => Ok(config),
+        _ => unreachable!("config must be a mapping"),
+    }
+}
+
+// Check the content of the config file and report all problems in it
+pub fn check_config_source(
+    path: &Path,
+    source: &str,
+    themes: &UserThemes,
+) -> Result<Mapping, Vec<ConfigProblem>> {
+    Checker { path, source, themes, problems: vec![] }.check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(source: &str) -> Vec<String> {
+        match check_config_source(Path::new("config.yml"), source, &UserThemes::default()) {
+            Ok(_) => vec![],
+            Err(problems) => problems.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn valid_config() {
+        assert_eq!(problems(DEFAULT_CONFIG_FILE), Vec::<String>::new());
+        assert_eq!(problems(""), Vec::<String>::new());
+        assert_eq!(problems("preview:\n  recent_files: 10\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let source = [
+            "preview:",
+            "  higlight:",
+            "    dark: Nord",
+            "  recent_files: 10",
+            "widnow:",
+            "  theme: Dark",
+            "foo: 42",
+        ]
+        .join("\n");
+        let want = [
+            "config.yml:2:3: unknown key `preview.higlight`. Did you mean `highlight`?",
+            "config.yml:5:1: unknown key `widnow`. Did you mean `window`?",
+            "config.yml:7:1: unknown key `foo`",
+        ];
+        assert_eq!(problems(&source), want);
+    }
+
+    #[test]
+    fn invalid_keymaps() {
+        let source = [
+            "keymaps:",
+            "  j: ScrollDwn",
+            "  ctl+k: ScrollUp",
+            "  ctrl+shift+f: Search",
+            "  shift+ctrl+f: Search",
+            "  \"?\": Help",
+            "  ctrl+pagedwn: ScrollBottom",
+            "search:",
+            "  saved:",
+            "    - name: todo",
+            "      query: TODO",
+            "      matcher: SmartCase",
+            "      key: j",
+            "    - name: fixme",
+            "      query: FIXME",
+            "      key: ctl+x",
+        ]
+        .join("\n");
+        let want = [
+            "config.yml:2:3: unknown key action `ScrollDwn`. Did you mean `ScrollDown`?",
+            "config.yml:3:3: unknown modifier `ctl` in key binding `ctl+k`. Did you mean `ctrl`?",
+            "config.yml:5:3: key binding `shift+ctrl+f` conflicts with `ctrl+shift+f`",
+            "config.yml:7:3: unknown key `pagedwn` in key binding `ctrl+pagedwn`. Did you mean `pagedown`?",
+            "config.yml:13:7: key binding `j` conflicts with `j`",
+            "config.yml:16:7: unknown modifier `ctl` in key binding `ctl+x`. Did you mean `ctrl`?",
+        ];
+        assert_eq!(problems(&source), want);
+    }
+
+    #[test]
+    fn invalid_accelerators() {
+        let source = [
+            "menu:",
+            "  accelerators:",
+            "    Outlin: mod+shift+s",
+            "    Reload: mod+foo",
+            "    Print: hyper+p",
+            "    Sidebar: mod+f",
+            "    Quit: null",
+            "    History: mod+q",
+        ]
+        .join("\n");
+        let want = [
+            "config.yml:3:5: unknown command `Outlin`. Did you mean `Outline`?",
+            "config.yml:4:5: unknown key `foo` in accelerator `mod+foo`",
+            "config.yml:5:5: unknown modifier `hyper` in accelerator `hyper+p`",
+            "config.yml:6:5: accelerator `mod+f` of `Sidebar` conflicts with `Search`",
+        ];
+        assert_eq!(problems(&source), want);
+    }
+
+    #[test]
+    fn invalid_external_commands() {
+        let source = [
+            "keymaps:",
+            "  ctrl+l: Reload",
+            "commands:",
+            "  - name: Format",
+            "    run: prettier --write '{path}",
+            "    key: ctrl+l",
+            "  - name: ''",
+            "    run: ''",
+            "    key: ctrl+shift+l",
+        ]
+        .join("\n");
+        let want = [
+            "config.yml:6:5: key binding `ctrl+l` conflicts with `ctrl+l`",
+            "config.yml:5:5: quote ' is not closed in command line `prettier --write '{path}`",
+            "config.yml:7:5: name of command must not be empty",
+            "config.yml:8:5: command line is empty",
+        ];
+        assert_eq!(problems(&source), want);
+
+        // Items at the same indentation as the parent key and keys in the following lines of `-`
+        let source =
+            ["commands:", "- name: Open", "  run: open {path}", "-", "  run: ''", "  name: Copy"]
+                .join("\n");
+        assert_eq!(problems(&source), ["config.yml:5:3: command line is empty"]);
+    }
+
+    #[test]
+    fn unknown_highlight_theme() {
+        let source = "preview:\n  highlight:\n    dark: Github Drak\n    light: No Such Theme\n";
+        let want = [
+            "config.yml:3:5: unknown highlight theme `Github Drak`. Did you mean `Github Dark`?",
+            "config.yml:4:5: unknown highlight theme `No Such Theme`",
+        ];
+        assert_eq!(problems(source), want);
+
+        let source = "preview:\n  highlight:\n    dark: themes/no-such-theme.css\n";
+        let want =
+            ["config.yml:3:5: highlight theme file `themes/no-such-theme.css` does not exist"];
+        assert_eq!(problems(source), want);
+    }
+
+    #[test]
+    fn type_and_syntax_errors() {
+        let source = "watch:\n  debounce_throttle: fast\n";
+        let want = [
+            "config.yml:2:22: watch.debounce_throttle: invalid type: string \"fast\", expected u32",
+        ];
+        assert_eq!(problems(source), want);
+
+        let source = "watch: [";
+        let want =
+            ["config.yml:2:1: did not find expected node content, while parsing a flow node"];
+        assert_eq!(problems(source), want);
+
+        assert_eq!(problems("42"), ["config.yml: config must be a YAML mapping"]);
+    }
+
+    #[test]
+    fn key_action_variants() {
+        let variants = variant_names::<Command>();
+        assert!(variants.contains(&"ScrollDown"), "{:?}", variants);
+        assert!(variants.contains(&"Quit"), "{:?}", variants);
+    }
+
+    #[test]
+    fn override_values() {
+        let ok = [
+            (&["preview", "highlight", "dark"][..], Value::String("Nord".into())),
+            (&["watch", "debounce_throttle"][..], Value::Number(200.into())),
+            (&["keymaps", "ctrl+."][..], Value::String("Quit".into())),
+            (&["dialog", "default_dir"][..], Value::Null),
+            (&["menu", "accelerators", "Reload"][..], Value::Null),
+            (&["menu", "accelerators", "Outline"][..], Value::String("mod+shift+s".into())),
+        ];
+        for (keys, value) in ok {
+            let config = check_override(keys, value.clone()).unwrap();
+            let mut current = &Value::Mapping(config);
+            for key in keys {
+                current = current.get(key).unwrap();
+            }
+            assert_eq!(current, &value, "{:?}", keys);
+        }
+
+        let err = [
+            (
+                &["preview", "higlight", "dark"][..],
+                Value::Null,
+                "unknown key `preview.higlight`. Did you mean `highlight`?",
+            ),
+            (
+                &["watch", "debounce_throttle", "foo"][..],
+                Value::Null,
+                "`watch.debounce_throttle` is not a mapping",
+            ),
+            (
+                &["watch", "debounce_throttle"][..],
+                Value::String("fast".into()),
+                "invalid type: string \"fast\", expected u32",
+            ),
+            (
+                &["keymaps", "ctl+x"][..],
+                Value::String("Quit".into()),
+                "unknown modifier `ctl` in key binding `ctl+x`. Did you mean `ctrl`?",
+            ),
+            (
+                &["menu", "accelerators", "Outline"][..],
+                Value::String("mod+shift+foo".into()),
+                "unknown key `foo` in accelerator `mod+shift+foo`",
+            ),
+        ];
+        for (keys, value, want) in err {
+            let have = check_override(keys, value).unwrap_err();
+            assert!(have.contains(want), "{:?} vs {:?}", have, want);
+        }
+    }
+
+    #[test]
+    fn key_binding_syntax() {
+        assert_eq!(normalize_key_binding("ctrl+shift+f").unwrap(), "ctrl+shift+f");
+        assert_eq!(normalize_key_binding("shift+ctrl+f").unwrap(), "ctrl+shift+f");
+        assert_eq!(normalize_key_binding("command+return").unwrap(), "meta+enter");
+        assert_eq!(normalize_key_binding("g g").unwrap(), "g g");
+        assert_eq!(normalize_key_binding("f12").unwrap(), "f12");
+        assert!(normalize_key_binding("f20").is_err());
+        assert!(normalize_key_binding("ctrl+ctrl+a").is_err());
+        assert!(normalize_key_binding("").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::renderer::{EventChannel, UserEvent};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+const MAX_ERROR_LINES: usize = 10;
+
+// Split the command line template into arguments. Arguments are separated by whitespaces. Quotes are available for
+// arguments including whitespaces and backslash escapes the next character outside single quotes. No shell is involved.
+pub fn split_command_line(template: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => arg.push(c),
+            (_, '\\') => match chars.next() {
+                Some(c) => arg.push(c),
+                None => return Err(format!("command line `{}` ends with backslash", template)),
+            },
+            (Some(_), c) => arg.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("quote {} is not closed in command line `{}`", q, template));
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    if args.is_empty() {
+        return Err("command line is empty".into());
+    }
+    Ok(args)
+}
+
+// Values for placeholders in command line templates
+pub struct Placeholders<'a> {
+    pub path: &'a Path,
+    pub line: usize,
+    pub selection: &'a str,
+}
+
+// Quote the value for POSIX shells so that it is passed to `sh -c` as a single word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+impl<'a> Placeholders<'a> {
+    // `{name:sh}` is the value quoted for shells. Values are pasted as-is otherwise, so selected text passed to a shell
+    // without quoting could run arbitrary commands.
+    fn value(&self, name: &str) -> Option<String> {
+        if let Some(name) = name.strip_suffix(":sh") {
+            return self.value(name).map(|v| shell_quote(&v));
+        }
+        match name {
+            "path" => Some(self.path.to_string_lossy().into_owned()),
+            "dir" => {
+                Some(self.path.parent().unwrap_or(Path::new("")).to_string_lossy().into_owned())
+            }
+            "line" => Some(self.line.to_string()),
+            "selection" => Some(self.selection.to_string()),
+            _ => None,
+        }
+    }
+
+    // Placeholders are replaced in a single pass from left to right so that placeholders in the substituted values
+    // are kept as-is
+    fn expand(&self, arg: &str) -> String {
+        let mut expanded = String::with_capacity(arg.len());
+        let mut rest = arg;
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest.find('}').and_then(|end| Some((end, self.value(&rest[1..end])?)));
+            if let Some((end, value)) = value {
+                expanded.push_str(&value);
+                rest = &rest[end + 1..];
+            } else {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+}
+
+fn error_message(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(MAX_ERROR_LINES)..].join("\n")
+}
+
+// Run the command in a separate thread. The result is notified with `UserEvent::ExternalCommandExited`
+pub fn spawn<C: EventChannel>(
+    name: &str,
+    template: &str,
+    placeholders: &Placeholders<'_>,
+    reload: bool,
+    channel: C,
+) -> Result<(), String> {
+    let args: Vec<String> =
+        split_command_line(template)?.iter().map(|arg| placeholders.expand(arg)).collect();
+    let path = placeholders.path.to_path_buf();
+    let dir = path.parent().map(Path::to_path_buf);
+    let name = name.to_string();
+    log::debug!("Running external command {:?}: {:?}", name, args);
+
+    thread::spawn(move || {
+        let mut command = Command::new(&args[0]);
+        command.args(&args[1..]).stdin(Stdio::null()).stdout(Stdio::null());
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        let error = match command.output() {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => {
+                let stderr = error_message(&output.stderr);
+                Some(format!("{} ({})\n{}", name, output.status, stderr).trim_end().to_string())
+            }
+            Err(err) => Some(format!("{}: Could not run {:?}: {}", name, args[0], err)),
+        };
+        log::debug!("External command {:?} exited (error={:?})", name, error);
+        channel.send_event(UserEvent::ExternalCommandExited { name, path, reload, error });
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_command_line_args() {
+        for (input, want) in [
+            ("prettier --write {path}", &["prettier", "--write", "{path}"][..]),
+            ("  open   {dir} ", &["open", "{dir}"]),
+            ("sh -c 'echo {selection:sh} | pbcopy'", &["sh", "-c", "echo {selection:sh} | pbcopy"]),
+            ("echo \"a b\"c ''", &["echo", "a bc", ""]),
+            ("echo a\\ b \"\\\"\" '\\'", &["echo", "a b", "\"", "\\"]),
+        ] {
+            assert_eq!(split_command_line(input).unwrap(), want, "input={:?}", input);
+        }
+
+        for (input, want) in [
+            ("", "command line is empty"),
+            ("echo 'foo", "quote ' is not closed in command line `echo 'foo`"),
+            ("echo foo\\", "command line `echo foo\\` ends with backslash"),
+        ] {
+            assert_eq!(split_command_line(input).unwrap_err(), want, "input={:?}", input);
+        }
+    }
+
+    #[test]
+    fn expand_placeholders() {
+        let path = Path::new("/path/to/README.md");
+        let p = Placeholders { path, line: 42, selection: "hello world" };
+        assert_eq!(p.expand("{path}"), "/path/to/README.md");
+        assert_eq!(p.expand("--dir={dir}"), "--dir=/path/to");
+        assert_eq!(p.expand("+{line}"), "+42");
+        assert_eq!(p.expand("{selection}"), "hello world");
+        assert_eq!(p.expand("{unknown} text"), "{unknown} text");
+        assert_eq!(p.expand("{{line}}:{line"), "{42}:{line");
+
+        // Values are quoted for shells with `:sh`
+        let p = Placeholders { path, line: 42, selection: "it's $(rm -rf ~)" };
+        assert_eq!(p.expand("echo {selection:sh}"), r"echo 'it'\''s $(rm -rf ~)'");
+        assert_eq!(p.expand("{path:sh}"), "'/path/to/README.md'");
+        assert_eq!(p.expand("{unknown:sh}"), "{unknown:sh}");
+
+        // Placeholders in the substituted values are not expanded again
+        let path = Path::new("/path/{selection}/{line}.md");
+        let p = Placeholders { path, line: 42, selection: "{path}" };
+        assert_eq!(p.expand("{path} {selection}"), "/path/{selection}/{line}.md {path}");
+        assert_eq!(p.expand("{dir}"), "/path/{selection}");
+    }
+
+    #[test]
+    fn last_lines_of_stderr() {
+        assert_eq!(error_message(b"error: oops\n"), "error: oops");
+        let stderr: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        let want: Vec<String> = (10..20).map(|i| format!("line {}", i)).collect();
+        assert_eq!(error_message(stderr.as_bytes()), want.join("\n"));
+    }
+}
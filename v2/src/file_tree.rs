@@ -0,0 +1,254 @@
+use crate::config::{FileExtensions, Watch};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(tag = "kind")]
+#[serde(rename_all = "snake_case")]
+pub enum FileTreeEntry<'a> {
+    File { name: Cow<'a, str>, path: &'a Path },
+    Dir { name: Cow<'a, str>, path: &'a Path, children: Vec<FileTreeEntry<'a>> },
+}
+
+impl<'a> FileTreeEntry<'a> {
+    fn name(&self) -> &str {
+        match self {
+            Self::File { name, .. } | Self::Dir { name, .. } => name,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self, Self::Dir { .. })
+    }
+}
+
+fn file_name(path: &Path) -> Cow<'_, str> {
+    path.file_name().map(|n| n.to_string_lossy()).unwrap_or_else(|| path.to_string_lossy())
+}
+
+// Build entries in `dir` consuming sorted file paths. Since `BTreeSet<PathBuf>` is sorted by path components, all files
+// in the same directory appear consecutively.
+fn build_entries<'a, I>(dir: &'a Path, files: &mut Peekable<I>) -> Vec<FileTreeEntry<'a>>
+where
+    I: Iterator<Item = &'a PathBuf>,
+{
+    let mut entries = vec![];
+    while let Some(file) = files.peek() {
+        if !file.starts_with(dir) {
+            break;
+        }
+        let Some(child) = file.ancestors().find(|p| p.parent() == Some(dir)) else {
+            files.next(); // `file` is `dir` itself
+            continue;
+        };
+        if child == file.as_path() {
+            entries.push(FileTreeEntry::File { name: file_name(child), path: child });
+            files.next();
+        } else {
+            let children = build_entries(child, files);
+            entries.push(FileTreeEntry::Dir { name: file_name(child), path: child, children });
+        }
+    }
+    // Directories first, then files
+    entries.sort_by(|l, r| r.is_dir().cmp(&l.is_dir()).then_with(|| l.name().cmp(r.name())));
+    entries
+}
+
+// Markdown files in watched directories. This is maintained by the application with file system events from watcher.
+pub struct FileTree {
+    extensions: FileExtensions,
+    ignore_dirs: Vec<String>,
+    roots: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl FileTree {
+    pub fn new(config: &Watch) -> Self {
+        let extensions = config.file_extensions().clone();
+        let ignore_dirs = config.ignore_dirs().to_vec();
+        Self { extensions, ignore_dirs, roots: BTreeMap::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        path.components().any(|c| self.ignore_dirs.iter().any(|d| c.as_os_str() == d.as_str()))
+    }
+
+    fn walk(&self, dir: &Path, files: &mut BTreeSet<PathBuf>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::debug!("Could not read directory {:?} for file tree: {}", dir, err);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                if !self.ignore_dirs.iter().any(|d| entry.file_name() == d.as_str()) {
+                    self.walk(&path, files);
+                }
+            } else if self.extensions.matches(&path) {
+                files.insert(path);
+            }
+        }
+    }
+
+    fn files_mut(&mut self, path: &Path) -> Option<&mut BTreeSet<PathBuf>> {
+        self.roots.iter_mut().find(|(root, _)| path.starts_with(root)).map(|(_, files)| files)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = &'_ Path> {
+        self.roots.keys().map(PathBuf::as_path)
+    }
+
+    pub fn add_root(&mut self, dir: PathBuf) -> bool {
+        if !dir.is_dir() || self.roots.keys().any(|root| dir.starts_with(root)) {
+            return false; // Files in the directory are already collected
+        }
+        // Merge roots which are nested in the new root
+        self.roots.retain(|root, _| !root.starts_with(&dir));
+
+        let mut files = BTreeSet::new();
+        self.walk(&dir, &mut files);
+        log::debug!("Collected {} files in {:?} for file tree", files.len(), dir);
+        self.roots.insert(dir, files);
+        true
+    }
+
+    // Reflect the file system change at the path to the tree. Returns true when the tree was changed.
+    pub fn update(&mut self, path: &Path) -> bool {
+        let Some(root) = self.roots.keys().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        if self.is_ignored(path.strip_prefix(root).unwrap_or(path)) {
+            return false;
+        }
+
+        if path.is_dir() {
+            let mut found = BTreeSet::new();
+            self.walk(path, &mut found);
+            let Some(files) = self.files_mut(path) else {
+                return false;
+            };
+            let len = files.len();
+            files.extend(found);
+            files.len() != len
+        } else if path.is_file() {
+            if !self.extensions.matches(path) {
+                return false;
+            }
+            let Some(files) = self.files_mut(path) else {
+                return false;
+            };
+            files.insert(path.to_path_buf())
+        } else {
+            // The path was removed. When it was a directory, all files in it were also removed.
+            let Some(files) = self.files_mut(path) else {
+                return false;
+            };
+            let len = files.len();
+            files.retain(|f| !f.starts_with(path));
+            files.len() != len
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &'_ Path> {
+        self.roots.values().flatten().map(PathBuf::as_path)
+    }
+
+    pub fn entries(&self) -> Vec<FileTreeEntry<'_>> {
+        self.roots
+            .iter()
+            .map(|(root, files)| {
+                let children = build_entries(root, &mut files.iter().peekable());
+                FileTreeEntry::Dir { name: root.to_string_lossy(), path: root, children }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn children<'a>(entry: &'a FileTreeEntry<'a>) -> &'a [FileTreeEntry<'a>] {
+        match entry {
+            FileTreeEntry::Dir { children, .. } => children,
+            FileTreeEntry::File { .. } => panic!("not a directory: {:?}", entry),
+        }
+    }
+
+    fn names(entries: &[FileTreeEntry<'_>]) -> Vec<String> {
+        let mut ret = vec![];
+        for entry in entries {
+            match entry {
+                FileTreeEntry::File { name, .. } => ret.push(name.to_string()),
+                FileTreeEntry::Dir { name, children, .. } => {
+                    ret.push(format!("{}/", name));
+                    ret.extend(names(children).into_iter().map(|n| format!("  {}", n)));
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn collect_files_in_root() {
+        let dir = TempDir::with_files(
+            "file-tree-collect",
+            &[
+                "b.md",
+                "a.markdown",
+                "c.txt",
+                "sub/x.md",
+                "sub/deep/y.md",
+                "node_modules/z.md",
+                "empty/w.txt",
+            ],
+        );
+        let mut tree = FileTree::new(&Watch::default());
+        assert!(tree.is_empty());
+        assert!(tree.add_root(dir.path().to_path_buf()));
+        assert!(!tree.add_root(dir.join("sub")));
+
+        let entries = tree.entries();
+        let names = names(children(&entries[0]));
+        let want = ["sub/", "  deep/", "    y.md", "  x.md", "a.markdown", "b.md"];
+        assert_eq!(names, want);
+    }
+
+    #[test]
+    fn update_files() {
+        let dir = TempDir::with_files("file-tree-update", &["a.md"]);
+        let mut tree = FileTree::new(&Watch::default());
+        tree.add_root(dir.path().to_path_buf());
+
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/b.md"), "").unwrap();
+        fs::write(dir.join("c.txt"), "").unwrap();
+        assert!(tree.update(&dir.join("sub")));
+        assert!(!tree.update(&dir.join("sub/b.md")));
+        assert!(!tree.update(&dir.join("c.txt")));
+        assert!(!tree.update(Path::new("/path/to/outside.md")));
+        let entries = tree.entries();
+        assert_eq!(names(children(&entries[0])), ["sub/", "  b.md", "a.md"]);
+
+        fs::remove_dir_all(dir.join("sub")).unwrap();
+        assert!(tree.update(&dir.join("sub")));
+        fs::remove_file(dir.join("a.md")).unwrap();
+        assert!(tree.update(&dir.join("a.md")));
+        let entries = tree.entries();
+        assert!(children(&entries[0]).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::path::Path;
+
+const SCORE_MIN: i32 = i32::MIN / 2;
+const SCORE_GAP_LEADING: i32 = -1;
+const SCORE_GAP_TRAILING: i32 = -1;
+const SCORE_GAP_INNER: i32 = -10;
+const SCORE_CONSECUTIVE: i32 = 100;
+const BONUS_PATH_SEP: i32 = 90;
+const BONUS_WORD: i32 = 80;
+const BONUS_CAMEL: i32 = 70;
+const BONUS_RECENT: i32 = 200;
+
+fn bonus(prev: Option<char>, cur: char) -> i32 {
+    match prev {
+        None | Some('/' | '\\') => BONUS_PATH_SEP,
+        Some('-' | '_' | ' ' | '.') => BONUS_WORD,
+        Some(p) if p.is_lowercase() && cur.is_uppercase() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+// Fuzzy matcher similar to fzy. A query matches to a text when all characters in the query appear in the text in the
+// same order. The score is higher when matched characters are consecutive or placed at the start of path components
+// or words.
+pub struct FuzzyMatcher {
+    query: Vec<char>,
+    ignore_case: bool,
+}
+
+impl FuzzyMatcher {
+    pub fn new(query: &str) -> Self {
+        // Smart case: case-insensitive unless the query contains upper case characters
+        let ignore_case = !query.chars().any(char::is_uppercase);
+        let query = query.chars().filter(|c| !c.is_whitespace()).collect();
+        Self { query, ignore_case }
+    }
+
+    fn eq(&self, q: char, c: char) -> bool {
+        if self.ignore_case {
+            c.to_lowercase().eq(q.to_lowercase())
+        } else {
+            q == c
+        }
+    }
+
+    fn is_subsequence(&self, text: &[char]) -> bool {
+        let mut chars = text.iter();
+        self.query.iter().all(|&q| chars.any(|&c| self.eq(q, c)))
+    }
+
+    // Returns the score and the character (not byte) positions of matched characters in the text
+    pub fn score(&self, text: &str) -> Option<(i32, Vec<usize>)> {
+        if self.query.is_empty() {
+            return Some((0, vec![]));
+        }
+        let text: Vec<char> = text.chars().collect();
+        if !self.is_subsequence(&text) {
+            return None;
+        }
+
+        let (m, n) = (self.query.len(), text.len());
+        let bonuses: Vec<_> =
+            (0..n).map(|j| bonus(j.checked_sub(1).map(|p| text[p]), text[j])).collect();
+
+        // `d[i][j]`: the best score when `query[i]` matches to `text[j]`
+        // `b[i][j]`: the best score of `query[..=i]` within `text[..=j]`
+        let mut d = vec![SCORE_MIN; m * n];
+        let mut b = vec![SCORE_MIN; m * n];
+        for i in 0..m {
+            let gap = if i + 1 == m { SCORE_GAP_TRAILING } else { SCORE_GAP_INNER };
+            let mut prev_best = SCORE_MIN;
+            for j in 0..n {
+                let idx = i * n + j;
+                if self.eq(self.query[i], text[j]) {
+                    d[idx] = if i == 0 {
+                        j as i32 * SCORE_GAP_LEADING + bonuses[j]
+                    } else if j > 0 {
+                        let prev = idx - n - 1;
+                        (b[prev] + bonuses[j]).max(d[prev] + SCORE_CONSECUTIVE)
+                    } else {
+                        SCORE_MIN
+                    };
+                }
+                prev_best = d[idx].max(prev_best.saturating_add(gap));
+                b[idx] = prev_best;
+            }
+        }
+
+        let score = b[m * n - 1];
+        if score <= SCORE_MIN / 2 {
+            return None;
+        }
+
+        // Backtrack to find the positions of matched characters
+        let mut positions = vec![0; m];
+        let mut must_match = false;
+        let mut j = n;
+        for i in (0..m).rev() {
+            while j > 0 {
+                j -= 1;
+                let idx = i * n + j;
+                if d[idx] > SCORE_MIN / 2 && (must_match || d[idx] == b[idx]) {
+                    must_match = i > 0 && j > 0 && d[idx] == d[idx - n - 1] + SCORE_CONSECUTIVE;
+                    positions[i] = j;
+                    break;
+                }
+            }
+        }
+
+        Some((score, positions))
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct FoundFile<'a> {
+    pub path: &'a Path,
+    pub positions: Vec<usize>,
+    #[serde(skip)]
+    score: i32,
+}
+
+// Find files matching to the query from candidates. The second element of each candidate is recency of the file. 0
+// means the most recently opened file.
+pub fn find_files<'a, I>(query: &str, candidates: I, limit: usize) -> Vec<FoundFile<'a>>
+where
+    I: IntoIterator<Item = (&'a Path, Option<usize>)>,
+{
+    let matcher = FuzzyMatcher::new(query);
+    let mut found: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|(path, recency)| {
+            let (mut score, positions) = matcher.score(path.to_str()?)?;
+            if let Some(r) = recency {
+                score += BONUS_RECENT / (r as i32 + 1);
+            }
+            Some(FoundFile { path, positions, score })
+        })
+        .collect();
+
+    found.sort_by(|l, r| {
+        r.score
+            .cmp(&l.score)
+            .then_with(|| l.path.as_os_str().len().cmp(&r.path.as_os_str().len()))
+            .then_with(|| l.path.cmp(r.path))
+    });
+    found.truncate(limit);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_positions() {
+        for (query, text, want) in [
+            ("abc", "abc", Some(&[0, 1, 2][..])),
+            ("abc", "a_b_c", Some(&[0, 2, 4][..])),
+            ("rdm", "/path/to/README.md", Some(&[9, 12, 13][..])),
+            ("readme", "/path/to/README.md", Some(&[9, 10, 11, 12, 13, 14][..])),
+            ("foo", "/foo/f_o_o.md", Some(&[1, 2, 3][..])),
+            ("fo", "/x/f/foo.md", Some(&[5, 6][..])),
+            ("", "/path/to/file.md", Some(&[][..])),
+            ("xyz", "/path/to/file.md", None),
+            ("cba", "abc", None),
+            ("README", "/path/to/readme.md", None),
+            ("Read", "/path/to/README.md", None),
+            ("src md", "/src/file.md", Some(&[1, 2, 3, 10, 11][..])),
+        ] {
+            let matcher = FuzzyMatcher::new(query);
+            let found = matcher.score(text).map(|(_, p)| p);
+            assert_eq!(found.as_deref(), want, "query={query:?} text={text:?}");
+        }
+    }
+
+    #[test]
+    fn fuzzy_match_ranking() {
+        let paths = [
+            "/docs/guide/installation.md",
+            "/docs/getting_started.md",
+            "/docs/api/global.md",
+            "/docs/github.md",
+        ];
+        let candidates = paths.iter().map(|p| (Path::new(*p), None));
+        let found = find_files("gs", candidates, 10);
+        let found: Vec<_> = found.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(found, ["/docs/getting_started.md", "/docs/guide/installation.md"]);
+    }
+
+    #[test]
+    fn recent_files_rank_higher() {
+        let candidates = [(Path::new("/a/foo.md"), None), (Path::new("/b/foo.md"), Some(0))];
+        let found = find_files("foo", candidates, 10);
+        let found: Vec<_> = found.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(found, ["/b/foo.md", "/a/foo.md"]);
+
+        let candidates = (0..10).map(|_| (Path::new("/foo.md"), None));
+        assert_eq!(find_files("foo", candidates, 3).len(), 3);
+    }
+}
@@ -3,12 +3,19 @@
 mod app;
 mod assets;
 mod cli;
+mod command;
 mod config;
 mod dialog;
+mod external;
+mod file_tree;
+mod finder;
 mod markdown;
 mod opener;
 mod persistent;
 mod renderer;
+mod search_index;
+#[cfg(test)]
+mod test_util;
 mod watcher;
 mod wry;
 
@@ -19,6 +26,7 @@ pub use crate::markdown::{MarkdownParseTarget, MarkdownParser};
 pub use crate::renderer::RawMessageWriter;
 
 use crate::app::Shiba;
+use crate::config::Config;
 use crate::opener::SystemOpener;
 use crate::renderer::EventLoop;
 use crate::watcher::NopWatcher;
@@ -26,6 +34,33 @@ use crate::wry::{WryEventLoop, WryRenderer};
 use anyhow::Result;
 use notify::RecommendedWatcher;
 use rfd::FileDialog;
+use std::env;
+
+// Check config files and print all problems in them. Returns `false` when some problem was found
+#[allow(clippy::print_stdout)]
+pub fn check_config(options: &Options) -> Result<bool> {
+    let project_dir = match options.project_dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => env::current_dir()?,
+    };
+    let checked = Config::check_files(options, &project_dir)?;
+    if checked.is_empty() {
+        println!("No config file was found. The default config is used");
+        return Ok(true);
+    }
+
+    let mut ok = true;
+    for (path, problems) in checked {
+        if problems.is_empty() {
+            println!("{}: OK", path.display());
+        }
+        for problem in problems {
+            println!("{}", problem);
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
 
 pub fn run(options: Options) -> Result<()> {
     let event_loop = WryEventLoop::with_user_event();
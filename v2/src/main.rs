@@ -1,6 +1,6 @@
 use anyhow::Result;
 use log::LevelFilter;
-use shiba_preview::{run, Options};
+use shiba_preview::{check_config, run, Options};
 use std::env;
 
 fn main() -> Result<()> {
@@ -11,5 +11,11 @@ fn main() -> Result<()> {
         .format_timestamp(None)
         .filter_module("html5ever", LevelFilter::Off)
         .init();
+    if options.check_config {
+        if !check_config(&options)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     run(options)
 }
@@ -0,0 +1,189 @@
+use std::ops::Range;
+use unicode_normalization::char::{canonical_combining_class, decompose_canonical};
+
+// Letters with strokes are not decomposed by NFD since the stroke is not a combining mark
+fn strip_stroke(c: char) -> char {
+    match c {
+        'Đ' => 'D',
+        'Ħ' => 'H',
+        'Ł' => 'L',
+        'Ø' => 'O',
+        'Ŧ' => 'T',
+        'đ' => 'd',
+        'ħ' => 'h',
+        'ł' => 'l',
+        'ø' => 'o',
+        'ŧ' => 't',
+        c => c,
+    }
+}
+
+// Diacritics are removed by decomposing the character into NFD and dropping combining marks (characters whose
+// canonical combining class is not 0). Kana and Hangul syllables are not decomposed since their voiced sound marks and
+// jamo are not diacritics.
+fn strip_accents(c: char, mut f: impl FnMut(char)) {
+    if matches!(c, '\u{3040}'..='\u{30ff}' | '\u{ac00}'..='\u{d7a3}') {
+        return f(c);
+    }
+    decompose_canonical(c, |d| {
+        if canonical_combining_class(d) == 0 {
+            f(strip_stroke(d));
+        }
+    });
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Folding {
+    pub ignore_case: bool,
+    pub ignore_accents: bool,
+}
+
+impl Folding {
+    pub fn is_enabled(self) -> bool {
+        self.ignore_case || self.ignore_accents
+    }
+
+    fn fold_char(self, c: char, out: &mut String) {
+        if self.ignore_accents {
+            strip_accents(c, |c| self.fold_case(c, out));
+        } else {
+            self.fold_case(c, out);
+        }
+    }
+
+    fn fold_case(self, c: char, out: &mut String) {
+        if self.ignore_case {
+            // Converting to upper case and then to lower case is an approximation of Unicode full case folding.
+            // For example, "ß" is folded into "ss" and "ς" is folded into "σ".
+            for u in c.to_uppercase() {
+                out.extend(u.to_lowercase());
+            }
+        } else {
+            out.push(c);
+        }
+    }
+
+    pub fn fold(self, text: &str) -> String {
+        let mut folded = String::with_capacity(text.len());
+        for c in text.chars() {
+            self.fold_char(c, &mut folded);
+        }
+        folded
+    }
+}
+
+// Character whose folded text is different from the original
+#[derive(Debug)]
+struct Changed {
+    folded: Range<usize>,
+    original: Range<usize>,
+}
+
+// Folded text with mappings from byte offsets in the folded text to byte offsets in the original text. Since only
+// changed characters are recorded, offsets in unchanged spans are calculated from the nearest changed character.
+#[derive(Default, Debug)]
+pub struct FoldedText {
+    text: String,
+    changes: Vec<Changed>,
+}
+
+impl FoldedText {
+    pub fn new(text: &str, folding: Folding) -> Self {
+        let mut folded = String::with_capacity(text.len());
+        let mut changes = vec![];
+        for (idx, c) in text.char_indices() {
+            let start = folded.len();
+            folding.fold_char(c, &mut folded);
+            let len = c.len_utf8();
+            if folded.len() - start != len || folded[start..] != text[idx..idx + len] {
+                changes.push(Changed { folded: start..folded.len(), original: idx..idx + len });
+            }
+        }
+        Self { text: folded, changes }
+    }
+
+    pub fn as_str(&self) -> &'_ str {
+        &self.text
+    }
+
+    // The last changed character starting at or before the offset
+    fn changed_at(&self, offset: usize) -> Option<&'_ Changed> {
+        let idx = self.changes.partition_point(|c| c.folded.start <= offset);
+        self.changes.get(idx.checked_sub(1)?)
+    }
+
+    // Map the start offset of a match. When the offset is in the middle of a folded character, the start of the
+    // original character is returned.
+    pub fn map_start(&self, offset: usize) -> usize {
+        match self.changed_at(offset) {
+            Some(c) if offset < c.folded.end => c.original.start,
+            Some(c) => c.original.end + (offset - c.folded.end),
+            None => offset,
+        }
+    }
+
+    // Map the end offset of a match. When the offset is in the middle of a folded character, the end of the original
+    // character is returned.
+    pub fn map_end(&self, offset: usize) -> usize {
+        match self.changed_at(offset) {
+            Some(c) if c.folded.start < offset && offset < c.folded.end => c.original.end,
+            Some(c) if offset < c.folded.end => c.original.start,
+            Some(c) => c.original.end + (offset - c.folded.end),
+            None => offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASE: Folding = Folding { ignore_case: true, ignore_accents: false };
+    const ACCENTS: Folding = Folding { ignore_case: false, ignore_accents: true };
+    const BOTH: Folding = Folding { ignore_case: true, ignore_accents: true };
+
+    #[test]
+    fn fold_text() {
+        for (folding, input, want) in [
+            (CASE, "Hello, World", "hello, world"),
+            (CASE, "STRASSE Straße", "strasse strasse"),
+            (CASE, "ÉCOLE école", "école école"),
+            (CASE, "МОСКВА", "москва"),
+            (CASE, "ΣΟΦΟΣ σοφος", "σοφοσ σοφοσ"),
+            (ACCENTS, "Résumé", "Resume"),
+            (ACCENTS, "Re\u{0301}sume\u{0301}", "Resume"),
+            (ACCENTS, "Ὀδυσσεύς", "Οδυσσευς"),
+            (ACCENTS, "Йод ёлка", "Иод елка"),
+            (ACCENTS, "Ǿ ȘȚ ṩ ǖ", "O ST s u"),
+            (ACCENTS, "한국어 ひらがな", "한국어 ひらがな"),
+            (BOTH, "ÉCOLE Łódź", "ecole lodz"),
+        ] {
+            assert_eq!(folding.fold(input), want, "input={input:?}");
+        }
+    }
+
+    #[test]
+    fn map_folded_offsets() {
+        // "ß" (2 bytes) is folded into "ss" (2 bytes), "É" (2 bytes) is folded into "e" (1 byte)
+        let text = "aßÉb";
+        let folded = FoldedText::new(text, BOTH);
+        assert_eq!(folded.as_str(), "asseb");
+        let starts: Vec<_> = (0..=5).map(|i| folded.map_start(i)).collect();
+        assert_eq!(starts, [0, 1, 1, 3, 5, 6]);
+        let ends: Vec<_> = (0..=5).map(|i| folded.map_end(i)).collect();
+        assert_eq!(ends, [0, 1, 3, 3, 5, 6]);
+
+        // Combining mark is removed
+        let text = "e\u{0301}x";
+        let folded = FoldedText::new(text, ACCENTS);
+        assert_eq!(folded.as_str(), "ex");
+        assert_eq!(folded.map_start(1), 3);
+        assert_eq!(folded.map_end(1), 3);
+        assert_eq!(folded.map_end(2), 4);
+
+        // Nothing is changed
+        let folded = FoldedText::new("abc", CASE);
+        assert_eq!(folded.map_start(1), 1);
+        assert_eq!(folded.map_end(3), 3);
+    }
+}
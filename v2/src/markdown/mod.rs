@@ -1,6 +1,9 @@
+mod fold;
 mod parser;
 mod sanitizer;
 mod search;
 
-pub use parser::{MarkdownParseTarget, MarkdownParser};
-pub use search::DisplayText;
+pub use parser::{LocalResources, MarkdownParseTarget, MarkdownParser};
+pub use search::{
+    DisplayText, Headings, SearchMatches, SearchOptions, SearchResult, SearchScope, SpanMatch,
+};
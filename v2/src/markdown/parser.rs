@@ -9,7 +9,7 @@ use pulldown_cmark::{
 };
 use std::cmp;
 use std::collections::HashMap;
-use std::io::{Read, Result, Write};
+use std::io::{self, Read, Result, Write};
 use std::iter::Peekable;
 use std::marker::PhantomData;
 use std::path::Path;
@@ -18,40 +18,94 @@ pub type Range = std::ops::Range<usize>;
 
 pub trait TextVisitor: Default {
     fn visit(&mut self, text: &str, range: &Range);
+    // Text in inline code or code block
+    fn visit_code(&mut self, text: &str, range: &Range) {
+        self.visit(text, range);
+    }
+    // Text which is not rendered as text such as link URLs, image alt texts, math expressions and raw HTML
+    fn visit_hidden(&mut self, _text: &str, _range: &Range) {}
+    // Local path referred from the document. The path is `dest` rebased with `base_dir`.
+    fn visit_local_path(&mut self, _base_dir: &str, _dest: &str) {}
+    // Start of heading. `range` is the source range of the entire heading. Texts in the heading are visited after this.
+    fn visit_heading(&mut self, _level: u8, _range: &Range) {}
+    // Text span put in the render tree. Spans are identified by the order of this method call. Each string in the
+    // tree except for children of `img` and `emoji` is a span, and each `hidden` element is also a span.
+    fn visit_span(&mut self, _range: &Range) {}
 }
 
 impl TextVisitor for () {
     fn visit(&mut self, _text: &str, _range: &Range) {}
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum TokenKind {
-    Normal,
-    MatchOther,
-    MatchCurrent,
-    MatchOtherStart,
-    MatchCurrentStart,
+impl<A: TextVisitor, B: TextVisitor> TextVisitor for (A, B) {
+    fn visit(&mut self, text: &str, range: &Range) {
+        self.0.visit(text, range);
+        self.1.visit(text, range);
+    }
+
+    fn visit_code(&mut self, text: &str, range: &Range) {
+        self.0.visit_code(text, range);
+        self.1.visit_code(text, range);
+    }
+
+    fn visit_hidden(&mut self, text: &str, range: &Range) {
+        self.0.visit_hidden(text, range);
+        self.1.visit_hidden(text, range);
+    }
+
+    fn visit_local_path(&mut self, base_dir: &str, dest: &str) {
+        self.0.visit_local_path(base_dir, dest);
+        self.1.visit_local_path(base_dir, dest);
+    }
+
+    fn visit_heading(&mut self, level: u8, range: &Range) {
+        self.0.visit_heading(level, range);
+        self.1.visit_heading(level, range);
+    }
+
+    fn visit_span(&mut self, range: &Range) {
+        self.0.visit_span(range);
+        self.1.visit_span(range);
+    }
 }
 
-impl TokenKind {
-    fn tag(self) -> &'static str {
-        match self {
-            Self::MatchOther => "match",
-            Self::MatchCurrent => "match-current",
-            Self::MatchOtherStart => "match-start",
-            Self::MatchCurrentStart => "match-current-start",
-            Self::Normal => unreachable!(),
+// Collect paths of local resources (images, linked files, ...) referred from the document to watch their changes
+#[derive(Default, Debug)]
+pub struct LocalResources(Vec<String>);
+
+impl TextVisitor for LocalResources {
+    fn visit(&mut self, _text: &str, _range: &Range) {}
+
+    fn visit_local_path(&mut self, base_dir: &str, dest: &str) {
+        // Query and hash are not a part of file path: 'foo.png?raw=true', 'bar.md#section'
+        let Some(dest) = dest.split(['?', '#']).next().filter(|d| !d.is_empty()) else {
+            return;
+        };
+        let mut path = base_dir.to_string();
+        if !dest.starts_with('/') {
+            path.push('/');
+        }
+        path.push_str(dest);
+        if !self.0.contains(&path) {
+            self.0.push(path);
         }
     }
 }
 
-pub trait TextTokenizer {
-    fn tokenize<'t>(&mut self, text: &'t str, range: &Range) -> (TokenKind, &'t str);
+impl LocalResources {
+    pub fn iter(&self) -> impl Iterator<Item = &'_ str> {
+        self.0.iter().map(String::as_str)
+    }
 }
 
-impl TextTokenizer for () {
-    fn tokenize<'t>(&mut self, text: &'t str, _range: &Range) -> (TokenKind, &'t str) {
-        (TokenKind::Normal, text)
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
     }
 }
 
@@ -84,18 +138,22 @@ impl MarkdownParseTarget {
     pub fn is_empty(&self) -> bool {
         self.source.is_empty() && self.base_dir.is_empty()
     }
+
+    pub fn source(&self) -> &'_ str {
+        &self.source
+    }
 }
 
-pub struct MarkdownParser<'a, V: TextVisitor, T: TextTokenizer> {
+pub struct MarkdownParser<'a, V: TextVisitor> {
     parser: Parser<'a, 'a>,
+    source: &'a str,
     base_dir: &'a SlashPath,
     offset: Option<usize>,
-    text_tokenizer: T,
     _phantom: PhantomData<V>,
 }
 
-impl<'a, V: TextVisitor, T: TextTokenizer> MarkdownParser<'a, V, T> {
-    pub fn new(target: &'a MarkdownParseTarget, offset: Option<usize>, text_tokenizer: T) -> Self {
+impl<'a, V: TextVisitor> MarkdownParser<'a, V> {
+    pub fn new(target: &'a MarkdownParseTarget, offset: Option<usize>) -> Self {
         let mut options = Options::empty();
         options.insert(
             Options::ENABLE_STRIKETHROUGH
@@ -105,19 +163,27 @@ impl<'a, V: TextVisitor, T: TextTokenizer> MarkdownParser<'a, V, T> {
                 | Options::ENABLE_MATH,
         );
         let parser = Parser::new_ext(&target.source, options);
+        let source = &target.source;
         let base_dir = &target.base_dir;
-        Self { parser, base_dir, offset, text_tokenizer, _phantom: PhantomData }
+        Self { parser, source, base_dir, offset, _phantom: PhantomData }
+    }
+
+    // Visit texts in the document without encoding the render tree. This is much cheaper than `write_to` when only the
+    // visitor is necessary. Raw HTML is not sanitized so local paths in it are not visited.
+    pub fn visit_texts(self) -> Result<V> {
+        let mut collector = TextCollector::new(self.source);
+        collector.events(self.parser)?;
+        Ok(collector.text_visitor)
     }
 }
 
 // Note: Build raw JavaScript expression which is evaluated to the render tree encoded as JSON value.
 // This expression will be evaluated via `receive(JSON.parse('{"kind":"render_tree",...}'))` by renderer.
-impl<'a, V: TextVisitor, T: TextTokenizer> RawMessageWriter for MarkdownParser<'a, V, T> {
+impl<'a, V: TextVisitor> RawMessageWriter for MarkdownParser<'a, V> {
     type Output = V;
 
     fn write_to(self, writer: impl Write) -> Result<Self::Output> {
-        let mut enc =
-            RenderTreeEncoder::new(writer, self.base_dir, self.offset, self.text_tokenizer);
+        let mut enc = RenderTreeEncoder::new(writer, self.source, self.base_dir, self.offset);
         enc.out.write_all(br#"JSON.parse('{"kind":"render_tree","tree":"#)?;
         enc.push(self.parser)?;
         enc.out.write_all(b"}')")?;
@@ -196,11 +262,13 @@ struct RawHtmlReader<'a, I: Iterator<Item = (Event<'a>, Range)>> {
     index: usize,
     events: Peekable<I>,
     stack: usize,
+    consumed: Vec<(CowStr<'a>, Range)>,
 }
 
 impl<'a, I: Iterator<Item = (Event<'a>, Range)>> RawHtmlReader<'a, I> {
-    fn new(current: CowStr<'a>, events: Peekable<I>) -> Self {
-        Self { current, index: 0, events, stack: 1 }
+    fn new(current: CowStr<'a>, range: Range, events: Peekable<I>) -> Self {
+        let consumed = vec![(current.clone(), range)];
+        Self { current, index: 0, events, stack: 1, consumed }
     }
 
     fn read_byte(&mut self) -> Option<u8> {
@@ -211,7 +279,8 @@ impl<'a, I: Iterator<Item = (Event<'a>, Range)>> RawHtmlReader<'a, I> {
             {
                 return None;
             }
-            self.current = match self.events.next().unwrap().0 {
+            let (event, range) = self.events.next().unwrap();
+            self.current = match event {
                 Event::Html(html) => {
                     if html.starts_with("</") {
                         self.stack -= 1;
@@ -223,6 +292,7 @@ impl<'a, I: Iterator<Item = (Event<'a>, Range)>> RawHtmlReader<'a, I> {
                 Event::Text(text) => text,
                 _ => unreachable!(),
             };
+            self.consumed.push((self.current.clone(), range));
             self.index = 0;
         }
 
@@ -245,34 +315,46 @@ impl<'a, I: Iterator<Item = (Event<'a>, Range)>> Read for RawHtmlReader<'a, I> {
     }
 }
 
-struct RenderTreeEncoder<'a, W: Write, V: TextVisitor, T: TextTokenizer> {
+// Find the destination in the source of link or image. The destination of reference link is not found since it is
+// defined outside the range.
+fn link_dest_range(source: &str, dest: &str, range: Range) -> Option<Range> {
+    let src = source.get(range.clone())?;
+    let paren = src.rfind("](")?;
+    let idx = src[paren..].find(dest)?;
+    let start = range.start + paren + idx;
+    Some(start..start + dest.len())
+}
+
+struct RenderTreeEncoder<'a, W: Write, V: TextVisitor> {
     out: W,
+    source: &'a str,
     base_dir: &'a SlashPath,
     table: TableState,
     is_start: bool,
     ids: HashMap<CowStr<'a>, usize>,
     modified: Option<usize>,
     text_visitor: V,
-    text_tokenizer: T,
     autolinker: Autolinker,
     sanitizer: Sanitizer<'a>,
     in_code_block: bool,
+    image_alt: Option<Vec<(String, Range)>>,
 }
 
-impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V, T> {
-    fn new(w: W, base_dir: &'a SlashPath, modified: Option<usize>, text_tokenizer: T) -> Self {
+impl<'a, W: Write, V: TextVisitor> RenderTreeEncoder<'a, W, V> {
+    fn new(w: W, source: &'a str, base_dir: &'a SlashPath, modified: Option<usize>) -> Self {
         Self {
             out: w,
+            source,
             base_dir,
             table: TableState::Head,
             is_start: true,
             ids: HashMap::new(),
             modified,
             text_visitor: V::default(),
-            text_tokenizer,
             autolinker: Autolinker::default(),
             sanitizer: Sanitizer::new(base_dir),
             in_code_block: false,
+            image_alt: None,
         }
     }
 
@@ -329,40 +411,65 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
         write!(self.out, r#"{{"t":"{}""#, name)
     }
 
-    fn text_tokens(&mut self, mut input: &str, mut range: Range) -> Result<()> {
-        use TokenKind::*;
+    fn text_span(&mut self, text: &str, range: Range) -> Result<()> {
+        self.text_visitor.visit_span(&range);
+        self.comma()?;
+        self.string(text)
+    }
 
-        while !input.is_empty() {
-            let (token, text) = self.text_tokenizer.tokenize(input, &range);
-            match token {
-                Normal => {
-                    self.comma()?;
-                    self.string(text)?;
-                }
-                MatchOther | MatchCurrent | MatchOtherStart | MatchCurrentStart => {
-                    self.tag(token.tag())?;
-                    self.children_begin()?;
-                    self.string(text)?;
-                    self.tag_end()?;
-                }
-            }
-            input = &input[text.len()..];
-            range.start += text.len();
+    fn text(&mut self, text: &str, range: Range) -> Result<()> {
+        if self.image_alt.is_some() {
+            return self.alt_text(text, range);
         }
+        self.text_visitor.visit(text, &range);
+        self.visible_text(text, range)
+    }
 
-        Ok(())
+    fn code_text(&mut self, text: &str, range: Range) -> Result<()> {
+        if self.image_alt.is_some() {
+            return self.alt_text(text, range);
+        }
+        self.text_visitor.visit_code(text, &range);
+        self.visible_text(text, range)
     }
 
-    fn text(&mut self, text: &str, range: Range) -> Result<()> {
-        self.text_visitor.visit(text, &range);
+    // Alt text is not a span since the renderer flattens children of `img` into a string. It is visited as hidden text
+    // after the image element.
+    fn alt_text(&mut self, text: &str, range: Range) -> Result<()> {
+        if let Some(alt) = &mut self.image_alt {
+            alt.push((text.to_string(), range));
+        }
+        self.comma()?;
+        self.string(text)
+    }
+
+    // Hidden text is not rendered as text. Put an empty `hidden` span instead so that the renderer can show matches in
+    // the text at the position.
+    fn hidden_text(&mut self, text: &str, range: Range) -> Result<()> {
+        if text.len() != range.len() {
+            return Ok(()); // Source map is not available (e.g. the text contains escaped characters)
+        }
+        self.text_visitor.visit_hidden(text, &range);
+        self.text_visitor.visit_span(&range);
+        self.tag("hidden")?;
+        self.out.write_all(b"}")
+    }
 
+    fn hidden_link_dest(&mut self, dest: &str, range: Range) -> Result<()> {
+        match link_dest_range(self.source, dest, range) {
+            Some(range) => self.hidden_text(dest, range),
+            None => Ok(()),
+        }
+    }
+
+    fn visible_text(&mut self, text: &str, range: Range) -> Result<()> {
         let Some(offset) = self.modified else {
-            return self.text_tokens(text, range);
+            return self.text_span(text, range);
         };
 
         let Range { start, end } = range;
         if end < offset {
-            return self.text_tokens(text, range);
+            return self.text_span(text, range);
         }
 
         // Handle the last modified offset with this text token
@@ -372,17 +479,17 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
         if offset <= start {
             self.tag("modified")?;
             self.out.write_all(b"}")?;
-            self.text_tokens(text, range)
+            self.text_span(text, range)
         } else if end == offset {
-            self.text_tokens(text, range)?;
+            self.text_span(text, range)?;
             self.tag("modified")?;
             self.out.write_all(b"}")
         } else {
             let i = offset - start;
-            self.text_tokens(&text[..i], range.start..offset)?;
+            self.text_span(&text[..i], range.start..offset)?;
             self.tag("modified")?;
             self.out.write_all(b"}")?;
-            self.text_tokens(&text[i..], offset..range.end)
+            self.text_span(&text[i..], offset..range.end)
         }
     }
 
@@ -448,18 +555,21 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
         while let Some((event, range)) = events.next() {
             match event {
                 Start(tag) => {
+                    if let Tag::Heading(level, _, _) = &tag {
+                        self.text_visitor.visit_heading(heading_level(*level), &range);
+                    }
                     let next_event = events.peek().map(|(e, _)| e);
-                    self.start_tag(tag, next_event)?;
+                    self.start_tag(tag, next_event, &range)?;
                 }
-                End(tag) => self.end_tag(tag)?,
-                Text(text) if self.in_code_block => self.text(&text, range)?,
+                End(tag) => self.end_tag(tag, range)?,
+                Text(text) if self.in_code_block => self.code_text(&text, range)?,
                 Text(text) => self.autolink_text(&text, range)?,
                 Code(text) => {
                     let pad = (range.len() - text.len()) / 2;
                     let inner_range = (range.start + pad)..(range.end - pad);
                     self.tag("code")?;
                     self.children_begin()?;
-                    self.text(&text, inner_range)?;
+                    self.code_text(&text, inner_range)?;
                     self.tag_end()?;
                 }
                 Html(html) => {
@@ -467,11 +577,19 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
                     self.out.write_all(br#","raw":""#)?;
 
                     let mut dst = StringContentEncoder(&mut self.out);
-                    let mut src = RawHtmlReader::new(html, events);
+                    let mut src = RawHtmlReader::new(html, range, events);
                     self.sanitizer.clean(&mut dst, &mut src)?;
                     events = src.events;
 
+                    for path in self.sanitizer.take_local_srcs() {
+                        self.text_visitor.visit_local_path(self.base_dir, &path);
+                    }
+
                     self.out.write_all(br#""}"#)?;
+
+                    for (html, range) in src.consumed {
+                        self.hidden_text(&html, range)?;
+                    }
                 }
                 SoftBreak => self.text("\n", range)?,
                 HardBreak => {
@@ -492,6 +610,10 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
                     write!(self.out, r#","checked":{}}}"#, checked)?;
                 }
                 Math(display, text) => {
+                    if let Some(idx) = self.source.get(range.clone()).and_then(|s| s.find(&*text)) {
+                        let start = range.start + idx;
+                        self.hidden_text(&text, start..start + text.len())?;
+                    }
                     self.tag("math")?;
                     write!(self.out, r#","inline":{},"expr":"#, display == MathDisplay::Inline)?;
                     self.string(&text)?;
@@ -508,6 +630,8 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
             return self.string(dest);
         }
 
+        self.text_visitor.visit_local_path(self.base_dir, dest);
+
         // Rebase 'foo/bar/' with '/path/to/base' as '/path/to/base/foo/bar'
         self.out.write_all(b"\"")?;
         self.string_content(self.base_dir)?;
@@ -528,7 +652,7 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
         self.out.write_all(b"]}")
     }
 
-    fn start_tag(&mut self, tag: Tag<'a>, next: Option<&Event>) -> Result<()> {
+    fn start_tag(&mut self, tag: Tag<'a>, next: Option<&Event>, range: &Range) -> Result<()> {
         use Tag::*;
         match tag {
             Paragraph => {
@@ -536,16 +660,8 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
             }
             Heading(level, id, _) => {
                 self.tag("h")?;
-
-                let level: u8 = match level {
-                    HeadingLevel::H1 => 1,
-                    HeadingLevel::H2 => 2,
-                    HeadingLevel::H3 => 3,
-                    HeadingLevel::H4 => 4,
-                    HeadingLevel::H5 => 5,
-                    HeadingLevel::H6 => 6,
-                };
-                write!(self.out, r#","level":{}"#, level)?;
+                // The source offset is used to know the line of the heading in the renderer
+                write!(self.out, r#","level":{},"offset":{}"#, heading_level(level), range.start)?;
 
                 if let Some(id) = id {
                     self.out.write_all(br#","id":"#)?;
@@ -636,6 +752,7 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
                 }
             }
             Image(_link_type, dest, title) => {
+                self.image_alt = Some(vec![]);
                 self.tag("img")?;
 
                 if !title.is_empty() {
@@ -663,10 +780,22 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
         self.children_begin()
     }
 
-    fn end_tag(&mut self, tag: Tag<'a>) -> Result<()> {
+    fn end_tag(&mut self, tag: Tag<'a>, range: Range) -> Result<()> {
         use Tag::*;
         match tag {
             Link(LinkType::Autolink, _, _) => Ok(()), // Ignore autolink since it is linked by `Autolinker`
+            Link(LinkType::Email, _, _) => self.tag_end(),
+            Link(_, dest, _) => {
+                self.hidden_link_dest(&dest, range)?;
+                self.tag_end()
+            }
+            Image(_, dest, _) => {
+                self.tag_end()?;
+                for (alt, range) in self.image_alt.take().unwrap_or_default() {
+                    self.hidden_text(&alt, range)?;
+                }
+                self.hidden_link_dest(&dest, range)
+            }
             Paragraph
             | Heading(_, _, _)
             | TableRow
@@ -677,8 +806,6 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
             | Emphasis
             | Strong
             | Strikethrough
-            | Link(_, _, _)
-            | Image(_, _, _)
             | FootnoteDefinition(_) => self.tag_end(),
             CodeBlock(_) => {
                 self.in_code_block = false;
@@ -699,6 +826,141 @@ impl<'a, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'a, W, V,
     }
 }
 
+// Visit texts in the same order as `RenderTreeEncoder` does without writing anything. Keep the visitor calls consistent
+// with the encoder since spans and source offsets collected by visitors must match the render tree.
+struct TextCollector<'a, V: TextVisitor> {
+    source: &'a str,
+    text_visitor: V,
+    autolinker: Autolinker,
+    in_code_block: bool,
+    image_alt: Option<Vec<(String, Range)>>,
+}
+
+impl<'a, V: TextVisitor> TextCollector<'a, V> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            text_visitor: V::default(),
+            autolinker: Autolinker::default(),
+            in_code_block: false,
+            image_alt: None,
+        }
+    }
+
+    fn text(&mut self, text: &str, range: Range) {
+        if let Some(alt) = &mut self.image_alt {
+            alt.push((text.to_string(), range));
+            return;
+        }
+        self.text_visitor.visit(text, &range);
+        self.text_visitor.visit_span(&range);
+    }
+
+    fn code_text(&mut self, text: &str, range: Range) {
+        if let Some(alt) = &mut self.image_alt {
+            alt.push((text.to_string(), range));
+            return;
+        }
+        self.text_visitor.visit_code(text, &range);
+        self.text_visitor.visit_span(&range);
+    }
+
+    fn hidden_text(&mut self, text: &str, range: Range) {
+        if text.len() == range.len() {
+            self.text_visitor.visit_hidden(text, &range);
+            self.text_visitor.visit_span(&range);
+        }
+    }
+
+    fn hidden_link_dest(&mut self, dest: &str, range: Range) {
+        if let Some(range) = link_dest_range(self.source, dest, range) {
+            self.hidden_text(dest, range);
+        }
+    }
+
+    fn emoji_text(&mut self, text: &str, range: Range) {
+        let mut start = range.start;
+        for token in EmojiTokenizer::new(text) {
+            match token {
+                EmojiToken::Text(text) => {
+                    if !text.is_empty() {
+                        self.text(text, start..start + text.len());
+                        start += text.len();
+                    }
+                }
+                EmojiToken::Emoji(_, len) => start += len,
+            }
+        }
+    }
+
+    fn autolink_text(&mut self, mut text: &str, range: Range) {
+        let Range { mut start, end } = range;
+        while let Some((s, e)) = self.autolinker.find_autolink(text) {
+            if s > 0 {
+                self.emoji_text(&text[..s], start..start + s);
+            }
+            self.text(&text[s..e], start + s..start + e);
+            text = &text[e..];
+            start += e;
+        }
+
+        if !text.is_empty() {
+            self.emoji_text(text, start..end);
+        }
+    }
+
+    fn events(&mut self, parser: Parser<'a, 'a>) -> Result<()> {
+        use Event::*;
+
+        let mut events = parser.into_offset_iter().peekable();
+        while let Some((event, range)) = events.next() {
+            match event {
+                Start(Tag::Heading(level, _, _)) => {
+                    self.text_visitor.visit_heading(heading_level(level), &range);
+                }
+                Start(Tag::CodeBlock(_)) => self.in_code_block = true,
+                Start(Tag::Image(_, _, _)) => self.image_alt = Some(vec![]),
+                Start(_) => {}
+                End(Tag::Link(LinkType::Autolink | LinkType::Email, _, _)) => {}
+                End(Tag::Link(_, dest, _)) => self.hidden_link_dest(&dest, range),
+                End(Tag::Image(_, dest, _)) => {
+                    for (alt, range) in self.image_alt.take().unwrap_or_default() {
+                        self.hidden_text(&alt, range);
+                    }
+                    self.hidden_link_dest(&dest, range);
+                }
+                End(Tag::CodeBlock(_)) => self.in_code_block = false,
+                End(_) => {}
+                Text(text) if self.in_code_block => self.code_text(&text, range),
+                Text(text) => self.autolink_text(&text, range),
+                Code(text) => {
+                    let pad = (range.len() - text.len()) / 2;
+                    self.code_text(&text, (range.start + pad)..(range.end - pad));
+                }
+                Html(html) => {
+                    // Consume the following events in the same way as the sanitizer in `RenderTreeEncoder`
+                    let mut src = RawHtmlReader::new(html, range, events);
+                    io::copy(&mut src, &mut io::sink())?;
+                    events = src.events;
+                    for (html, range) in src.consumed {
+                        self.hidden_text(&html, range);
+                    }
+                }
+                SoftBreak => self.text("\n", range),
+                HardBreak | Rule | FootnoteReference(_) | TaskListMarker(_) => {}
+                Math(_, text) => {
+                    if let Some(idx) = self.source.get(range.clone()).and_then(|s| s.find(&*text)) {
+                        let start = range.start + idx;
+                        self.hidden_text(&text, start..start + text.len());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum UrlCharKind {
     Invalid,
@@ -856,7 +1118,7 @@ mod tests {
             fn $name() {
                 let source = load_data(stringify!($name));
                 let target = MarkdownParseTarget::new(source, $basedir);
-                let parser = MarkdownParser::new(&target, $offset, ());
+                let parser = MarkdownParser::new(&target, $offset);
                 let mut buf = Vec::new();
                 let () = parser.write_to(&mut buf).unwrap();
                 let buf = String::from_utf8(buf).unwrap();
@@ -927,7 +1189,7 @@ mod tests {
                 fn $name() {
                     let source = load_data(stringify!($name));
                     let target = MarkdownParseTarget::new(source, None);
-                    let parser = MarkdownParser::new(&target, None, ());
+                    let parser = MarkdownParser::new(&target, None);
                     let mut buf = Vec::new();
                     let visitor: DisplayText = parser.write_to(&mut buf).unwrap();
                     let text = &visitor.raw_text();
@@ -970,6 +1232,53 @@ mod tests {
         snapshot_test!(not_link);
     }
 
+    #[test]
+    fn search_scopes() {
+        use crate::config::SearchMatcher;
+        use crate::markdown::{DisplayText, SearchOptions, SearchScope};
+
+        let source = "foo `foo` [foo](foo.md) ![foo](foo.png)\n\n```\nfoo\n```\n\n<div>foo</div>\n";
+        let target = MarkdownParseTarget::new(source.to_string(), None);
+        let parser = MarkdownParser::new(&target, None);
+        let mut buf = Vec::new();
+        let text: DisplayText = parser.write_to(&mut buf).unwrap();
+        let rendered = String::from_utf8(buf).unwrap();
+        assert_eq!(rendered.matches(r#"{"t":"hidden"}"#).count(), 4, "rendered={rendered}");
+
+        for (scope, want) in [
+            (SearchScope::All, 4),
+            (SearchScope::Prose, 2),
+            (SearchScope::Code, 2),
+            (SearchScope::Everything, 8),
+        ] {
+            let options = SearchOptions { scope, ..Default::default() };
+            let matches =
+                text.search_with_options("foo", SearchMatcher::CaseSensitive, options).unwrap();
+            assert_eq!(matches.len(), want, "scope={scope:?}");
+
+            // Every match is put in text spans including `hidden` elements in the render tree
+            let spans = text.span_matches(source, &matches);
+            let mut indices: Vec<_> = spans.iter().map(|m| m.index).collect();
+            indices.dedup();
+            assert_eq!(indices, (0..want).collect::<Vec<_>>(), "scope={scope:?} spans={spans:?}");
+        }
+    }
+
+    #[test]
+    fn local_resources() {
+        let source = [
+            "![img](foo.png) ![img](/abs/bar.png?raw=true) ![img](https://example.com/x.png)",
+            "[doc](doc.md#section) [anchor](#top) [doc](doc.md)",
+            r#"<img src="baz.png"><img src="http://example.com/y.png">"#,
+        ]
+        .join("\n\n");
+        let target = MarkdownParseTarget::new(source, Some(Path::new("/base")));
+        let parser = MarkdownParser::new(&target, None);
+        let resources: LocalResources = parser.write_to(&mut Vec::new()).unwrap();
+        let want = ["/base/foo.png", "/base/abs/bar.png", "/base/doc.md", "/base/baz.png"];
+        assert_eq!(resources.iter().collect::<Vec<_>>(), want);
+    }
+
     #[test]
     fn emoji_tokenizer() {
         #[derive(PartialEq, Eq, Debug)]
@@ -1066,4 +1375,47 @@ mod tests {
             }
         }
     }
+
+    // Record all visitor calls except for local paths, which are not visited by `visit_texts`
+    #[derive(Default, PartialEq, Eq, Debug)]
+    struct VisitLog(Vec<String>);
+
+    impl TextVisitor for VisitLog {
+        fn visit(&mut self, text: &str, range: &Range) {
+            self.0.push(format!("text {text:?} {range:?}"));
+        }
+        fn visit_code(&mut self, text: &str, range: &Range) {
+            self.0.push(format!("code {text:?} {range:?}"));
+        }
+        fn visit_hidden(&mut self, text: &str, range: &Range) {
+            self.0.push(format!("hidden {text:?} {range:?}"));
+        }
+        fn visit_heading(&mut self, level: u8, range: &Range) {
+            self.0.push(format!("heading {level} {range:?}"));
+        }
+        fn visit_span(&mut self, range: &Range) {
+            self.0.push(format!("span {range:?}"));
+        }
+    }
+
+    #[test]
+    fn visit_texts_without_render_tree() {
+        let mut names: Vec<_> = fs::read_dir(Path::new("src").join("markdown").join("testdata"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .map(|path| path.file_stem().unwrap().to_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert!(!names.is_empty());
+
+        for name in names {
+            let target = MarkdownParseTarget::new(load_data(&name), None);
+            let encoded: VisitLog =
+                MarkdownParser::new(&target, None).write_to(io::sink()).unwrap();
+            let visited: VisitLog = MarkdownParser::new(&target, None).visit_texts().unwrap();
+            assert!(!visited.0.is_empty(), "{name}");
+            assert_eq!(encoded, visited, "{name}");
+        }
+    }
 }
@@ -2,8 +2,10 @@ use ammonia::{Builder, UrlRelative, UrlRelativeEvaluate};
 use once_cell::unsync::OnceCell;
 use std::borrow::Cow;
 use std::io::{Read, Result, Write};
+use std::mem;
 use std::ops::Deref;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 #[derive(Default)]
 pub struct SlashPath(String);
@@ -61,25 +63,38 @@ const ALLOWED_ATTRIBUTES: &[&str] = &["name", "id"];
 pub struct Sanitizer<'a> {
     base_dir: &'a SlashPath,
     cleaner: OnceCell<Builder<'a>>,
+    // Relative `src` attribute values found while cleaning HTML. Attribute filter callback must be `Send + Sync + 'static`
+    local_srcs: Arc<Mutex<Vec<String>>>,
 }
 
 impl<'a> Sanitizer<'a> {
     pub fn new(base_dir: &'a SlashPath) -> Self {
-        Self { base_dir, cleaner: OnceCell::new() }
+        Self { base_dir, cleaner: OnceCell::new(), local_srcs: Arc::default() }
     }
 
     pub fn clean<W: Write, R: Read>(&self, out: W, reader: R) -> Result<()> {
         let cleaner = self.cleaner.get_or_init(|| {
             let prefix = self.base_dir.to_string();
             let eval = Box::new(RebaseUrl { prefix });
+            let local_srcs = self.local_srcs.clone();
             let mut builder = Builder::default();
             builder
                 .add_generic_attributes(ALLOWED_ATTRIBUTES)
-                .url_relative(UrlRelative::Custom(eval));
+                .url_relative(UrlRelative::Custom(eval))
+                .attribute_filter(move |_elem, attr, value| {
+                    if attr == "src" && should_rebase_url(value) {
+                        local_srcs.lock().unwrap().push(value.to_string());
+                    }
+                    Some(value.into())
+                });
             builder
         });
         cleaner.clean_from_reader(reader)?.write_to(out)
     }
+
+    pub fn take_local_srcs(&self) -> Vec<String> {
+        mem::take(&mut self.local_srcs.lock().unwrap())
+    }
 }
 
 #[cfg(test)]
@@ -206,4 +221,21 @@ mod tests {
             assert_eq!(&have, want);
         }
     }
+
+    #[test]
+    fn collect_local_srcs() {
+        let base_dir = SlashPath::from(Path::new("/a/b"));
+        let sanitizer = Sanitizer::new(&base_dir);
+
+        for (input, want) in [
+            ("<img src=\"foo.png\">", &["foo.png"][..]),
+            ("<img src=\"/foo.png\"><img src=\"../bar.png\">", &["/foo.png", "../bar.png"][..]),
+            ("<img src=\"https://example.com/foo.png\">", &[][..]),
+            ("<a href=\"foo.md\">foo</a>", &[][..]),
+            ("<p>hello</p>", &[][..]),
+        ] {
+            sanitizer.clean(&mut Vec::new(), input.as_bytes()).unwrap();
+            assert_eq!(sanitizer.take_local_srcs(), want, "input={input:?}");
+        }
+    }
 }
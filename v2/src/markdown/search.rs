@@ -1,10 +1,13 @@
-use super::parser::{Range, TextTokenizer, TextVisitor, TokenKind};
+use super::fold::{FoldedText, Folding};
+use super::parser::{Range, TextVisitor};
 use crate::config::SearchMatcher;
 use aho_corasick::{
     AhoCorasick, AhoCorasickBuilder, FindIter as AhoCorasickFindIter, Match as AhoCorasickMatch,
 };
 use anyhow::Result;
 use regex::{Match as RegexMatch, Matches as RegexMatches, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 trait MatchPosition {
     fn start(&self) -> usize;
@@ -64,70 +67,407 @@ impl Searcher for Regex {
     }
 }
 
-#[derive(Default)]
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Upper case characters in escape sequences such as `\S` or `\W` don't mean case-sensitive search. Names of Unicode
+// classes such as `\pL` or `\p{Greek}` are skipped as well.
+fn has_upper_case_in_regex(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some('p' | 'P') = chars.next() {
+                if chars.next() == Some('{') {
+                    chars.by_ref().find(|&c| c == '}');
+                }
+            }
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+// Searcher for whole words. `\b` cannot be used since it never matches between two non-word characters (e.g. "c++"
+// would match to "c++x"). Instead, characters just before and after each match are checked not to be word characters.
+struct WholeWordSearcher(Regex);
+
+impl Searcher for WholeWordSearcher {
+    type Match<'text> = RegexMatch<'text>;
+    type Iter<'me, 'text> = WholeWordMatches<'me, 'text>;
+
+    fn new(query: &str, ignore_case: bool) -> Result<Self> {
+        Ok(Self(<Regex as Searcher>::new(&regex::escape(query), ignore_case)?))
+    }
+
+    fn find_iter<'me, 'text>(&'me self, text: &'text str) -> Self::Iter<'me, 'text> {
+        WholeWordMatches { regex: &self.0, text, pos: 0 }
+    }
+}
+
+struct WholeWordMatches<'me, 'text> {
+    regex: &'me Regex,
+    text: &'text str,
+    pos: usize,
+}
+
+impl<'me, 'text> Iterator for WholeWordMatches<'me, 'text> {
+    type Item = RegexMatch<'text>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos <= self.text.len() {
+            let m = self.regex.find_at(self.text, self.pos)?;
+            let before = self.text[..m.start()].chars().next_back();
+            let after = self.text[m.end()..].chars().next();
+            if !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char) {
+                self.pos = if m.is_empty() { m.end() + 1 } else { m.end() };
+                return Some(m);
+            }
+            // Retry from the next character since the match may overlap with the whole word
+            let next = self.text[m.start()..].chars().next().map(char::len_utf8).unwrap_or(1);
+            self.pos = m.start() + next;
+        }
+        None
+    }
+}
+
+// Characters in each word of the query match in the same order within a word, and words in the query match to
+// consecutive words. For example, "md fl prv" matches to "markdown-file preview".
+fn fuzzy_regex(query: &str) -> String {
+    let mut pattern = String::new();
+    for (i, word) in query.split_whitespace().enumerate() {
+        if i > 0 {
+            pattern.push_str(r"\w*\W+"); // Skip the rest of the previous word
+        }
+        for (j, c) in word.chars().enumerate() {
+            if j > 0 {
+                pattern.push_str(r"\w*?");
+            }
+            pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+        }
+    }
+    pattern
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TextKind {
+    Prose,
+    Code,
+    Hidden,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Debug)]
+pub enum SearchScope {
+    // All texts rendered as text
+    #[default]
+    All,
+    // Texts except for code
+    Prose,
+    // Inline code and code blocks
+    Code,
+    // All texts including link URLs, image alt texts, math expressions and raw HTML
+    Everything,
+}
+
+impl SearchScope {
+    fn contains(self, kind: TextKind) -> bool {
+        matches!(
+            (self, kind),
+            (Self::Everything, _)
+                | (Self::All, TextKind::Prose | TextKind::Code)
+                | (Self::Prose, TextKind::Prose)
+                | (Self::Code, TextKind::Code),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SearchOptions {
+    pub ignore_accents: bool,
+    pub scope: SearchScope,
+}
+
+#[derive(Clone, Default)]
 pub struct DisplayText {
     text: String,
     srcmap: Vec<Range>,
+    kinds: Vec<TextKind>, // Kind of each span in `srcmap`
+    offsets: Vec<usize>,  // Start offset in `text` of each span in `srcmap`
+    spans: Vec<Range>,    // Source ranges of text spans in the render tree. Index is the ID of span
 }
 
 impl TextVisitor for DisplayText {
     fn visit(&mut self, text: &str, range: &Range) {
-        self.text.push_str(text);
-        match self.srcmap.last_mut() {
-            Some(last) if last.end == range.start => {
+        self.push(text, range, TextKind::Prose);
+    }
+
+    fn visit_code(&mut self, text: &str, range: &Range) {
+        self.push(text, range, TextKind::Code);
+    }
+
+    fn visit_hidden(&mut self, text: &str, range: &Range) {
+        self.push(text, range, TextKind::Hidden);
+    }
+
+    fn visit_span(&mut self, range: &Range) {
+        self.spans.push(range.clone());
+    }
+}
+
+impl DisplayText {
+    fn push(&mut self, text: &str, range: &Range, kind: TextKind) {
+        match (self.srcmap.last_mut(), self.kinds.last()) {
+            (Some(last), Some(&k)) if last.end == range.start && k == kind => {
                 last.end = range.end;
             }
-            _ => self.srcmap.push(range.clone()),
+            _ => {
+                self.srcmap.push(range.clone());
+                self.kinds.push(kind);
+                self.offsets.push(self.text.len());
+            }
         }
+        self.text.push_str(text);
     }
-}
 
-impl DisplayText {
-    fn search_with<S: Searcher>(&self, query: &str, ignore_case: bool) -> Result<SearchMatches> {
-        let searcher = S::new(query, ignore_case)?;
+    // Text which only contains spans in the scope
+    fn scoped(&self, scope: SearchScope) -> Cow<'_, Self> {
+        if self.kinds.iter().all(|&k| scope.contains(k)) {
+            return Cow::Borrowed(self);
+        }
+        let mut scoped = Self::default();
+        let mut offset = 0;
+        for (range, &kind) in self.srcmap.iter().zip(self.kinds.iter()) {
+            let end = offset + range.len();
+            if scope.contains(kind) {
+                scoped.push(&self.text[offset..end], range, kind);
+            }
+            offset = end;
+        }
+        Cow::Owned(scoped)
+    }
 
+    fn collect_matches(&self, found: impl Iterator<Item = Range>) -> SearchMatches {
         let Some(mut mapper) = SourceMapper::new(&self.srcmap) else {
-            return Ok(SearchMatches::default());
+            return SearchMatches::default();
         };
         let mut matches = vec![];
-        for mat in searcher.find_iter(&self.text) {
-            let Some(start) = mapper.map_inclusive(mat.start()) else {
+        for found in found {
+            let Some(start) = mapper.map_inclusive(found.start) else {
                 break;
             };
-            let Some(end) = mapper.map_exclusive(mat.end()) else {
+            let Some(end) = mapper.map_exclusive(found.end) else {
                 break;
             };
             matches.push(start..end);
         }
+        SearchMatches(matches)
+    }
+
+    fn search_with<S: Searcher>(&self, query: &str, ignore_case: bool) -> Result<SearchMatches> {
+        let searcher = S::new(query, ignore_case)?;
+        let found = searcher.find_iter(&self.text).map(|m| m.start()..m.end());
+        Ok(self.collect_matches(found))
+    }
+
+    // Search the folded text with the folded query, then map offsets of matches back to the original text. This is
+    // necessary since the length of folded text may be different from the original (e.g. "ß" is folded into "ss").
+    fn search_folded<S: Searcher>(&self, query: &str, folding: Folding) -> Result<SearchMatches> {
+        let searcher = S::new(query, false)?;
+        let folded = FoldedText::new(&self.text, folding);
+        let found = searcher
+            .find_iter(folded.as_str())
+            .map(|m| folded.map_start(m.start())..folded.map_end(m.end()));
+        Ok(self.collect_matches(found))
+    }
 
-        Ok(SearchMatches(matches))
+    pub fn search_with_options(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        options: SearchOptions,
+    ) -> Result<SearchMatches> {
+        self.scoped(options.scope).search_text(query, matcher, options.ignore_accents)
     }
 
+    #[cfg(test)]
     pub fn search(&self, query: &str, matcher: SearchMatcher) -> Result<SearchMatches> {
+        self.search_with_options(query, matcher, SearchOptions::default())
+    }
+
+    fn search_text(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        ignore_accents: bool,
+    ) -> Result<SearchMatches> {
         use SearchMatcher::*;
 
         let ignore_case = match matcher {
-            SmartCase => !query.chars().any(|c| c.is_ascii_uppercase()),
-            CaseInsensitive => true,
+            SmartCase | WholeWord | Fuzzy => !query.chars().any(char::is_uppercase),
+            SmartCaseRegex => !has_upper_case_in_regex(query),
+            CaseInsensitive | CaseInsensitiveRegex => true,
             CaseSensitive | CaseSensitiveRegex => false,
         };
 
+        // Regular expressions cannot be folded. Unicode-aware case-insensitive match is supported by `regex` crate.
+        match matcher {
+            CaseSensitiveRegex | CaseInsensitiveRegex | SmartCaseRegex => {
+                return self.search_with::<Regex>(query, ignore_case);
+            }
+            SmartCase | CaseInsensitive | CaseSensitive | WholeWord | Fuzzy => {}
+        }
+
+        let folding = Folding { ignore_case, ignore_accents };
+        if !folding.is_enabled() {
+            return match matcher {
+                WholeWord => self.search_with::<WholeWordSearcher>(query, false),
+                Fuzzy => self.search_with::<Regex>(&fuzzy_regex(query), false),
+                _ => self.search_with::<AhoCorasick>(query, false),
+            };
+        }
+
+        let query = folding.fold(query);
         match matcher {
-            SmartCase | CaseInsensitive | CaseSensitive => {
-                self.search_with::<AhoCorasick>(query, ignore_case)
+            WholeWord => self.search_folded::<WholeWordSearcher>(&query, folding),
+            Fuzzy => self.search_folded::<Regex>(&fuzzy_regex(&query), folding),
+            _ => self.search_folded::<AhoCorasick>(&query, folding),
+        }
+    }
+
+    // Split the matches into pieces in text spans so that the renderer can highlight them in the render tree which was
+    // already sent. Pieces of each match are ordered by their positions.
+    pub fn span_matches(&self, source: &str, matches: &SearchMatches) -> Vec<SpanMatch> {
+        // Offsets are converted into UTF-16 code units since the renderer handles them as JavaScript strings
+        let utf16_len =
+            |range: Range| source.get(range).map(|s| s.encode_utf16().count()).unwrap_or(0);
+
+        // Sort spans by their positions since the order in the render tree is not guaranteed to be the source order
+        let mut order: Vec<_> = (0..self.spans.len()).collect();
+        order.sort_by_key(|&i| self.spans[i].start);
+
+        let mut pieces = vec![];
+        for (index, found) in matches.iter().enumerate() {
+            let first = order.partition_point(|&i| self.spans[i].end <= found.start);
+            for &span in &order[first..] {
+                let range = &self.spans[span];
+                if found.end <= range.start {
+                    break;
+                }
+                let (s, e) = (found.start.max(range.start), found.end.min(range.end));
+                let start = utf16_len(range.start..s);
+                let end = start + utf16_len(s..e);
+                pieces.push(SpanMatch { index, span, start, end });
+            }
+        }
+        pieces
+    }
+
+    // Snippet of the source line containing the match. Texts are taken from the display text so that Markdown syntax
+    // is not included. Hidden texts such as link URLs are omitted from the context around the match.
+    fn snippet(&self, source: &str, range: &Range) -> (String, String, String) {
+        const MAX_BEFORE: usize = 40;
+        const MAX_AFTER: usize = 80;
+
+        fn clip<'a>(text: &'a str, span: &Range, range: Range) -> &'a str {
+            let start = range.start.max(span.start);
+            let end = range.end.min(span.end);
+            if start >= end {
+                return "";
+            }
+            text.get(start - span.start..end - span.start).unwrap_or_default()
+        }
+
+        let line_start = source[..range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end =
+            source[range.end..].find('\n').map(|i| range.end + i).unwrap_or(source.len());
+
+        let (mut before, mut matched, mut after) = (String::new(), String::new(), String::new());
+        // Spans are sorted by their source positions so the first span in the line can be found by binary search
+        let first = self.srcmap.partition_point(|span| span.end <= line_start);
+        for (i, span) in self.srcmap.iter().enumerate().skip(first) {
+            if line_end <= span.start {
+                break;
+            }
+            let offset = self.offsets[i];
+            let Some(text) = self.text.get(offset..offset + span.len()) else {
+                break;
+            };
+            if self.kinds[i] != TextKind::Hidden {
+                before.push_str(clip(text, span, line_start..range.start));
+                after.push_str(clip(text, span, range.end..line_end));
             }
-            CaseSensitiveRegex => self.search_with::<Regex>(query, ignore_case),
+            matched.push_str(clip(text, span, range.clone()));
         }
+
+        let before = before.trim_start();
+        let start = before.char_indices().rev().nth(MAX_BEFORE - 1).map(|(i, _)| i).unwrap_or(0);
+        let before = before[start..].to_string();
+
+        let after = after.trim_end();
+        let end = after.char_indices().nth(MAX_AFTER).map(|(i, _)| i).unwrap_or(after.len());
+        let after = after[..end].to_string();
+
+        (before, matched, after)
+    }
+
+    // Search result of the match at the index with its snippet and the enclosing heading as context
+    pub fn search_result<'h>(
+        &self,
+        source: &str,
+        headings: &'h Headings,
+        index: usize,
+        range: &Range,
+    ) -> SearchResult<'h> {
+        let heading = headings.heading_at(range.start);
+        let (before, matched, after) = self.snippet(source, range);
+        SearchResult { index, heading, before, matched, after }
     }
 
     #[cfg(test)]
-    pub fn raw_text(&self) -> &'_ str {
-        &self.text
+    pub fn raw_text(&self) -> String {
+        self.scoped(SearchScope::All).text.clone()
     }
 
     #[cfg(test)]
-    pub fn sourcemap(&self) -> &'_ [Range] {
-        &self.srcmap
+    pub fn sourcemap(&self) -> Vec<Range> {
+        self.scoped(SearchScope::All).srcmap.clone()
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SearchResult<'a> {
+    pub index: usize,
+    pub heading: Option<&'a str>,
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+}
+
+// Headings in the document. Texts are collected while visiting headings so that they can be shown as context of matches.
+#[derive(Default)]
+pub struct Headings(Vec<(Range, String)>);
+
+impl TextVisitor for Headings {
+    fn visit(&mut self, text: &str, range: &Range) {
+        if let Some((heading, content)) = self.0.last_mut() {
+            if heading.start <= range.start && range.end <= heading.end {
+                content.push_str(text);
+            }
+        }
+    }
+
+    fn visit_heading(&mut self, _level: u8, range: &Range) {
+        self.0.push((range.clone(), String::new()));
+    }
+}
+
+impl Headings {
+    // The nearest heading before the source offset
+    pub fn heading_at(&self, offset: usize) -> Option<&'_ str> {
+        let idx = self.0.partition_point(|(range, _)| range.start <= offset);
+        let (_, text) = &self.0[idx.checked_sub(1)?];
+        Some(text.as_str())
     }
 }
 
@@ -180,80 +520,26 @@ impl SearchMatches {
         self.0.len()
     }
 
-    pub fn tokenizer(&self, index: Option<usize>) -> Option<MatchTokenizer<'_>> {
-        let (head, tail) = self.0.split_first()?;
-        Some(MatchTokenizer { head, tail, current: 0, is_start: true, index })
+    pub fn iter(&self) -> impl Iterator<Item = &'_ Range> {
+        self.0.iter()
     }
 }
 
-pub struct MatchTokenizer<'a> {
-    head: &'a Range,
-    tail: &'a [Range],
-    current: usize,
-    is_start: bool,
-    index: Option<usize>,
-}
-
-impl<'a> MatchTokenizer<'a> {
-    fn next(&mut self) -> bool {
-        let Some((h, t)) = self.tail.split_first() else {
-            return false;
-        };
-        self.head = h;
-        self.tail = t;
-        self.current += 1;
-        self.is_start = true;
-        true
-    }
-
-    fn match_token(&mut self) -> TokenKind {
-        match self.index {
-            Some(idx) if idx == self.current => {
-                if self.is_start {
-                    self.is_start = false;
-                    TokenKind::MatchCurrentStart
-                } else {
-                    TokenKind::MatchCurrent
-                }
-            }
-            _ if self.is_start => {
-                self.is_start = false;
-                TokenKind::MatchOtherStart
-            }
-            _ => TokenKind::MatchOther,
-        }
-    }
-}
-
-impl<'a> TextTokenizer for MatchTokenizer<'a> {
-    fn tokenize<'t>(&mut self, text: &'t str, range: &Range) -> (TokenKind, &'t str) {
-        debug_assert_eq!(text.len(), range.len(), "text={:?} range={:?}", text, range);
-        let Range { start, end } = *range;
-
-        while self.head.end <= start {
-            if !self.next() {
-                return (TokenKind::Normal, text);
-            }
-        }
-
-        if self.head.start <= start {
-            let token = self.match_token();
-            if self.head.end < end {
-                (token, &text[..self.head.end - start])
-            } else {
-                (token, text)
-            }
-        } else if self.head.start < end {
-            (TokenKind::Normal, &text[..self.head.start - start])
-        } else {
-            (TokenKind::Normal, text)
-        }
-    }
+// Piece of the match in a text span of the render tree. `start` and `end` are offsets in the span
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct SpanMatch {
+    pub index: usize,
+    pub span: usize,
+    pub start: usize,
+    pub end: usize,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::markdown::{MarkdownParseTarget, MarkdownParser};
+
+    const ACCENTS: SearchOptions = SearchOptions { ignore_accents: true, scope: SearchScope::All };
 
     fn build_text(input: &str, maps: &[Range]) -> DisplayText {
         let mut text = DisplayText::default();
@@ -372,4 +658,239 @@ mod tests {
         let matches = text.search("(fo+)+", SearchMatcher::CaseSensitiveRegex).unwrap().0;
         assert_eq!(&matches, &[0..11]);
     }
+
+    #[test]
+    fn search_unicode_case_insensitive() {
+        let input = "Straße STRASSE école ÉCOLE Москва";
+        let text = build_text(input, &[0..input.len()]);
+
+        let matches = text.search("strasse", SearchMatcher::SmartCase).unwrap().0;
+        assert_eq!(&matches, &[0..7, 8..15]);
+        let matches = text.search("straße", SearchMatcher::CaseInsensitive).unwrap().0;
+        assert_eq!(&matches, &[0..7, 8..15]);
+        let matches = text.search("ss", SearchMatcher::SmartCase).unwrap().0;
+        assert_eq!(&matches, &[4..6, 12..14]);
+        let matches = text.search("école", SearchMatcher::SmartCase).unwrap().0;
+        assert_eq!(&matches, &[16..22, 23..29]);
+        let matches = text.search("École", SearchMatcher::SmartCase).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+        let matches = text.search("москва", SearchMatcher::SmartCase).unwrap().0;
+        assert_eq!(&matches, &[30..42]);
+        let matches = text.search("école", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[16..22, 23..29]);
+    }
+
+    #[test]
+    fn search_ignore_accents() {
+        let input = "résumé Re\u{0301}sume\u{0301} resume Résumé";
+        let text = build_text(input, &[0..input.len()]);
+
+        let matches =
+            text.search_with_options("resume", SearchMatcher::SmartCase, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[0..8, 9..19, 20..26, 27..35]);
+        let matches =
+            text.search_with_options("resume", SearchMatcher::CaseSensitive, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[0..8, 20..26]);
+        let matches = text.search("resume", SearchMatcher::SmartCase).unwrap().0;
+        assert_eq!(&matches, &[20..26]);
+        let matches = text.search_with_options("sum", SearchMatcher::SmartCase, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[3..6, 13..16, 22..25, 30..33]);
+        let matches = text.search_with_options("rsm", SearchMatcher::Fuzzy, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[0..6, 9..16, 20..25, 27..33]);
+    }
+
+    #[test]
+    fn search_folded_text_across_source_maps() {
+        //           Aß  É  b
+        let input = "Aßx-Éxb";
+        let text = build_text(input, &[0..3, 5..7, 8..9]);
+
+        let matches =
+            text.search_with_options("asseb", SearchMatcher::SmartCase, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[0..9]);
+        let matches = text.search_with_options("se", SearchMatcher::SmartCase, ACCENTS).unwrap().0;
+        assert_eq!(&matches, &[1..7]);
+    }
+
+    #[test]
+    fn search_regex_case() {
+        let input = "foo Foo FOO";
+        let text = build_text(input, &[0..11]);
+
+        let matches = text.search("fo+", SearchMatcher::CaseInsensitiveRegex).unwrap().0;
+        assert_eq!(&matches, &[0..3, 4..7, 8..11]);
+        let matches = text.search("fo+", SearchMatcher::SmartCaseRegex).unwrap().0;
+        assert_eq!(&matches, &[0..3, 4..7, 8..11]);
+        let matches = text.search("Fo+", SearchMatcher::SmartCaseRegex).unwrap().0;
+        assert_eq!(&matches, &[4..7]);
+        // Upper case characters in escape sequences are not considered
+        let matches = text.search(r"\Sf\S+", SearchMatcher::SmartCaseRegex).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+        let matches = text.search(r"\bf\S+", SearchMatcher::SmartCaseRegex).unwrap().0;
+        assert_eq!(&matches, &[0..3, 4..7, 8..11]);
+        // Names of Unicode classes are not considered
+        for query in [r"f\p{Latin}+", r"f\pL+", r"f\P{Zs}+"] {
+            let matches = text.search(query, SearchMatcher::SmartCaseRegex).unwrap().0;
+            assert_eq!(&matches, &[0..3, 4..7, 8..11], "query={query:?}");
+        }
+        let matches = text.search(r"F\p{Latin}+", SearchMatcher::SmartCaseRegex).unwrap().0;
+        assert_eq!(&matches, &[4..7, 8..11]);
+    }
+
+    #[test]
+    fn search_whole_word() {
+        let input = "foo foobar barfoo Foo foo_bar föo föoß c++ c++x (c++)";
+        let text = build_text(input, &[0..input.len()]);
+
+        let matches = text.search("foo", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[0..3, 18..21]);
+        let matches = text.search("Foo", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[18..21]);
+        let matches = text.search("föo", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[30..34]);
+        let matches = text.search("c++", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[42..45, 52..55]);
+        let matches = text.search("oo", SearchMatcher::WholeWord).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+
+        // Overlapping candidate is found after the first candidate is rejected
+        let input = "aaa aa";
+        let text = build_text(input, &[0..input.len()]);
+        let matches = text.search("aa", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[4..6]);
+    }
+
+    #[test]
+    fn search_fuzzy() {
+        let input = "markdown-file preview, Markdown files";
+        let text = build_text(input, &[0..input.len()]);
+
+        let matches = text.search("mdfl", SearchMatcher::Fuzzy).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+        let matches = text.search("md", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[0..5, 23..28]);
+        let matches = text.search("Md", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[23..28]);
+        let matches = text.search("fl prv", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[9..18]);
+        let matches = text.search("md fls", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[23..37]);
+    }
+
+    #[test]
+    fn collect_headings() {
+        //           0       8           20                 37
+        let input = "# Intro\nhello world\n## *Usage* `cmd`\nsay hello\n";
+        let target = MarkdownParseTarget::new(input.to_string(), None);
+        let headings: Headings = MarkdownParser::new(&target, None).visit_texts().unwrap();
+
+        assert_eq!(headings.heading_at(0), Some("Intro"));
+        assert_eq!(headings.heading_at(10), Some("Intro"));
+        assert_eq!(headings.heading_at(20), Some("Usage cmd"));
+        assert_eq!(headings.heading_at(40), Some("Usage cmd"));
+        assert_eq!(Headings::default().heading_at(10), None);
+
+        let target = MarkdownParseTarget::new("hello\n\n# Title\n".to_string(), None);
+        let headings: Headings = MarkdownParser::new(&target, None).visit_texts().unwrap();
+        assert_eq!(headings.heading_at(0), None);
+        assert_eq!(headings.heading_at(7), Some("Title"));
+    }
+
+    #[test]
+    fn search_result_snippets() {
+        //           0      7   12  16    22   27  31           43   48
+        let input = "# Intro\nsay **hello** [world](https://hello)\n\nhello";
+        let mut text = DisplayText::default();
+        let mut headings = Headings::default();
+        headings.visit_heading(1, &(0..7));
+        let spans = [
+            (2..7, TextKind::Prose),
+            (8..12, TextKind::Prose),
+            (14..19, TextKind::Prose),
+            (21..22, TextKind::Prose),
+            (23..28, TextKind::Prose),
+            (30..43, TextKind::Hidden),
+            (46..51, TextKind::Prose),
+        ];
+        for (range, kind) in spans {
+            text.push(&input[range.clone()], &range, kind);
+            headings.visit(&input[range.clone()], &range);
+        }
+
+        let options = SearchOptions { scope: SearchScope::Everything, ..Default::default() };
+        let matches = text.search_with_options("hello", SearchMatcher::SmartCase, options).unwrap();
+        let results: Vec<_> = matches
+            .iter()
+            .enumerate()
+            .map(|(i, r)| text.search_result(input, &headings, i, r))
+            .collect();
+        let want = [
+            SearchResult {
+                index: 0,
+                heading: Some("Intro"),
+                before: "say ".into(),
+                matched: "hello".into(),
+                after: " world".into(),
+            },
+            SearchResult {
+                index: 1,
+                heading: Some("Intro"),
+                before: "say hello world".into(),
+                matched: "hello".into(),
+                after: "".into(),
+            },
+            SearchResult {
+                index: 2,
+                heading: Some("Intro"),
+                before: "".into(),
+                matched: "hello".into(),
+                after: "".into(),
+            },
+        ];
+        assert_eq!(results, want);
+    }
+
+    #[test]
+    fn snippet_of_long_line() {
+        let input = format!("{}foo{}\nnext line", "a".repeat(100), "b".repeat(100));
+        let text = build_text(&input, &[0..203, 204..213]);
+        let (before, matched, after) = text.snippet(&input, &(100..103));
+        assert_eq!(before, "a".repeat(40));
+        assert_eq!(matched, "foo");
+        assert_eq!(after, "b".repeat(80));
+    }
+
+    #[test]
+    fn split_matches_into_spans() {
+        //           0    5       13
+        let input = "foo **bar** foo";
+        let mut text = build_text(input, &[0..4, 6..9, 11..15]);
+        for span in [0..4, 6..9, 11..15] {
+            text.visit_span(&span);
+        }
+
+        let matches = text.search("o b", SearchMatcher::SmartCase).unwrap();
+        let want = [
+            SpanMatch { index: 0, span: 0, start: 2, end: 4 },
+            SpanMatch { index: 0, span: 1, start: 0, end: 1 },
+        ];
+        assert_eq!(text.span_matches(input, &matches), want);
+
+        let matches = text.search("foo", SearchMatcher::SmartCase).unwrap();
+        let want = [
+            SpanMatch { index: 0, span: 0, start: 0, end: 3 },
+            SpanMatch { index: 1, span: 2, start: 1, end: 4 },
+        ];
+        assert_eq!(text.span_matches(input, &matches), want);
+
+        let matches = text.search("xyz", SearchMatcher::SmartCase).unwrap();
+        assert_eq!(text.span_matches(input, &matches), []);
+
+        let input = "あいう🐶え";
+        let mut text = build_text(input, &[0..16]);
+        text.visit_span(&(0..16));
+        let matches = text.search("🐶え", SearchMatcher::SmartCase).unwrap();
+        let want = [SpanMatch { index: 0, span: 0, start: 3, end: 6 }];
+        assert_eq!(text.span_matches(input, &matches), want);
+    }
 }
@@ -1,19 +1,228 @@
+use crate::config::SearchMatcher;
 use crate::renderer::ZoomLevel;
 use anyhow::{Context, Result};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::fs;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const RECENT_FILES_FILE: &str = "recent_files.json";
+const SEARCH_HISTORY_FILE: &str = "search_history.json";
 
 pub trait PersistentData {
     const FILE: &'static str;
+    // Increment the version when the format of the data is changed and convert the old data in `migrate`
+    const VERSION: u32 = 1;
+
+    // Convert the data saved with the older version into the current format. Files saved before the version was
+    // introduced have version 0.
+    fn migrate(_version: u32, data: Value) -> Result<Value> {
+        Ok(data)
+    }
+}
+
+// Lock to update the same data file from multiple Shiba processes. It is an advisory lock of the OS on the lock file
+// so it is released when the file is closed, even when the process crashed. The lock file itself is left.
+struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn acquire(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Could not open lock file {path:?}"))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(err)) => {
+                    return Err(err).with_context(|| format!("Could not lock file {path:?}"))
+                }
+            }
+            if start.elapsed() > Self::TIMEOUT {
+                anyhow::bail!("Could not acquire lock {path:?} within {:?}", Self::TIMEOUT);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Versioned<'a, D> {
+    version: u32,
+    data: &'a D,
+}
+
+// Files saved before the version was introduced contain the data directly
+fn unversioned(value: Value) -> (u32, Value) {
+    match value {
+        Value::Object(mut map) if map.len() == 2 && map.contains_key("data") => {
+            if let Some(version) = map.get("version").and_then(Value::as_u64) {
+                return (version as u32, map.remove("data").unwrap());
+            }
+            (0, Value::Object(map))
+        }
+        value => (0, value),
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub count: u32,
+    // Seconds since the UNIX epoch
+    pub last_opened: u64,
+    pub pinned: bool,
+}
+
+impl RecentFile {
+    // Files opened often and recently get higher score. Older visits are weighted less.
+    fn frecency(&self, now: u64) -> u64 {
+        const DAY: u64 = 24 * 60 * 60;
+        let weight = match now.saturating_sub(self.last_opened) / DAY {
+            0..=3 => 100,
+            4..=13 => 70,
+            14..=30 => 50,
+            31..=89 => 30,
+            _ => 10,
+        };
+        self.count as u64 * weight
+    }
+}
+
+// Recently opened files ranked by 'frecency'. Pinned files are always ranked first and they are never dropped.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RecentFiles {
+    // The most recently opened file comes last
+    files: Vec<RecentFile>,
+}
+
+impl RecentFiles {
+    pub fn iter(&self) -> impl Iterator<Item = &'_ RecentFile> {
+        self.files.iter()
+    }
+
+    pub fn visit(&mut self, path: &Path, now: u64) {
+        let (count, pinned) = match self.files.iter().position(|f| f.path == path) {
+            Some(idx) => {
+                let file = self.files.remove(idx);
+                (file.count.saturating_add(1), file.pinned)
+            }
+            None => (1, false),
+        };
+        let path = path.to_path_buf();
+        self.files.push(RecentFile { path, count, last_opened: now, pinned });
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|f| f.path != path);
+    }
+
+    // Pinned files are not cleared
+    pub fn clear(&mut self) {
+        self.files.retain(|f| f.pinned);
+    }
+
+    // Returns the new pinned state of the file. `None` is returned when the file is not in the recent files.
+    pub fn toggle_pin(&mut self, path: &Path) -> Option<bool> {
+        let file = self.files.iter_mut().find(|f| f.path == path)?;
+        file.pinned = !file.pinned;
+        Some(file.pinned)
+    }
+
+    // Pinned files come first and the rest are sorted by frecency. The more recent one comes first on tie.
+    pub fn ranked(&self, now: u64) -> Vec<&'_ RecentFile> {
+        let mut files: Vec<_> = self.files.iter().rev().collect();
+        files.sort_by_key(|f| Reverse((f.pinned, f.frecency(now))));
+        files
+    }
+
+    // Drop the files ranked lowest when the number of files exceeds the limit
+    fn truncate(&mut self, max_files: usize, now: u64) {
+        if self.files.len() <= max_files {
+            return;
+        }
+        let kept: HashSet<PathBuf> =
+            self.ranked(now).into_iter().take(max_files).map(|f| f.path.clone()).collect();
+        self.files.retain(|f| f.pinned || kept.contains(&f.path));
+    }
+
+    // Merge the files saved by other processes. `loaded` is the files loaded by this process at startup and is used to
+    // find which process changed the file since then. A file missing on one side was removed by that side unless the
+    // other side opened it again. The pinned state is taken from the side which toggled it.
+    fn merge(&mut self, saved: Vec<RecentFile>, loaded: &HashMap<PathBuf, RecentFile>) {
+        let opened_since_loaded = |file: &RecentFile| {
+            loaded.get(&file.path).is_none_or(|l| file.last_opened > l.last_opened)
+        };
+
+        let saved_paths: HashSet<&Path> = saved.iter().map(|f| f.path.as_path()).collect();
+        self.files.retain(|f| saved_paths.contains(f.path.as_path()) || opened_since_loaded(f));
+
+        for file in saved {
+            if let Some(f) = self.files.iter_mut().find(|f| f.path == file.path) {
+                let pinned_by_this = match loaded.get(&f.path) {
+                    Some(l) => l.pinned != f.pinned,
+                    None => f.pinned,
+                };
+                if !pinned_by_this {
+                    f.pinned = file.pinned;
+                }
+                f.count = f.count.max(file.count);
+                f.last_opened = f.last_opened.max(file.last_opened);
+            } else if opened_since_loaded(&file) {
+                self.files.push(file);
+            }
+        }
+        self.files.sort_by_key(|f| f.last_opened);
+    }
+}
+
+impl PersistentData for RecentFiles {
+    const FILE: &'static str = "recent_files.json";
+    const VERSION: u32 = 2;
+
+    // Version 1 or earlier saved only paths as `{"paths": [...]}`
+    fn migrate(version: u32, data: Value) -> Result<Value> {
+        if version >= 2 {
+            return Ok(data);
+        }
+        #[derive(Deserialize)]
+        struct Data {
+            paths: Vec<PathBuf>,
+        }
+        let Data { paths } = serde_json::from_value(data)?;
+        let files: Vec<_> = paths
+            .into_iter()
+            .map(|path| RecentFile { path, count: 1, last_opened: 0, pinned: false })
+            .collect();
+        Ok(serde_json::to_value(RecentFiles { files })?)
+    }
 }
 
 pub struct DataDir {
     path: Option<PathBuf>,
+    // Recent files loaded at startup. Differences from them tell which process changed the files.
+    loaded_recent_files: HashMap<PathBuf, RecentFile>,
+    // Bookmarks when this process loaded or saved them last time. Changes since then are made by other processes.
+    synced_bookmarks: HashSet<BookmarkKey>,
 }
 
 impl DataDir {
@@ -25,12 +234,43 @@ impl DataDir {
             log::debug!("Data directory: {dir:?}");
             Some(dir)
         }
-        Self { path: data_dir() }
+        Self {
+            path: data_dir(),
+            loaded_recent_files: HashMap::new(),
+            synced_bookmarks: HashSet::new(),
+        }
     }
 
     pub fn custom_dir(dir: impl Into<PathBuf>) -> Self {
         let dir = dir.into();
-        Self { path: dir.is_dir().then_some(dir) }
+        Self {
+            path: dir.is_dir().then_some(dir),
+            loaded_recent_files: HashMap::new(),
+            synced_bookmarks: HashSet::new(),
+        }
+    }
+
+    // Data is not saved without the lock since it may overwrite the changes by other processes. `None` is returned when
+    // the data directory is not available.
+    fn lock<D: PersistentData>(&self) -> Result<Option<FileLock>> {
+        let Some(dir) = &self.path else {
+            return Ok(None);
+        };
+        FileLock::acquire(&dir.join(format!("{}.lock", D::FILE))).map(Some)
+    }
+
+    // The broken file is kept as a backup instead of being overwritten on the next save
+    fn backup(path: &Path, err: &anyhow::Error) {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        match fs::rename(path, &backup) {
+            Ok(()) => log::error!(
+                "Persistent data is broken. It was moved to {backup:?} and the data is ignored: {err:#}"
+            ),
+            Err(e) => log::error!(
+                "Persistent data is broken. Remove {path:?} to solve this error: {err:#} (could not backup it: {e})"
+            ),
+        }
     }
 
     pub fn load<D: PersistentData + DeserializeOwned>(&self) -> Option<D> {
@@ -42,90 +282,172 @@ impl DataDir {
                 return None;
             }
         };
-        // serde_json::from_reader may be efficient when writing large data
-        match serde_json::from_slice(&bytes) {
-            Ok(data) => Some(data),
-            Err(err) => {
+
+        let parse = || -> Result<Option<D>> {
+            // serde_json::from_reader may be efficient when writing large data
+            let (version, mut data) = unversioned(serde_json::from_slice(&bytes)?);
+            if version > D::VERSION {
                 log::error!(
-                    "Persistent data is broken. Remove {path:?} to solve this error: {err}"
+                    "Persistent data at {path:?} was saved by newer Shiba (version {version} > {}). It is ignored",
+                    D::VERSION,
+                );
+                return Ok(None);
+            }
+            if version < D::VERSION {
+                log::debug!(
+                    "Migrating persistent data at {path:?} from version {version} to {}",
+                    D::VERSION
                 );
+                data = D::migrate(version, data)
+                    .with_context(|| format!("Could not migrate data from version {version}"))?;
+            }
+            Ok(Some(serde_json::from_value(data)?))
+        };
+
+        match parse() {
+            Ok(data) => data,
+            Err(err) => {
+                Self::backup(&path, &err);
                 None
             }
         }
     }
 
+    // The data is written to a temporary file at first then it is renamed to the actual file so that the file is not
+    // broken even if the process crashes while writing the data.
+    // Other processes may save the same file at the same time
     pub fn save<D: PersistentData + Serialize>(&self, data: &D) -> Result<()> {
+        let _lock = self.lock::<D>()?;
+        self.write(data)
+    }
+
+    // The data is written to a temporary file and then the file is renamed so that the file is not broken when the
+    // process crashes while writing it. The temporary file name is unique so that processes or threads writing the
+    // same file do not write into the same temporary file.
+    fn write<D: PersistentData + Serialize>(&self, data: &D) -> Result<()> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
         let Some(dir) = &self.path else {
             return Ok(());
         };
         let path = dir.join(D::FILE);
         // serde_json::to_writer may be efficient when writing large data
-        let s = serde_json::to_string(data)
+        let s = serde_json::to_string(&Versioned { version: D::VERSION, data })
             .with_context(|| format!("Could not serialize persistent data to {path:?}"))?;
+
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp = dir.join(format!("{}.{}.{}.tmp", D::FILE, process::id(), id));
+        let write = || -> io::Result<()> {
+            let mut file = File::create(&tmp)?;
+            file.write_all(s.as_bytes())?;
+            file.sync_all()
+        };
+        if let Err(err) = write() {
+            let _ = fs::remove_file(&tmp);
+            return Err(err)
+                .with_context(|| format!("Could not write persistent data to file {tmp:?}"));
+        }
+        if let Err(err) = fs::rename(&tmp, &path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(err)
+                .with_context(|| format!("Could not save persistent data to file {path:?}"));
+        }
         log::debug!("Saved persistent data at {path:?}");
-        fs::write(&path, s)
-            .with_context(|| format!("Could not save persistent data to file {path:?}"))
+        Ok(())
     }
 
-    pub fn load_recent_files(&self, max_files: usize) -> Vec<PathBuf> {
+    pub fn load_recent_files(&mut self, max_files: usize) -> RecentFiles {
         if max_files == 0 {
-            return vec![];
+            return RecentFiles::default();
+        }
+
+        let Some(mut recent) = self.load::<RecentFiles>() else {
+            return RecentFiles::default();
+        };
+        self.loaded_recent_files =
+            recent.files.iter().map(|f| (f.path.clone(), f.clone())).collect();
+
+        recent.files.retain(|f| f.path.exists());
+        recent.truncate(max_files, unix_time());
+        recent
+    }
+
+    // Other processes may save their recent files after this process loaded them. Their changes are merged so that
+    // they are not lost. Files removed by this process are not restored by the merge.
+    pub fn save_recent_files(&self, recent: &RecentFiles, max_files: usize) -> Result<()> {
+        if max_files == 0 {
+            return Ok(());
+        }
+
+        let _lock = self.lock::<RecentFiles>()?;
+        let mut merged = RecentFiles { files: recent.files.clone() };
+        if let Some(saved) = self.load::<RecentFiles>() {
+            merged.merge(saved.files, &self.loaded_recent_files);
+        }
+        merged.truncate(max_files, unix_time());
+
+        self.write(&merged)
+    }
+
+    pub fn load_bookmarks(&mut self) -> Bookmarks {
+        let bookmarks: Bookmarks = self.load().unwrap_or_default();
+        self.synced_bookmarks = bookmarks.keys();
+        bookmarks
+    }
+
+    // Bookmarks added or removed by other processes since the last load or save are merged into `bookmarks` so that
+    // the changes are not overwritten.
+    pub fn save_bookmarks(&mut self, bookmarks: &mut Bookmarks) -> Result<()> {
+        let _lock = self.lock::<Bookmarks>()?;
+        if let Some(saved) = self.load::<Bookmarks>() {
+            bookmarks.merge(saved.bookmarks, &self.synced_bookmarks);
         }
+        self.write(bookmarks)?;
+        self.synced_bookmarks = bookmarks.keys();
+        Ok(())
+    }
 
+    pub fn load_search_history(&self, max_queries: usize) -> Vec<SearchQuery> {
         #[derive(Deserialize)]
         struct Data {
-            paths: Vec<PathBuf>,
+            queries: Vec<SearchQuery>,
         }
         impl PersistentData for Data {
-            const FILE: &'static str = RECENT_FILES_FILE;
+            const FILE: &'static str = SEARCH_HISTORY_FILE;
         }
 
-        let Some(Data { mut paths }) = self.load() else {
+        let Some(Data { mut queries }) = self.load() else {
             return vec![];
         };
 
-        paths.retain(|p| p.exists());
-        let len = paths.len();
-        if len > max_files {
-            paths.drain(0..len - max_files);
+        let len = queries.len();
+        if len > max_queries {
+            queries.drain(0..len - max_queries);
         }
 
-        paths
+        queries
     }
 
-    pub fn save_recent_files<'a, I>(&self, iter: I, max_files: usize) -> Result<()>
-    where
-        I: Iterator<Item = &'a Path>,
-    {
-        let mut seen = HashSet::new();
-        let mut paths = vec![];
-        for path in iter {
-            if paths.len() >= max_files {
-                break;
-            }
-            if seen.contains(path) {
-                continue;
-            }
-            seen.insert(path);
-            paths.push(path);
-        }
-
-        if paths.is_empty() {
-            return Ok(());
-        }
-
+    // The empty history is saved as well so that clearing the history is persisted
+    pub fn save_search_history(&self, queries: &[SearchQuery]) -> Result<()> {
         #[derive(Serialize)]
         struct Data<'a> {
-            paths: Vec<&'a Path>,
+            queries: &'a [SearchQuery],
         }
         impl<'a> PersistentData for Data<'a> {
-            const FILE: &'static str = RECENT_FILES_FILE;
+            const FILE: &'static str = SEARCH_HISTORY_FILE;
         }
 
-        self.save(&Data { paths })
+        self.save(&Data { queries })
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SearchQuery {
+    pub query: String,
+    pub matcher: SearchMatcher,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WindowState {
     pub x: i32,
@@ -141,9 +463,159 @@ impl PersistentData for WindowState {
     const FILE: &'static str = "window.json";
 }
 
+// Scroll position of the document on the last visit. `len` is the length of the document source at that time to detect
+// drastic changes of the content. `heading` is the text of the heading at the top of the window and `offset` is the
+// distance from it in pixels. The position is restored relative to the heading since the ratio `scroll` drifts when
+// images or diagrams above it change their heights.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ViewState {
+    pub path: PathBuf,
+    pub scroll: f64,
+    pub len: usize,
+    #[serde(default)]
+    pub heading: Option<String>,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+impl ViewState {
+    // The scroll position is no longer meaningful when the length of the content changed more than 20%
+    pub fn is_applicable(&self, len: usize) -> bool {
+        let (min, max) = if self.len < len { (self.len, len) } else { (len, self.len) };
+        max - min <= max / 5
+    }
+}
+
+// View states of recently previewed documents. The least recently used one is removed when the number of the states
+// exceeds the limit.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ViewStates {
+    states: VecDeque<ViewState>,
+}
+
+impl ViewStates {
+    const MAX_STATES: usize = 500;
+
+    pub fn get(&self, path: &Path) -> Option<&ViewState> {
+        self.states.iter().rev().find(|s| s.path == path)
+    }
+
+    // The updated state is moved to the end as the most recently used one
+    pub fn update(&mut self, state: ViewState) {
+        self.states.retain(|s| s.path != state.path);
+        if self.states.len() >= Self::MAX_STATES {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+}
+
+impl PersistentData for ViewStates {
+    const FILE: &'static str = "view_states.json";
+}
+
+// Bookmark of a document or a section in the document. The section is identified by the text of its heading.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Bookmark {
+    pub path: PathBuf,
+    pub heading: Option<String>,
+    pub label: Option<String>,
+}
+
+// Bookmarks are identified by their files and headings. Labels are not part of the identity.
+type BookmarkKey = (PathBuf, Option<String>);
+
+impl Bookmark {
+    pub fn is_at(&self, path: &Path, heading: Option<&str>) -> bool {
+        self.path == path && self.heading.as_deref() == heading
+    }
+
+    fn key(&self) -> BookmarkKey {
+        (self.path.clone(), self.heading.clone())
+    }
+
+    pub fn label(&self) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+        let file = self.path.file_name().unwrap_or(self.path.as_os_str()).to_string_lossy();
+        match &self.heading {
+            Some(heading) => format!("{} › {}", file, heading),
+            None => file.into_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn as_slice(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn find(&self, path: &Path, heading: Option<&str>) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.is_at(path, heading))
+    }
+
+    // Add the bookmark or remove the existing bookmark of the same section. Returns true when it was added.
+    pub fn toggle(&mut self, bookmark: Bookmark) -> bool {
+        if self.remove(&bookmark.path, bookmark.heading.as_deref()).is_some() {
+            return false;
+        }
+        self.bookmarks.push(bookmark);
+        true
+    }
+
+    pub fn remove(&mut self, path: &Path, heading: Option<&str>) -> Option<Bookmark> {
+        let index = self.bookmarks.iter().position(|b| b.is_at(path, heading))?;
+        Some(self.bookmarks.remove(index))
+    }
+
+    fn keys(&self) -> HashSet<BookmarkKey> {
+        self.bookmarks.iter().map(Bookmark::key).collect()
+    }
+
+    // Merge the bookmarks saved by other processes. `synced` is the bookmarks when this process synced them with the
+    // file last time. Bookmarks in it were removed by other processes when they are not saved, and bookmarks not in it
+    // were added by other processes.
+    fn merge(&mut self, saved: Vec<Bookmark>, synced: &HashSet<BookmarkKey>) {
+        let saved_keys: HashSet<_> = saved.iter().map(Bookmark::key).collect();
+        self.bookmarks.retain(|b| {
+            let key = b.key();
+            !synced.contains(&key) || saved_keys.contains(&key)
+        });
+        for bookmark in saved {
+            let key = bookmark.key();
+            if !synced.contains(&key) && self.find(&key.0, key.1.as_deref()).is_none() {
+                self.bookmarks.push(bookmark);
+            }
+        }
+    }
+}
+
+impl PersistentData for Bookmarks {
+    const FILE: &'static str = "bookmarks.json";
+}
+
+// The file at `history[index]` is the current file
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Session {
+    pub history: Vec<PathBuf>,
+    pub index: usize,
+    pub watch_dirs: Vec<PathBuf>,
+}
+
+impl PersistentData for Session {
+    const FILE: &'static str = "session.json";
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
 
     #[test]
     fn custom_dir() {
@@ -152,4 +624,329 @@ mod tests {
         let dir = DataDir::custom_dir(Path::new("this-directory-does-not-exist"));
         assert!(dir.path.is_none());
     }
+
+    #[test]
+    fn save_and_load_search_history() {
+        let (_tmp, dir) = temp_data_dir("search-history");
+
+        let queries: Vec<_> = ["foo", "bar", "piyo"]
+            .into_iter()
+            .map(|q| SearchQuery { query: q.to_string(), matcher: SearchMatcher::SmartCase })
+            .collect();
+        dir.save_search_history(&queries).unwrap();
+        assert_eq!(dir.load_search_history(10), queries);
+        assert_eq!(dir.load_search_history(2), &queries[1..]);
+
+        dir.save_search_history(&[]).unwrap();
+        assert_eq!(dir.load_search_history(10), []);
+    }
+
+    fn temp_data_dir(name: &str) -> (TempDir, DataDir) {
+        let tmp = TempDir::new(name);
+        let dir = DataDir::custom_dir(tmp.path());
+        (tmp, dir)
+    }
+
+    fn data_files(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct TestData {
+        names: Vec<String>,
+    }
+
+    // Version 1 saved a single name as `name`
+    impl PersistentData for TestData {
+        const FILE: &'static str = "test.json";
+        const VERSION: u32 = 2;
+
+        fn migrate(version: u32, mut data: Value) -> Result<Value> {
+            if version < 2 {
+                let name = data.as_object_mut().and_then(|o| o.remove("name"));
+                let name = name.context("name is missing")?;
+                data = serde_json::json!({ "names": [name] });
+            }
+            Ok(data)
+        }
+    }
+
+    #[test]
+    fn save_and_load_versioned_data() {
+        let (path, dir) = temp_data_dir("versioned-data");
+        let file = path.join(TestData::FILE);
+
+        let data = TestData { names: vec!["foo".to_string()] };
+        dir.save(&data).unwrap();
+        let saved: Value = serde_json::from_slice(&fs::read(&file).unwrap()).unwrap();
+        assert_eq!(saved, serde_json::json!({ "version": 2, "data": { "names": ["foo"] } }));
+        assert_eq!(dir.load::<TestData>(), Some(data));
+        // No temporary file is left
+        assert_eq!(data_files(path.path()), ["test.json", "test.json.lock"]);
+
+        for saved in [r#"{"name":"bar"}"#, r#"{"version":1,"data":{"name":"bar"}}"#] {
+            fs::write(&file, saved).unwrap();
+            let want = TestData { names: vec!["bar".to_string()] };
+            assert_eq!(dir.load::<TestData>(), Some(want), "saved={saved:?}");
+        }
+
+        // Data saved by newer version is ignored without backup
+        fs::write(&file, r#"{"version":3,"data":{}}"#).unwrap();
+        assert_eq!(dir.load::<TestData>(), None);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn save_same_data_concurrently() {
+        let (path, _) = temp_data_dir("concurrent-save");
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                // Each thread has its own data directory as if it were another process
+                let dir = DataDir::custom_dir(path.path());
+                thread::spawn(move || {
+                    for j in 0..10 {
+                        let names = vec![format!("{i}-{j}"); 1000];
+                        dir.save(&TestData { names }).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let dir = DataDir::custom_dir(path.path());
+        let data = dir.load::<TestData>().unwrap();
+        assert_eq!(data.names.len(), 1000);
+        assert!(data.names.iter().all(|n| n == &data.names[0]), "{:?}", data.names);
+        assert_eq!(data_files(path.path()), ["test.json", "test.json.lock"]);
+    }
+
+    #[test]
+    fn save_fails_while_locked() {
+        let (path, dir) = temp_data_dir("locked-save");
+        let lock = FileLock::acquire(&path.join("test.json.lock")).unwrap();
+        let err = dir.save(&TestData { names: vec![] }).unwrap_err();
+        assert!(format!("{err}").contains("Could not acquire lock"), "{err:?}");
+        assert_eq!(data_files(path.path()), ["test.json.lock"]);
+
+        // The lock is released even if the lock file is left
+        drop(lock);
+        dir.save(&TestData { names: vec![] }).unwrap();
+        assert_eq!(dir.load::<TestData>(), Some(TestData { names: vec![] }));
+    }
+
+    #[test]
+    fn backup_broken_data() {
+        let (path, dir) = temp_data_dir("broken-data");
+        let file = path.join(TestData::FILE);
+        let backup = path.join("test.json.bak");
+
+        for broken in ["{", r#"{"version":1,"data":{}}"#, r#"{"version":2,"data":{"names":42}}"#] {
+            fs::write(&file, broken).unwrap();
+            assert_eq!(dir.load::<TestData>(), None, "broken={broken:?}");
+            assert!(!file.exists(), "broken={broken:?}");
+            assert_eq!(fs::read_to_string(&backup).unwrap(), broken);
+        }
+    }
+
+    #[test]
+    fn merge_recent_files_saved_by_other_process() {
+        let (path, mut dir) = temp_data_dir("recent-files");
+        let files: Vec<_> = ["a.md", "b.md", "c.md", "d.md"].map(|f| path.join(f)).into();
+        for file in &files {
+            fs::write(file, "").unwrap();
+        }
+        let paths = |f: &[usize]| f.iter().map(|&i| files[i].clone()).collect::<Vec<_>>();
+        let paths_of = |r: &RecentFiles| r.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+
+        let mut recent = RecentFiles::default();
+        recent.visit(&files[0], 10);
+        recent.visit(&files[1], 20);
+        dir.save_recent_files(&recent, 10).unwrap();
+        let mut recent = dir.load_recent_files(10);
+        assert_eq!(paths_of(&recent), paths(&[0, 1]));
+
+        // Other process opens c.md and b.md
+        let mut other_dir = DataDir::custom_dir(path.path());
+        let mut other = other_dir.load_recent_files(10);
+        other.visit(&files[2], 30);
+        other.visit(&files[1], 40);
+        other_dir.save_recent_files(&other, 10).unwrap();
+
+        // a.md was removed and d.md was opened by this process. c.md opened by the other process is kept.
+        recent.remove(&files[0]);
+        recent.visit(&files[3], 35);
+        dir.save_recent_files(&recent, 10).unwrap();
+        let mut recent = dir.load_recent_files(10);
+        assert_eq!(paths_of(&recent), paths(&[2, 3, 1]));
+        assert_eq!(recent.iter().last().unwrap().count, 2);
+
+        // Other process removes d.md and pins b.md
+        let mut other_dir = DataDir::custom_dir(path.path());
+        let mut other = other_dir.load_recent_files(10);
+        other.remove(&files[3]);
+        other.toggle_pin(&files[1]);
+        other_dir.save_recent_files(&other, 10).unwrap();
+
+        // This process still has d.md and unpinned b.md. It opens a.md again and pins c.md
+        recent.visit(&files[0], 50);
+        recent.toggle_pin(&files[2]);
+        dir.save_recent_files(&recent, 10).unwrap();
+        let recent = dir.load_recent_files(10);
+        assert_eq!(paths_of(&recent), paths(&[2, 1, 0]));
+        let pinned: Vec<_> = recent.iter().map(|f| f.pinned).collect();
+        assert_eq!(pinned, [true, true, false]);
+
+        // Pinned files are never dropped
+        dir.save_recent_files(&recent, 1).unwrap();
+        assert_eq!(paths_of(&dir.load_recent_files(10)), paths(&[2, 1]));
+    }
+
+    #[test]
+    fn rank_recent_files_by_frecency() {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 100 * DAY;
+        let mut recent = RecentFiles::default();
+        for _ in 0..5 {
+            recent.visit(Path::new("old.md"), now - 60 * DAY);
+        }
+        recent.visit(Path::new("daily.md"), now - 2 * DAY);
+        recent.visit(Path::new("daily.md"), now - DAY);
+        recent.visit(Path::new("once.md"), now);
+        recent.visit(Path::new("pinned.md"), now - 99 * DAY);
+        assert_eq!(recent.toggle_pin(Path::new("pinned.md")), Some(true));
+        assert_eq!(recent.toggle_pin(Path::new("unknown.md")), None);
+
+        let ranked: Vec<_> = recent.ranked(now).into_iter().map(|f| f.path.as_path()).collect();
+        let want = ["pinned.md", "daily.md", "old.md", "once.md"].map(Path::new);
+        assert_eq!(ranked, want);
+
+        recent.clear();
+        let paths: Vec<_> = recent.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("pinned.md")]);
+    }
+
+    #[test]
+    fn migrate_recent_files_from_paths() {
+        let (path, dir) = temp_data_dir("recent-files-migration");
+        fs::write(path.join(RecentFiles::FILE), r#"{"paths":["a.md","b.md"]}"#).unwrap();
+        let recent: RecentFiles = dir.load().unwrap();
+        let want = ["a.md", "b.md"].map(|p| RecentFile {
+            path: p.into(),
+            count: 1,
+            last_opened: 0,
+            pinned: false,
+        });
+        assert_eq!(recent.files, want);
+    }
+
+    #[test]
+    fn view_states_lru() {
+        let state = |path: &str, scroll: f64| ViewState {
+            path: path.into(),
+            scroll,
+            len: 100,
+            heading: None,
+            offset: 0.0,
+        };
+        let mut states = ViewStates::default();
+        for i in 0..ViewStates::MAX_STATES {
+            states.update(state(&format!("{i}.md"), 0.0));
+        }
+        states.update(state("0.md", 0.5)); // Move to the most recent one
+        states.update(state("new.md", 0.1));
+
+        assert_eq!(states.states.len(), ViewStates::MAX_STATES);
+        assert_eq!(states.get(Path::new("0.md")), Some(&state("0.md", 0.5)));
+        assert_eq!(states.get(Path::new("new.md")), Some(&state("new.md", 0.1)));
+        assert_eq!(states.get(Path::new("1.md")), None);
+        assert!(states.get(Path::new("2.md")).is_some());
+    }
+
+    #[test]
+    fn view_state_applicable() {
+        let state =
+            ViewState { path: "a.md".into(), scroll: 0.3, len: 1000, heading: None, offset: 0.0 };
+        for (len, want) in
+            [(1000, true), (800, true), (1200, true), (799, false), (1251, false), (0, false)]
+        {
+            assert_eq!(state.is_applicable(len), want, "len={len}");
+        }
+    }
+
+    #[test]
+    fn view_state_without_heading() {
+        // View states saved by older versions have no heading
+        let json = r#"{"path":"a.md","scroll":0.5,"len":100}"#;
+        let state: ViewState = serde_json::from_str(json).unwrap();
+        let want =
+            ViewState { path: "a.md".into(), scroll: 0.5, len: 100, heading: None, offset: 0.0 };
+        assert_eq!(state, want);
+    }
+
+    #[test]
+    fn toggle_bookmarks() {
+        let bookmark = |heading: Option<&str>| Bookmark {
+            path: "/path/to/README.md".into(),
+            heading: heading.map(String::from),
+            label: None,
+        };
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.toggle(bookmark(None)));
+        assert!(bookmarks.toggle(bookmark(Some("Usage"))));
+        assert_eq!(bookmarks.as_slice(), &[bookmark(None), bookmark(Some("Usage"))]);
+        assert!(!bookmarks.toggle(bookmark(None)));
+        assert_eq!(bookmarks.as_slice(), &[bookmark(Some("Usage"))]);
+        let path = Path::new("/path/to/README.md");
+        assert_eq!(bookmarks.find(path, Some("Usage")), Some(&bookmark(Some("Usage"))));
+        assert_eq!(bookmarks.find(path, None), None);
+        assert_eq!(bookmarks.remove(path, None), None);
+        assert_eq!(bookmarks.remove(path, Some("Usage")), Some(bookmark(Some("Usage"))));
+        assert!(bookmarks.as_slice().is_empty());
+    }
+
+    #[test]
+    fn merge_bookmarks_saved_by_other_process() {
+        let (tmp, mut dir) = temp_data_dir("bookmarks");
+        let bookmark = |path: &str| Bookmark { path: path.into(), heading: None, label: None };
+        let paths = |b: &Bookmarks| b.as_slice().iter().map(|b| b.path.clone()).collect::<Vec<_>>();
+
+        let mut this = dir.load_bookmarks();
+        this.toggle(bookmark("a.md"));
+        this.toggle(bookmark("b.md"));
+        dir.save_bookmarks(&mut this).unwrap();
+
+        // Other process adds c.md and removes a.md
+        let mut other_dir = DataDir::custom_dir(tmp.path());
+        let mut other = other_dir.load_bookmarks();
+        other.toggle(bookmark("c.md"));
+        other.toggle(bookmark("a.md"));
+        other_dir.save_bookmarks(&mut other).unwrap();
+        assert_eq!(paths(&other), [PathBuf::from("b.md"), "c.md".into()]);
+
+        // This process adds d.md and removes b.md. The changes by the other process are not overwritten
+        this.toggle(bookmark("d.md"));
+        this.toggle(bookmark("b.md"));
+        dir.save_bookmarks(&mut this).unwrap();
+        let want = [PathBuf::from("d.md"), "c.md".into()];
+        assert_eq!(paths(&this), want);
+        assert_eq!(paths(&DataDir::custom_dir(tmp.path()).load_bookmarks()), want);
+    }
+
+    #[test]
+    fn bookmark_label() {
+        let mut bookmark =
+            Bookmark { path: "/path/to/README.md".into(), heading: None, label: None };
+        assert_eq!(bookmark.label(), "README.md");
+        bookmark.heading = Some("Usage".into());
+        assert_eq!(bookmark.label(), "README.md › Usage");
+        bookmark.label = Some("How to use".into());
+        assert_eq!(bookmark.label(), "How to use");
+    }
 }
@@ -1,6 +1,11 @@
 use crate::cli::Options;
-use crate::config::{Config, KeyAction, Search as SearchConfig, SearchMatcher};
-use crate::persistent::WindowState;
+use crate::command::{Command, CommandSpec};
+use crate::config::{Config, ExternalCommand, Search as SearchConfig, SearchMatcher};
+use crate::file_tree::FileTreeEntry;
+use crate::finder::FoundFile;
+use crate::markdown::{SearchResult, SearchScope, SpanMatch};
+use crate::persistent::{Bookmark, RecentFile, SearchQuery, WindowState};
+use crate::search_index::WorkspaceMatch;
 use anyhow::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,21 +18,21 @@ use std::path::{Path, PathBuf};
 #[serde(rename_all = "snake_case")]
 pub enum MessageToRenderer<'a> {
     Config {
-        keymaps: &'a HashMap<String, KeyAction>,
+        keymaps: &'a HashMap<String, Command>,
+        commands: &'static [CommandSpec],
+        external_commands: &'a [ExternalCommand],
         search: &'a SearchConfig,
         theme: Theme,
-        recent: Vec<&'a Path>,
+        recent: Vec<&'a RecentFile>,
+        search_history: &'a [SearchQuery],
+    },
+    Command {
+        command: Command,
     },
-    Search,
-    SearchNext,
-    SearchPrevious,
     Welcome,
-    Outline,
-    NewFile {
-        path: &'a Path,
+    RecentFiles {
+        files: Vec<&'a RecentFile>,
     },
-    History,
-    Help,
     Zoom {
         percent: u16,
     },
@@ -36,6 +41,69 @@ pub enum MessageToRenderer<'a> {
     AlwaysOnTop {
         pinned: bool,
     },
+    ReloadResources {
+        urls: Vec<&'a str>,
+    },
+    ExternalCommandError {
+        message: &'a str,
+    },
+    ConfigError {
+        message: &'a str,
+    },
+    Theme {
+        theme: Theme,
+    },
+    HighlightThemes {
+        themes: Vec<&'a str>,
+        current: &'a str,
+    },
+    FileTree {
+        roots: Vec<FileTreeEntry<'a>>,
+    },
+    CurrentFile {
+        path: &'a Path,
+    },
+    // `heading` is the text of the heading at the top of the window and `offset` is the distance from the heading in
+    // pixels. The ratio `scroll` is used when the heading is not found
+    RestoreScroll {
+        scroll: f64,
+        heading: Option<&'a str>,
+        offset: f64,
+    },
+    ScrollToHeading {
+        heading: &'a str,
+    },
+    Bookmarks {
+        bookmarks: &'a [Bookmark],
+    },
+    // The bookmarked file no longer exists. The renderer offers to remove the bookmark
+    BookmarkNotFound {
+        bookmark: &'a Bookmark,
+    },
+    Bookmarked {
+        added: bool,
+    },
+    FoundFiles {
+        query: &'a str,
+        files: Vec<FoundFile<'a>>,
+    },
+    SearchMatches {
+        query: &'a str,
+        index: Option<usize>,
+        count: usize,
+        matches: Vec<SpanMatch>,
+    },
+    SearchResults {
+        query: &'a str,
+        results: Vec<SearchResult<'a>>,
+    },
+    WorkspaceMatches {
+        query: &'a str,
+        matches: Vec<WorkspaceMatch>,
+    },
+    // Request to report the current scroll position before quitting. The renderer replies with
+    // `MessageFromRenderer::Quit`
+    Quit,
 }
 
 #[derive(Clone, Copy, Deserialize, Debug)]
@@ -49,16 +117,77 @@ pub enum Zoom {
 #[serde(rename_all = "snake_case")]
 pub enum MessageFromRenderer {
     Init,
-    Reload,
+    Command {
+        command: Command,
+    },
     FileDialog,
-    DirDialog,
-    Forward,
-    Back,
+    Search {
+        query: String,
+        index: Option<usize>,
+        matcher: SearchMatcher,
+        scope: SearchScope,
+    },
+    OpenFile {
+        path: String,
+    },
+    FindFiles {
+        query: String,
+    },
+    SearchWorkspace {
+        query: String,
+        matcher: SearchMatcher,
+        scope: SearchScope,
+    },
+    OpenSearchMatch {
+        path: String,
+        query: String,
+        index: usize,
+        matcher: SearchMatcher,
+        scope: SearchScope,
+    },
+    AddSearchHistory {
+        query: String,
+        matcher: SearchMatcher,
+    },
+    Zoom {
+        zoom: Zoom,
+    },
+    PreviewHighlightTheme {
+        name: Option<String>,
+    },
+    // `scroll` is the ratio of the scroll position to the height of the document
+    ScrollPosition {
+        path: String,
+        scroll: f64,
+        heading: Option<String>,
+        offset: f64,
+    },
+    TogglePinRecentFile {
+        path: String,
+    },
+    ToggleBookmark {
+        heading: Option<String>,
+        label: Option<String>,
+    },
+    OpenBookmark {
+        path: String,
+        heading: Option<String>,
+    },
+    RemoveBookmark {
+        path: String,
+        heading: Option<String>,
+    },
+    // `offset` is the source offset of the heading at the top of the window
+    RunExternalCommand {
+        index: usize,
+        selection: String,
+        offset: Option<usize>,
+    },
+    Error {
+        message: String,
+    },
+    // Reply to `MessageToRenderer::Quit`
     Quit,
-    Search { query: String, index: Option<usize>, matcher: SearchMatcher },
-    OpenFile { path: String },
-    Zoom { zoom: Zoom },
-    Error { message: String },
 }
 
 #[derive(Debug)]
@@ -66,30 +195,26 @@ pub enum UserEvent {
     IpcMessage(MessageFromRenderer),
     FileDrop(PathBuf),
     WatchedFilesChanged(Vec<PathBuf>),
+    WatchedResourcesChanged(Vec<PathBuf>),
+    WatchedTreeChanged(Vec<PathBuf>),
     OpenLocalPath(PathBuf),
     OpenExternalLink(String),
+    ThemeChanged(Theme),
+    // `path` is the file previewed when the command was run
+    ExternalCommandExited { name: String, path: PathBuf, reload: bool, error: Option<String> },
+    // Result of searching watched files in the worker thread of `SearchIndex`
+    WorkspaceSearched { query: String, matches: Vec<WorkspaceMatch> },
+    Quit,
     Error(Error),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum MenuItem {
-    Quit,
-    Forward,
-    Back,
-    Reload,
-    OpenFile,
-    WatchDir,
-    Search,
-    SearchNext,
-    SearchPrevious,
-    Outline,
-    Print,
-    ZoomIn,
-    ZoomOut,
-    History,
-    Help,
-    OpenRepo,
-    ToggleAlwaysOnTop,
+    Command(Command),
+    OpenRecent(PathBuf),
+    RemoveRecent(PathBuf),
+    OpenBookmark(Bookmark),
+    RemoveBookmark(Bookmark),
 }
 
 pub trait MenuItems {
@@ -168,7 +293,7 @@ pub trait App<M: MenuItems> {
     fn handle_exit(&self) -> Result<()>;
 }
 
-pub trait EventChannel: 'static + Send {
+pub trait EventChannel: 'static + Send + Clone {
     fn send_event(&self, event: UserEvent);
 }
 
@@ -192,11 +317,16 @@ pub trait Renderer: Sized {
         window_state: Option<WindowState>,
     ) -> Result<Self>;
     fn menu(&self) -> &Self::Menu;
+    fn update_menu(&mut self, config: &Config, recent_files: &[&Path], bookmarks: &[Bookmark]);
     fn send_message(&self, message: MessageToRenderer<'_>) -> Result<()>;
     fn send_message_raw<W: RawMessageWriter>(&self, writer: W) -> Result<W::Output>;
     fn set_title(&self, title: &str);
+    fn reload_assets(&self, config: &Config);
     fn window_state(&self) -> Option<WindowState>;
     fn theme(&self) -> Theme;
+    fn set_theme(&self, theme: Theme);
+    fn on_theme_changed(&self, theme: Theme);
+    fn set_highlight_theme(&self, config: &Config, theme: Option<&str>);
     fn show(&self);
     fn set_background_color(&self, rbga: (u8, u8, u8, u8)) -> Result<()>;
     fn print(&self) -> Result<()>;
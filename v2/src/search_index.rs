@@ -0,0 +1,333 @@
+use crate::config::SearchMatcher;
+use crate::markdown::{
+    DisplayText, Headings, MarkdownParseTarget, MarkdownParser, SearchOptions, SearchResult,
+};
+use crate::renderer::{EventChannel, UserEvent};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct WorkspaceMatch {
+    pub path: PathBuf,
+    pub index: usize,
+    pub heading: Option<String>,
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+}
+
+impl WorkspaceMatch {
+    fn new(path: &Path, result: SearchResult<'_>) -> Self {
+        let SearchResult { index, heading, before, matched, after } = result;
+        let heading = heading.map(str::to_string);
+        Self { path: path.to_path_buf(), index, heading, before, matched, after }
+    }
+}
+
+#[derive(Default)]
+struct IndexedText {
+    target: MarkdownParseTarget,
+    text: DisplayText,
+    headings: Headings,
+    last_used: u64,
+}
+
+impl IndexedText {
+    fn load(path: &Path) -> Option<Self> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                log::debug!("Could not read {:?} for search index: {}", path, err);
+                return None;
+            }
+        };
+        let target = MarkdownParseTarget::new(source, None);
+        let parser = MarkdownParser::new(&target, None);
+        let (text, headings) = parser.visit_texts().ok()?;
+        Some(Self { target, text, headings, last_used: 0 })
+    }
+
+    fn size(&self) -> usize {
+        self.target.source().len()
+    }
+
+    fn search(
+        &self,
+        path: &Path,
+        query: &str,
+        matcher: SearchMatcher,
+        options: SearchOptions,
+    ) -> Result<Vec<WorkspaceMatch>> {
+        let source = self.target.source();
+        let matches = self.text.search_with_options(query, matcher, options)?;
+        Ok(matches
+            .iter()
+            .take(SearchIndex::MAX_MATCHES_PER_FILE)
+            .enumerate()
+            .map(|(index, range)| {
+                let result = self.text.search_result(source, &self.headings, index, range);
+                WorkspaceMatch::new(path, result)
+            })
+            .collect())
+    }
+}
+
+// Display texts of Markdown files loaded lazily on searching. The least recently used texts are evicted when the total
+// size of their sources exceeds the limit.
+#[derive(Default)]
+struct IndexCache {
+    texts: HashMap<PathBuf, IndexedText>,
+    size: usize,
+    clock: u64,
+}
+
+impl IndexCache {
+    fn invalidate(&mut self, path: &Path) {
+        // When the path is a directory, all files in it are invalidated
+        let size = &mut self.size;
+        self.texts.retain(|p, text| {
+            let keep = !p.starts_with(path);
+            if !keep {
+                *size -= text.size();
+            }
+            keep
+        });
+    }
+
+    fn get(&mut self, path: &Path) -> &IndexedText {
+        self.clock += 1;
+        if !self.texts.contains_key(path) {
+            let text = IndexedText::load(path).unwrap_or_default();
+            self.size += text.size();
+            self.texts.insert(path.to_path_buf(), text);
+        }
+        let text = self.texts.get_mut(path).unwrap();
+        text.last_used = self.clock;
+        text
+    }
+
+    fn evict(&mut self, max_size: usize) {
+        if self.size <= max_size {
+            return;
+        }
+        let mut entries: Vec<_> =
+            self.texts.iter().map(|(p, t)| (t.last_used, p.clone())).collect();
+        entries.sort_unstable();
+        for (_, path) in entries {
+            if self.size <= max_size {
+                break;
+            }
+            if let Some(text) = self.texts.remove(&path) {
+                self.size -= text.size();
+            }
+        }
+        log::debug!("Evicted search index cache: {} files ({} bytes)", self.texts.len(), self.size);
+    }
+
+    fn search(
+        &mut self,
+        query: &str,
+        matcher: SearchMatcher,
+        options: SearchOptions,
+        files: &[PathBuf],
+    ) -> Result<Vec<WorkspaceMatch>> {
+        let mut matches = vec![];
+        if !query.is_empty() {
+            for path in files {
+                matches.extend(self.get(path).search(path, query, matcher, options)?);
+                if matches.len() >= SearchIndex::MAX_MATCHES {
+                    matches.truncate(SearchIndex::MAX_MATCHES);
+                    break;
+                }
+            }
+        }
+        self.evict(SearchIndex::MAX_CACHE_SIZE);
+        Ok(matches)
+    }
+}
+
+enum Request {
+    Search { query: String, matcher: SearchMatcher, options: SearchOptions, files: Vec<PathBuf> },
+    Invalidate(PathBuf),
+}
+
+fn run_worker<C: EventChannel>(rx: Receiver<Request>, channel: C) {
+    let mut cache = IndexCache::default();
+    while let Ok(request) = rx.recv() {
+        // Only the latest query needs to be searched. Queued older queries are outdated
+        let mut search = None;
+        for request in iter::once(request).chain(rx.try_iter()) {
+            match request {
+                Request::Search { query, matcher, options, files } => {
+                    search = Some((query, matcher, options, files));
+                }
+                Request::Invalidate(path) => cache.invalidate(&path),
+            }
+        }
+
+        let Some((query, matcher, options, files)) = search else {
+            continue;
+        };
+        let matches = match cache.search(&query, matcher, options, &files) {
+            Ok(matches) => matches,
+            Err(err) => {
+                log::debug!("Could not search {:?} with {:?} matcher: {}", query, matcher, err);
+                vec![]
+            }
+        };
+        log::debug!("Found {} matches in watched files for query {:?}", matches.len(), query);
+        channel.send_event(UserEvent::WorkspaceSearched { query, matches });
+    }
+    log::debug!("Search index worker stopped");
+}
+
+// Search all Markdown files in watched directories. Files are loaded and searched in a worker thread and the result is
+// notified with `UserEvent::WorkspaceSearched`. The cache is invalidated by file system events from watcher.
+pub struct SearchIndex {
+    tx: Sender<Request>,
+}
+
+impl SearchIndex {
+    const MAX_MATCHES_PER_FILE: usize = 20;
+    const MAX_MATCHES: usize = 200;
+    const MAX_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+    pub fn new<C: EventChannel>(channel: C) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || run_worker(rx, channel));
+        Self { tx }
+    }
+
+    fn send(&self, request: Request) {
+        if self.tx.send(request).is_err() {
+            log::error!("Search index worker thread is not running");
+        }
+    }
+
+    pub fn invalidate(&self, path: &Path) {
+        self.send(Request::Invalidate(path.to_path_buf()));
+    }
+
+    pub fn search<'a>(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        options: SearchOptions,
+        files: impl Iterator<Item = &'a Path>,
+    ) {
+        let query = query.to_string();
+        let files = files.map(Path::to_path_buf).collect();
+        self.send(Request::Search { query, matcher, options, files });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::time::Duration;
+
+    fn search(cache: &mut IndexCache, query: &str, files: &[PathBuf]) -> Vec<WorkspaceMatch> {
+        cache.search(query, SearchMatcher::SmartCase, SearchOptions::default(), files).unwrap()
+    }
+
+    #[test]
+    fn search_files() {
+        let dir = TempDir::new("search-index");
+        let (a, b) = (dir.join("a.md"), dir.join("b.md"));
+        fs::write(&a, "# Title\n\nfoo bar\n\n## Section\n\n  baz **foo**  \n").unwrap();
+        fs::write(&b, "nothing here").unwrap();
+
+        let mut cache = IndexCache::default();
+        let files = [a.clone(), b.clone()];
+        let found = search(&mut cache, "foo", &files);
+        let want = [
+            WorkspaceMatch {
+                path: a.clone(),
+                index: 0,
+                heading: Some("Title".into()),
+                before: "".into(),
+                matched: "foo".into(),
+                after: " bar".into(),
+            },
+            WorkspaceMatch {
+                path: a.clone(),
+                index: 1,
+                heading: Some("Section".into()),
+                before: "baz ".into(),
+                matched: "foo".into(),
+                after: "".into(),
+            },
+        ];
+        assert_eq!(found, want);
+        assert!(search(&mut cache, "", &files).is_empty());
+
+        fs::write(&b, "foo").unwrap();
+        let found = search(&mut cache, "foo", &files);
+        assert_eq!(found.len(), 2, "cache is used until invalidated");
+        cache.invalidate(dir.path());
+        assert_eq!(cache.size, 0);
+        let found = search(&mut cache, "foo", &files);
+        assert_eq!(found.len(), 3);
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let dir = TempDir::new("search-index-evict");
+        let files: Vec<_> = (0..3).map(|i| dir.join(format!("{i}.md"))).collect();
+        for path in &files {
+            fs::write(path, "0123456789").unwrap();
+        }
+
+        let mut cache = IndexCache::default();
+        for path in &files {
+            cache.get(path);
+        }
+        cache.get(&files[0]);
+        assert_eq!(cache.size, 30);
+
+        cache.evict(20);
+        assert_eq!(cache.size, 20);
+        assert!(cache.texts.contains_key(&files[0]));
+        assert!(!cache.texts.contains_key(&files[1]));
+        assert!(cache.texts.contains_key(&files[2]));
+
+        cache.evict(0);
+        assert_eq!(cache.size, 0);
+        assert!(cache.texts.is_empty());
+    }
+
+    #[derive(Clone)]
+    struct TestChannel(Sender<UserEvent>);
+
+    impl EventChannel for TestChannel {
+        fn send_event(&self, event: UserEvent) {
+            self.0.send(event).unwrap();
+        }
+    }
+
+    #[test]
+    fn search_in_worker_thread() {
+        let dir = TempDir::new("search-index-worker");
+        let path = dir.join("a.md");
+        fs::write(&path, "foo").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let index = SearchIndex::new(TestChannel(tx));
+        index.search("foo", SearchMatcher::SmartCase, SearchOptions::default(), iter::once(&*path));
+
+        let event = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        let UserEvent::WorkspaceSearched { query, matches } = event else {
+            panic!("unexpected event: {:?}", event);
+        };
+        assert_eq!(query, "foo");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, path);
+    }
+}
@@ -1,23 +1,40 @@
 use crate::config::{FileExtensions, Watch as Config};
 use crate::renderer::{EventChannel, EventLoop, UserEvent};
 use anyhow::{Context as _, Result};
-use notify::event::{CreateKind, DataChange, EventKind as WatchEventKind, ModifyKind};
+use notify::event::{CreateKind, DataChange, EventKind as WatchEventKind, ModifyKind, RemoveKind};
 use notify::{recommended_watcher, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+// Local files referred from the current preview (images, user CSS, ...). This set is shared between the application and
+// the watcher thread to know which file change events are for resources.
+#[derive(Clone, Default)]
+pub struct ResourcePaths(Arc<Mutex<HashSet<PathBuf>>>);
+
+impl ResourcePaths {
+    pub fn replace(&self, paths: HashSet<PathBuf>) -> HashSet<PathBuf> {
+        std::mem::replace(&mut self.0.lock().unwrap(), paths)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.0.lock().unwrap().contains(path)
+    }
+}
+
 pub struct PathFilter {
     extensions: FileExtensions,
+    resources: ResourcePaths,
     last_changed: HashMap<PathBuf, Instant>,
     debounce_throttle: Duration,
 }
 
 impl PathFilter {
-    pub fn new(config: &Config) -> Self {
+    pub fn new(config: &Config, resources: ResourcePaths) -> Self {
         let extensions = config.file_extensions().clone();
         let debounce_throttle = config.debounce_throttle();
-        Self { extensions, last_changed: HashMap::new(), debounce_throttle }
+        Self { extensions, resources, last_changed: HashMap::new(), debounce_throttle }
     }
 
     fn debounce(&mut self, path: &Path) -> bool {
@@ -38,6 +55,10 @@ impl PathFilter {
         self.extensions.matches(path) && path.is_file() && self.debounce(path)
     }
 
+    fn is_resource(&mut self, path: &Path) -> bool {
+        self.resources.contains(path) && self.debounce(path)
+    }
+
     fn cleanup_debouncer(&mut self) {
         let before = self.last_changed.len();
         let now = Instant::now();
@@ -53,6 +74,8 @@ impl PathFilter {
 pub trait Watcher: Sized {
     fn new<E: EventLoop>(event_loop: &E, filter: PathFilter) -> Result<Self>;
     fn watch(&mut self, path: &Path) -> Result<()>;
+    // Watch only the entries directly in the directory
+    fn watch_non_recursive(&mut self, dir: &Path) -> Result<()>;
     fn unwatch(&mut self, path: &Path) -> Result<()>;
 }
 
@@ -94,15 +117,52 @@ impl Watcher for RecommendedWatcher {
                     // If the content is updated multiple times within the duration, only the first change is
                     // reflected to the preview.
                     let mut paths = event.paths;
-                    paths.retain(|p| filter.should_retain(p));
+                    let mut resources = vec![];
+                    paths.retain(|p| {
+                        if filter.is_resource(p) {
+                            resources.push(p.clone());
+                            false
+                        } else {
+                            filter.should_retain(p)
+                        }
+                    });
 
                     if !paths.is_empty() {
                         log::debug!("Files change event from watcher: {:?}", paths);
                         channel.send_event(UserEvent::WatchedFilesChanged(paths));
                     }
 
+                    if !resources.is_empty() {
+                        log::debug!("Resources change event from watcher: {:?}", resources);
+                        channel.send_event(UserEvent::WatchedResourcesChanged(resources));
+                    }
+
                     filter.cleanup_debouncer();
                 }
+                // Changes of directory structure for file tree. Files in the paths may not exist anymore.
+                WatchEventKind::Create(CreateKind::Folder | CreateKind::Any)
+                | WatchEventKind::Remove(RemoveKind::File | RemoveKind::Folder | RemoveKind::Any)
+                | WatchEventKind::Modify(ModifyKind::Name(_)) => {
+                    log::debug!(
+                        "Caught filesystem structure event: {:?} {:?}",
+                        event.kind,
+                        event.paths
+                    );
+
+                    // Editors saving files atomically replace the file by renaming a temporary file to it
+                    let resources: Vec<_> = event
+                        .paths
+                        .iter()
+                        .filter(|p| p.is_file() && filter.is_resource(p))
+                        .cloned()
+                        .collect();
+                    if !resources.is_empty() {
+                        log::debug!("Resources replaced event from watcher: {:?}", resources);
+                        channel.send_event(UserEvent::WatchedResourcesChanged(resources));
+                    }
+
+                    channel.send_event(UserEvent::WatchedTreeChanged(event.paths));
+                }
                 _ => {}
             },
             Err(err) => {
@@ -120,6 +180,12 @@ impl Watcher for RecommendedWatcher {
             .context("Error while starting to watch a path. Note: Watching non-existing path is unsupported. Instead watch its parent directory")
     }
 
+    fn watch_non_recursive(&mut self, dir: &Path) -> Result<()> {
+        log::debug!("Watching directory {:?} non-recursively", dir);
+        <RecommendedWatcher as NotifyWatcher>::watch(self, dir, RecursiveMode::NonRecursive)?;
+        Ok(())
+    }
+
     fn unwatch(&mut self, path: &Path) -> Result<()> {
         <RecommendedWatcher as NotifyWatcher>::unwatch(self, path)?;
         Ok(())
@@ -135,6 +201,9 @@ impl Watcher for NopWatcher {
     fn watch(&mut self, _path: &Path) -> Result<()> {
         Ok(())
     }
+    fn watch_non_recursive(&mut self, _dir: &Path) -> Result<()> {
+        Ok(())
+    }
     fn unwatch(&mut self, _path: &Path) -> Result<()> {
         Ok(())
     }
@@ -1,5 +1,6 @@
 use crate::renderer::{App, AppControl, EventChannel, EventLoop, UserEvent};
 use crate::wry::menu::MenuIds;
+use crate::wry::webview::renderer_theme;
 use anyhow::Error;
 use wry::application::event::{Event, StartCause, WindowEvent};
 use wry::application::event_loop::{ControlFlow, EventLoopProxy};
@@ -40,7 +41,26 @@ impl EventLoop for WryEventLoop {
                 }
                 Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
                     log::debug!("Closing window was requested");
-                    AppControl::Exit
+                    match app.handle_user_event(UserEvent::Quit) {
+                        Ok(control) => control,
+                        Err(err) => {
+                            log::error!("Could not handle window close request");
+                            log_causes(err);
+                            AppControl::Exit
+                        }
+                    }
+                }
+                Event::WindowEvent { event: WindowEvent::ThemeChanged(theme), .. } => {
+                    log::debug!("Window theme was changed: {:?}", theme);
+                    let event = UserEvent::ThemeChanged(renderer_theme(theme));
+                    match app.handle_user_event(event) {
+                        Ok(control) => control,
+                        Err(err) => {
+                            log::error!("Could not handle window theme change");
+                            log_causes(err);
+                            AppControl::Continue
+                        }
+                    }
                 }
                 Event::UserEvent(event) => {
                     log::debug!("Handling user event {:?}", event);
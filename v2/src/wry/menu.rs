@@ -1,21 +1,134 @@
+use crate::command::{Accelerator as AppAccelerator, Command, Modifiers as AppModifiers, COMMANDS};
+use crate::config::{Config, Menu as MenuConfig};
+use crate::persistent::Bookmark;
 use crate::renderer::{MenuItem as AppMenuItem, MenuItems};
 use anyhow::Result;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use wry::application::accelerator::Accelerator;
 use wry::application::keyboard::{KeyCode, ModifiersState};
 #[cfg(not(target_os = "windows"))]
 use wry::application::menu::AboutMetadata;
 use wry::application::menu::{MenuBar, MenuId, MenuItem, MenuItemAttributes};
 
+fn to_wry_accelerator(accel: AppAccelerator) -> Option<Accelerator> {
+    let Some(key) = key_code(accel.key) else {
+        log::error!("Key {:?} is not supported by menu accelerator", accel.key);
+        return None;
+    };
+    let mut mods = ModifiersState::empty();
+    let AppModifiers { ctrl, shift, alt, meta } = accel.modifiers;
+    for (enabled, modifier) in [
+        (ctrl, ModifiersState::CONTROL),
+        (shift, ModifiersState::SHIFT),
+        (alt, ModifiersState::ALT),
+        (meta, ModifiersState::SUPER),
+    ] {
+        if enabled {
+            mods |= modifier;
+        }
+    }
+    Some(Accelerator::new(Some(mods), key))
+}
+
+#[rustfmt::skip]
+fn key_code(key: &str) -> Option<KeyCode> {
+    use KeyCode::*;
+    let code = match key {
+        "a" => KeyA, "b" => KeyB, "c" => KeyC, "d" => KeyD, "e" => KeyE, "f" => KeyF, "g" => KeyG,
+        "h" => KeyH, "i" => KeyI, "j" => KeyJ, "k" => KeyK, "l" => KeyL, "m" => KeyM, "n" => KeyN,
+        "o" => KeyO, "p" => KeyP, "q" => KeyQ, "r" => KeyR, "s" => KeyS, "t" => KeyT, "u" => KeyU,
+        "v" => KeyV, "w" => KeyW, "x" => KeyX, "y" => KeyY, "z" => KeyZ,
+        "0" => Digit0, "1" => Digit1, "2" => Digit2, "3" => Digit3, "4" => Digit4,
+        "5" => Digit5, "6" => Digit6, "7" => Digit7, "8" => Digit8, "9" => Digit9,
+        "f1" => F1, "f2" => F2, "f3" => F3, "f4" => F4, "f5" => F5, "f6" => F6,
+        "f7" => F7, "f8" => F8, "f9" => F9, "f10" => F10, "f11" => F11, "f12" => F12,
+        "plus" => Plus, "-" => Minus, "=" => Equal, "," => Comma, "." => Period, "/" => Slash,
+        "\\" => Backslash, ";" => Semicolon, "'" => Quote, "`" => Backquote,
+        "[" => BracketLeft, "]" => BracketRight,
+        "space" => Space, "enter" => Enter, "tab" => Tab, "esc" => Escape,
+        "backspace" => Backspace, "del" => Delete,
+        "up" => ArrowUp, "down" => ArrowDown, "left" => ArrowLeft, "right" => ArrowRight,
+        "home" => Home, "end" => End, "pageup" => PageUp, "pagedown" => PageDown,
+        _ => return None,
+    };
+    Some(code)
+}
+
+struct MenuBuilder<'a> {
+    ids: HashMap<MenuId, AppMenuItem>,
+    config: &'a MenuConfig,
+    binds: HashMap<Command, Vec<&'a str>>,
+}
+
+impl<'a> MenuBuilder<'a> {
+    fn new(config: &'a Config) -> Self {
+        let mut binds: HashMap<_, Vec<_>> = HashMap::new();
+        for (bind, command) in config.keymaps() {
+            binds.entry(*command).or_default().push(bind.as_str());
+        }
+        for binds in binds.values_mut() {
+            binds.sort_unstable();
+        }
+        Self { ids: HashMap::new(), config: config.menu(), binds }
+    }
+
+    fn add(&mut self, menu: &mut MenuBar, command: Command) {
+        let accel = self.config.accelerator(command);
+
+        // Show key mappings for the command in the label except for the one which is the same as the accelerator
+        let binds: Vec<&str> = self
+            .binds
+            .get(&command)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|bind| !matches!((accel, bind.parse()), (Some(a), Ok(b)) if a == b))
+            .collect();
+        let label = command.spec().label;
+        let label = if binds.is_empty() {
+            label.into()
+        } else {
+            format!("{} ({})", label, binds.join(", "))
+        };
+
+        let mut attrs = MenuItemAttributes::new(&label);
+        let accel = accel.and_then(to_wry_accelerator);
+        if let Some(accel) = &accel {
+            attrs = attrs.with_accelerators(accel);
+        }
+        let item = menu.add_item(attrs);
+        self.ids.insert(item.id(), AppMenuItem::Command(command));
+    }
+
+    fn add_path(&mut self, menu: &mut MenuBar, path: &Path, item: fn(PathBuf) -> AppMenuItem) {
+        let label = path.to_string_lossy();
+        let id = menu.add_item(MenuItemAttributes::new(&label)).id();
+        self.ids.insert(id, item(path.to_path_buf()));
+    }
+
+    fn add_bookmarks(
+        &mut self,
+        menu: &mut MenuBar,
+        bookmarks: &[Bookmark],
+        item: fn(Bookmark) -> AppMenuItem,
+    ) {
+        for bookmark in bookmarks {
+            let id = menu.add_item(MenuItemAttributes::new(&bookmark.label())).id();
+            self.ids.insert(id, item(bookmark.clone()));
+        }
+    }
+}
+
 pub struct MenuIds(HashMap<MenuId, AppMenuItem>);
 
 impl MenuIds {
-    pub fn set_menu(root_menu: &mut MenuBar) -> Self {
-        #[cfg(target_os = "macos")]
-        const MOD: ModifiersState = ModifiersState::SUPER;
-        #[cfg(not(target_os = "macos"))]
-        const MOD: ModifiersState = ModifiersState::CONTROL;
-
+    pub fn set_menu(
+        root_menu: &mut MenuBar,
+        config: &Config,
+        recent_files: &[&Path],
+        bookmarks: &[Bookmark],
+    ) -> Self {
         // Windows / macOS / Android / iOS: The metadata is ignored on these platforms.
         #[cfg(target_os = "linux")]
         let metadata = AboutMetadata {
@@ -31,15 +144,28 @@ impl MenuIds {
 
         // Note: Some native menu items are not supported by Windows. Those items are actually not inserted into menu bar.
 
+        let mut ids = MenuBuilder::new(config);
+
         let mut file_menu = MenuBar::new();
-        let cmd_o = Accelerator::new(Some(MOD), KeyCode::KeyO);
-        let open_file =
-            file_menu.add_item(MenuItemAttributes::new("Open File…").with_accelerators(&cmd_o));
-        let cmd_shift_o = Accelerator::new(Some(MOD | ModifiersState::SHIFT), KeyCode::KeyO);
-        let watch_dir = file_menu
-            .add_item(MenuItemAttributes::new("Watch Directory…").with_accelerators(&cmd_shift_o));
+        ids.add(&mut file_menu, Command::OpenFile);
+        ids.add(&mut file_menu, Command::FileFinder);
+        ids.add(&mut file_menu, Command::OpenDir);
+        let mut recent_menu = MenuBar::new();
+        for path in recent_files {
+            ids.add_path(&mut recent_menu, path, AppMenuItem::OpenRecent);
+        }
+        if !recent_files.is_empty() {
+            recent_menu.add_native_item(MenuItem::Separator);
+            let mut remove_menu = MenuBar::new();
+            for path in recent_files {
+                ids.add_path(&mut remove_menu, path, AppMenuItem::RemoveRecent);
+            }
+            recent_menu.add_submenu("Remove from Recent", true, remove_menu);
+        }
+        ids.add(&mut recent_menu, Command::ClearRecent);
+        file_menu.add_submenu("Open Recent", !recent_files.is_empty(), recent_menu);
         file_menu.add_native_item(MenuItem::Separator);
-        let print = file_menu.add_item(MenuItemAttributes::new("Print…"));
+        ids.add(&mut file_menu, Command::Print);
         file_menu.add_native_item(MenuItem::Separator);
         #[cfg(not(target_os = "windows"))]
         {
@@ -52,8 +178,7 @@ impl MenuIds {
         file_menu.add_native_item(MenuItem::HideOthers);
         file_menu.add_native_item(MenuItem::ShowAll);
         file_menu.add_native_item(MenuItem::Separator);
-        let cmd_q = Accelerator::new(Some(MOD), KeyCode::KeyQ);
-        let quit = file_menu.add_item(MenuItemAttributes::new("Quit").with_accelerators(&cmd_q));
+        ids.add(&mut file_menu, Command::Quit);
         root_menu.add_submenu("File", true, file_menu);
 
         let mut edit_menu = MenuBar::new();
@@ -65,91 +190,90 @@ impl MenuIds {
         edit_menu.add_native_item(MenuItem::Paste);
         edit_menu.add_native_item(MenuItem::SelectAll);
         edit_menu.add_native_item(MenuItem::Separator);
-        let cmd_f = Accelerator::new(Some(MOD), KeyCode::KeyF);
-        let search =
-            edit_menu.add_item(MenuItemAttributes::new("Search…").with_accelerators(&cmd_f));
-        let cmd_g = Accelerator::new(Some(MOD), KeyCode::KeyG);
-        let search_next =
-            edit_menu.add_item(MenuItemAttributes::new("Search Next").with_accelerators(&cmd_g));
-        let cmd_shift_g = Accelerator::new(Some(MOD | ModifiersState::SHIFT), KeyCode::KeyG);
-        let search_prev = edit_menu
-            .add_item(MenuItemAttributes::new("Search Previous").with_accelerators(&cmd_shift_g));
-        let cmd_s = Accelerator::new(Some(MOD), KeyCode::KeyS);
-        let outline = edit_menu
-            .add_item(MenuItemAttributes::new("Section Outline…").with_accelerators(&cmd_s));
+        ids.add(&mut edit_menu, Command::Search);
+        ids.add(&mut edit_menu, Command::NextSearch);
+        ids.add(&mut edit_menu, Command::PrevSearch);
+        ids.add(&mut edit_menu, Command::WorkspaceSearch);
+        ids.add(&mut edit_menu, Command::Outline);
+        edit_menu.add_native_item(MenuItem::Separator);
+        ids.add(&mut edit_menu, Command::CommandPalette);
         root_menu.add_submenu("Edit", true, edit_menu);
 
+        let mut go_menu = MenuBar::new();
+        ids.add(&mut go_menu, Command::ScrollDown);
+        ids.add(&mut go_menu, Command::ScrollUp);
+        ids.add(&mut go_menu, Command::ScrollLeft);
+        ids.add(&mut go_menu, Command::ScrollRight);
+        go_menu.add_native_item(MenuItem::Separator);
+        ids.add(&mut go_menu, Command::ScrollPageDown);
+        ids.add(&mut go_menu, Command::ScrollPageUp);
+        ids.add(&mut go_menu, Command::ScrollTop);
+        ids.add(&mut go_menu, Command::ScrollBottom);
+        go_menu.add_native_item(MenuItem::Separator);
+        ids.add(&mut go_menu, Command::ScrollNextSection);
+        ids.add(&mut go_menu, Command::ScrollPrevSection);
+        root_menu.add_submenu("Go", true, go_menu);
+
         let mut display_menu = MenuBar::new();
-        let cmd_r = Accelerator::new(Some(MOD), KeyCode::KeyR);
-        let reload =
-            display_menu.add_item(MenuItemAttributes::new("Reload").with_accelerators(&cmd_r));
+        ids.add(&mut display_menu, Command::Reload);
         display_menu.add_native_item(MenuItem::Separator);
         #[cfg(not(target_os = "windows"))]
         {
             display_menu.add_native_item(MenuItem::EnterFullScreen);
             display_menu.add_native_item(MenuItem::Separator);
         }
-        let cmd_plus = Accelerator::new(Some(MOD), KeyCode::Plus);
-        let zoom_in =
-            display_menu.add_item(MenuItemAttributes::new("Zoom In").with_accelerators(&cmd_plus));
-        let cmd_minus = Accelerator::new(Some(MOD), KeyCode::Minus);
-        let zoom_out = display_menu
-            .add_item(MenuItemAttributes::new("Zoom Out").with_accelerators(&cmd_minus));
+        ids.add(&mut display_menu, Command::ZoomIn);
+        ids.add(&mut display_menu, Command::ZoomOut);
+        display_menu.add_native_item(MenuItem::Separator);
+        ids.add(&mut display_menu, Command::Sidebar);
+        ids.add(&mut display_menu, Command::ToggleTheme);
+        ids.add(&mut display_menu, Command::HighlightThemes);
         root_menu.add_submenu("Display", true, display_menu);
 
         let mut history_menu = MenuBar::new();
-        let cmd_left_bracket = Accelerator::new(Some(MOD), KeyCode::BracketRight);
-        let forward = history_menu
-            .add_item(MenuItemAttributes::new("Forward").with_accelerators(&cmd_left_bracket));
-        let cmd_right_bracket = Accelerator::new(Some(MOD), KeyCode::BracketLeft);
-        let back = history_menu
-            .add_item(MenuItemAttributes::new("Back").with_accelerators(&cmd_right_bracket));
+        ids.add(&mut history_menu, Command::Forward);
+        ids.add(&mut history_menu, Command::Back);
         history_menu.add_native_item(MenuItem::Separator);
-        let cmd_y = Accelerator::new(Some(MOD), KeyCode::KeyY);
-        let history =
-            history_menu.add_item(MenuItemAttributes::new("History…").with_accelerators(&cmd_y));
+        ids.add(&mut history_menu, Command::History);
+        ids.add(&mut history_menu, Command::PinRecent);
         root_menu.add_submenu("History", true, history_menu);
 
+        let mut bookmarks_menu = MenuBar::new();
+        ids.add(&mut bookmarks_menu, Command::BookmarkFile);
+        ids.add(&mut bookmarks_menu, Command::BookmarkHeading);
+        ids.add(&mut bookmarks_menu, Command::Bookmarks);
+        if !bookmarks.is_empty() {
+            bookmarks_menu.add_native_item(MenuItem::Separator);
+            ids.add_bookmarks(&mut bookmarks_menu, bookmarks, AppMenuItem::OpenBookmark);
+            bookmarks_menu.add_native_item(MenuItem::Separator);
+            let mut remove_menu = MenuBar::new();
+            ids.add_bookmarks(&mut remove_menu, bookmarks, AppMenuItem::RemoveBookmark);
+            bookmarks_menu.add_submenu("Remove Bookmark", true, remove_menu);
+        }
+        root_menu.add_submenu("Bookmarks", true, bookmarks_menu);
+
         let mut window_menu = MenuBar::new();
         window_menu.add_native_item(MenuItem::Minimize);
         window_menu.add_native_item(MenuItem::Zoom);
-        let toggle_always_on_top =
-            window_menu.add_item(MenuItemAttributes::new("Pin/Unpin On Top"));
+        ids.add(&mut window_menu, Command::ToggleAlwaysOnTop);
         root_menu.add_submenu("Window", true, window_menu);
 
         let mut help_menu = MenuBar::new();
-        let guide = help_menu.add_item(MenuItemAttributes::new("Show Guide…"));
-        let open_repo = help_menu.add_item(MenuItemAttributes::new("Open Repository Page"));
+        ids.add(&mut help_menu, Command::Help);
+        ids.add(&mut help_menu, Command::OpenRepo);
         root_menu.add_submenu("Help", true, help_menu);
 
         log::debug!("Added menubar to window");
 
-        #[rustfmt::skip]
-        let ids = HashMap::from_iter({
-            use AppMenuItem::*;
-
-            [
-                (open_file.id(),            OpenFile),
-                (watch_dir.id(),            WatchDir),
-                (quit.id(),                 Quit),
-                (forward.id(),              Forward),
-                (back.id(),                 Back),
-                (reload.id(),               Reload),
-                (search.id(),               Search),
-                (search_next.id(),          SearchNext),
-                (search_prev.id(),          SearchPrevious),
-                (outline.id(),              Outline),
-                (print.id(),                Print),
-                (zoom_in.id(),              ZoomIn),
-                (zoom_out.id(),             ZoomOut),
-                (history.id(),              History),
-                (toggle_always_on_top.id(), ToggleAlwaysOnTop),
-                (guide.id(),                Help),
-                (open_repo.id(),            OpenRepo),
-            ]
-        });
-
-        Self(ids)
+        debug_assert!(
+            COMMANDS.iter().all(|spec| ids
+                .ids
+                .values()
+                .any(|i| matches!(i, AppMenuItem::Command(c) if *c == spec.command))),
+            "all commands must be put in the menu",
+        );
+
+        Self(ids.ids)
     }
 }
 
@@ -157,7 +281,7 @@ impl MenuItems for MenuIds {
     type ItemId = MenuId;
 
     fn item_from_id(&self, id: Self::ItemId) -> Result<AppMenuItem> {
-        if let Some(item) = self.0.get(&id).copied() {
+        if let Some(item) = self.0.get(&id).cloned() {
             Ok(item)
         } else {
             Err(anyhow::anyhow!("Unknown menu item id: {:?}", id))
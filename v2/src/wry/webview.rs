@@ -1,13 +1,16 @@
 use crate::assets::Assets;
 use crate::cli::Options;
 use crate::config::{Config, WindowTheme as ThemeConfig};
-use crate::persistent::WindowState;
+use crate::persistent::{Bookmark, WindowState};
 use crate::renderer::{
     MessageFromRenderer, MessageToRenderer, RawMessageWriter, Renderer, Theme as RendererTheme,
     UserEvent, ZoomLevel,
 };
 use crate::wry::menu::MenuIds;
 use anyhow::Result;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
 use wry::application::dpi::{PhysicalPosition, PhysicalSize};
 use wry::application::event_loop::EventLoop as WryEventLoop;
 use wry::application::menu::MenuBar;
@@ -18,8 +21,8 @@ use wry::http::Response;
 use wry::webview::WebViewBuilderExtWindows;
 use wry::webview::{FileDropEvent, WebView, WebViewBuilder};
 
-fn window_theme(window: &Window) -> RendererTheme {
-    match window.theme() {
+pub fn renderer_theme(theme: Theme) -> RendererTheme {
+    match theme {
         Theme::Light => RendererTheme::Light,
         Theme::Dark => RendererTheme::Dark,
         t => {
@@ -29,15 +32,21 @@ fn window_theme(window: &Window) -> RendererTheme {
     }
 }
 
+fn window_theme(window: &Window) -> RendererTheme {
+    renderer_theme(window.theme())
+}
+
 fn create_webview(
     window: Window,
     event_loop: &WryEventLoop<UserEvent>,
     config: &Config,
-) -> Result<WebView> {
+) -> Result<(WebView, Rc<RefCell<Assets>>)> {
     let ipc_proxy = event_loop.create_proxy();
     let file_drop_proxy = event_loop.create_proxy();
     let navigation_proxy = event_loop.create_proxy();
-    let loader = Assets::new(config, window_theme(&window));
+    // Assets are shared with the renderer to rebuild them when the config is reloaded
+    let assets = Rc::new(RefCell::new(Assets::new(config, window_theme(&window))));
+    let loader = assets.clone();
 
     #[allow(unused_mut)]
     let mut builder = WebViewBuilder::new(window)?
@@ -112,7 +121,7 @@ fn create_webview(
             let uri = request.uri();
             log::debug!("Handling custom protocol: {:?}", uri);
             let path = uri.path();
-            let (body, mime) = loader.load(path);
+            let (body, mime) = loader.borrow().load(path);
             let status = if body.is_empty() { 404 } else { 200 };
             // Response body of custom protocol handler requires `Vec<u8>`
             Response::builder()
@@ -134,11 +143,12 @@ fn create_webview(
         }
     }
 
-    builder.build().map_err(Into::into)
+    Ok((builder.build()?, assets))
 }
 
 pub struct WryRenderer {
     webview: WebView,
+    assets: Rc<RefCell<Assets>>,
     menu_ids: MenuIds,
     zoom_level: ZoomLevel,
     always_on_top: bool,
@@ -155,7 +165,7 @@ impl Renderer for WryRenderer {
         window_state: Option<WindowState>,
     ) -> Result<Self> {
         let mut menu = MenuBar::new();
-        let menu_ids = MenuIds::set_menu(&mut menu);
+        let menu_ids = MenuIds::set_menu(&mut menu, config, &[], &[]);
 
         let mut builder =
             WindowBuilder::new().with_title("Shiba").with_menu(menu).with_visible(false);
@@ -192,7 +202,7 @@ impl Renderer for WryRenderer {
         let window = builder.build(event_loop)?;
         log::debug!("Event loop and window were created successfully");
 
-        let webview = create_webview(window, event_loop, config)?;
+        let (webview, assets) = create_webview(window, event_loop, config)?;
         log::debug!("WebView was created successfully with options: {:?}", options);
 
         if zoom_level.factor() != 1.0 {
@@ -205,13 +215,24 @@ impl Renderer for WryRenderer {
             log::debug!("Opened DevTools for debugging");
         }
 
-        Ok(WryRenderer { webview, menu_ids, zoom_level, always_on_top })
+        Ok(WryRenderer { webview, assets, menu_ids, zoom_level, always_on_top })
     }
 
     fn menu(&self) -> &Self::Menu {
         &self.menu_ids
     }
 
+    fn update_menu(&mut self, config: &Config, recent_files: &[&Path], bookmarks: &[Bookmark]) {
+        let mut menu = MenuBar::new();
+        self.menu_ids = MenuIds::set_menu(&mut menu, config, recent_files, bookmarks);
+        self.webview.window().set_menu(Some(menu));
+        log::debug!(
+            "Updated menubar with {} recent files and {} bookmarks",
+            recent_files.len(),
+            bookmarks.len(),
+        );
+    }
+
     fn send_message(&self, message: MessageToRenderer) -> Result<()> {
         let mut buf = b"window.postShibaMessageFromMain(".to_vec();
         serde_json::to_writer(&mut buf, &message)?;
@@ -233,6 +254,11 @@ impl Renderer for WryRenderer {
         self.webview.window().set_title(title);
     }
 
+    fn reload_assets(&self, config: &Config) {
+        log::debug!("Rebuild assets with the new config");
+        *self.assets.borrow_mut() = Assets::new(config, self.theme());
+    }
+
     fn window_state(&self) -> Option<WindowState> {
         let w = self.webview.window();
         let PhysicalPosition { x, y } = match w.inner_position() {
@@ -253,6 +279,27 @@ impl Renderer for WryRenderer {
         window_theme(self.webview.window())
     }
 
+    // Explicitly switching the theme stops following the system theme until the next launch
+    fn set_theme(&self, theme: RendererTheme) {
+        log::debug!("Set window theme: {:?}", theme);
+        let window_theme = match theme {
+            RendererTheme::Light => Theme::Light,
+            RendererTheme::Dark => Theme::Dark,
+        };
+        self.webview.window().set_theme(Some(window_theme));
+        self.assets.borrow_mut().set_theme(theme);
+    }
+
+    fn on_theme_changed(&self, theme: RendererTheme) {
+        log::debug!("Window theme was changed: {:?}", theme);
+        self.assets.borrow_mut().set_theme(theme);
+    }
+
+    fn set_highlight_theme(&self, config: &Config, theme: Option<&str>) {
+        log::debug!("Set highlight theme: {:?}", theme);
+        self.assets.borrow_mut().set_highlight_theme(config, theme);
+    }
+
     fn show(&self) {
         self.webview.window().set_visible(true);
     }
@@ -3,11 +3,18 @@ import { useReducer, useEffect } from 'react';
 import { ThemeProvider, createTheme } from '@mui/material/styles';
 import { Preview } from './Preview';
 import { Search } from './Search';
+import { SearchResults } from './SearchResults';
 import { Welcome } from './Welcome';
 import { Outline } from './Outline';
 import { History } from './History';
+import { Bookmarks } from './Bookmarks';
+import { FileFinder } from './FileFinder';
+import { WorkspaceSearch } from './WorkspaceSearch';
+import { HighlightThemes } from './HighlightThemes';
 import { Guide } from './Guide';
+import { CommandPalette } from './CommandPalette';
 import { Notification } from './Notification';
+import { Sidebar, SIDEBAR_WIDTH } from './Sidebar';
 import { sendMessage } from '../ipc';
 import { INITIAL_STATE, reducer } from '../reducer';
 import type { GlobalDispatcher } from '../dispatcher';
@@ -24,8 +31,14 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
     const {
         previewTree,
         searching,
+        searchQuery,
         searchIndex,
+        searchMatchCount,
         matcher,
+        scope,
+        searchResults,
+        resultsPanel,
+        searchHistory,
         outline,
         theme,
         history,
@@ -34,18 +47,44 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
         notifying,
         notification,
         welcome,
+        sidebar,
+        fileTree,
+        currentFile,
+        finder,
+        foundFiles,
+        workspaceSearch,
+        workspaceMatches,
+        highlightThemes,
+        commandPalette,
+        bookmarks,
+        bookmarkPalette,
     } = state;
 
     let searchInput;
     if (searching && !welcome) {
         searchInput = (
-            <Search index={searchIndex} total={previewTree.matchCount} matcher={matcher} dispatch={dispatch} />
+            <Search
+                query={searchQuery}
+                index={searchIndex}
+                total={searchMatchCount}
+                matcher={matcher}
+                scope={scope}
+                resultsPanel={resultsPanel}
+                history={searchHistory}
+                dispatch={dispatch}
+                key={searchQuery}
+            />
         );
     }
 
+    let searchResultsPanel;
+    if (searching && resultsPanel && !welcome) {
+        searchResultsPanel = <SearchResults results={searchResults} index={searchIndex} dispatch={dispatch} />;
+    }
+
     let welcomePage;
     if (welcome) {
-        welcomePage = <Welcome />;
+        welcomePage = <Welcome files={files} />;
     }
 
     let outlineDialog;
@@ -58,24 +97,82 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
         historyDialog = <History history={files} dispatch={dispatch} />;
     }
 
+    let bookmarksDialog;
+    if (bookmarkPalette) {
+        bookmarksDialog = <Bookmarks bookmarks={bookmarks} dispatch={dispatch} />;
+    }
+
+    let finderDialog;
+    if (finder) {
+        finderDialog = <FileFinder files={foundFiles} dispatch={dispatch} />;
+    }
+
+    let workspaceSearchDialog;
+    if (workspaceSearch) {
+        workspaceSearchDialog = (
+            <WorkspaceSearch matches={workspaceMatches} matcher={matcher} scope={scope} dispatch={dispatch} />
+        );
+    }
+
+    let highlightThemesDialog;
+    if (highlightThemes !== null) {
+        highlightThemesDialog = (
+            <HighlightThemes themes={highlightThemes.themes} current={highlightThemes.current} dispatch={dispatch} />
+        );
+    }
+
+    let commandPaletteDialog;
+    if (commandPalette) {
+        commandPaletteDialog = (
+            <CommandPalette
+                shortcuts={dispatcher.keymap.shortcuts}
+                externalCommands={dispatcher.keymap.externalCommands}
+                dispatcher={dispatcher}
+            />
+        );
+    }
+
     let guideDialog;
     if (help) {
         guideDialog = <Guide shortcuts={dispatcher.keymap.shortcuts} dispatcher={dispatcher} />;
     }
 
+    let previewStyle;
+    if (sidebar) {
+        previewStyle = { marginLeft: `${SIDEBAR_WIDTH}px` };
+    }
+
     useEffect(() => {
         dispatcher.setDispatch(dispatch, state);
     });
     useEffect(() => {
         sendMessage({ kind: 'init' });
     }, []); // Run only when component was mounted
+    useEffect(() => {
+        const handleScroll = (): void => {
+            dispatcher.scheduleScrollReport();
+        };
+        window.addEventListener('scroll', handleScroll, { passive: true });
+        return () => {
+            window.removeEventListener('scroll', handleScroll);
+        };
+    }, [dispatcher]);
 
     return (
         <ThemeProvider theme={theme === 'light' ? LIGHT_THEME : DARK_THEME}>
-            <Preview tree={previewTree} />
+            <Sidebar open={sidebar} roots={fileTree} current={currentFile} />
+            <div style={previewStyle}>
+                <Preview tree={previewTree} />
+            </div>
             {searchInput}
+            {searchResultsPanel}
             {outlineDialog}
             {historyDialog}
+            {bookmarksDialog}
+            {finderDialog}
+            {workspaceSearchDialog}
+            {highlightThemesDialog}
+            {commandPaletteDialog}
             {guideDialog}
             {welcomePage}
             <Notification open={notifying} content={notification} dispatch={dispatch} />
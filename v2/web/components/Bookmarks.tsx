@@ -0,0 +1,70 @@
+import * as React from 'react';
+import { useMemo, useCallback } from 'react';
+import { Palette } from './Palette';
+import { type Dispatch, closeBookmarks } from '../reducer';
+import { sendMessage, type Bookmark } from '../ipc';
+import * as log from '../log';
+
+const PATH_STYLE: React.CSSProperties = {
+    marginLeft: '8px',
+    opacity: 0.6,
+};
+
+interface BookmarkItem {
+    text: string;
+    path: string;
+    heading: string | null;
+}
+
+function bookmarkText({ path, heading, label }: Bookmark): string {
+    if (label !== null) {
+        return label;
+    }
+    const file = path.split(/[\\/]/).pop() ?? path;
+    return heading === null ? file : `${file} › ${heading}`;
+}
+
+function renderBookmarkItem(item: BookmarkItem): React.ReactNode {
+    return (
+        <>
+            {item.text}
+            <span style={PATH_STYLE}>{item.path}</span>
+        </>
+    );
+}
+
+export interface Props {
+    bookmarks: Bookmark[];
+    dispatch: Dispatch;
+}
+
+export const Bookmarks: React.FC<Props> = ({ bookmarks, dispatch }) => {
+    const items = useMemo(
+        () => bookmarks.map(bookmark => ({ ...bookmark, text: bookmarkText(bookmark) })),
+        [bookmarks],
+    );
+
+    const handleClose = useCallback(() => {
+        dispatch(closeBookmarks());
+    }, [dispatch]);
+
+    const handleSelect = useCallback(
+        (item: BookmarkItem) => {
+            log.debug('Opening bookmark:', item);
+            // The bookmark is identified by its path and heading since the list may be stale
+            sendMessage({ kind: 'open_bookmark', path: item.path, heading: item.heading });
+            dispatch(closeBookmarks());
+        },
+        [dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search bookmarks…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderBookmarkItem}
+        />
+    );
+};
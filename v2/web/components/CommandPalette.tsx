@@ -0,0 +1,87 @@
+import * as React from 'react';
+import { useMemo, useCallback } from 'react';
+import Chip from '@mui/material/Chip';
+import { Palette } from './Palette';
+import { closeCommandPalette } from '../reducer';
+import type { GlobalDispatcher } from '../dispatcher';
+import { type KeyShortcut, runExternalCommand } from '../keymaps';
+import type { ExternalCommand } from '../ipc';
+
+const ITEM_STYLE: React.CSSProperties = {
+    display: 'flex',
+    width: '100%',
+};
+const BINDS_STYLE: React.CSSProperties = {
+    marginLeft: 'auto',
+};
+const BIND_CHIP_STYLE: React.CSSProperties = {
+    marginLeft: '4px',
+};
+
+interface CommandItem {
+    text: string;
+    binds: string[];
+    run: () => void;
+}
+
+export interface Props {
+    shortcuts: KeyShortcut[];
+    externalCommands: ExternalCommand[];
+    dispatcher: GlobalDispatcher;
+}
+
+function renderCommandItem(item: CommandItem): React.ReactNode {
+    return (
+        <div style={ITEM_STYLE}>
+            {item.text}
+            <span style={BINDS_STYLE}>
+                {item.binds.map((b, i) => (
+                    <Chip key={i} size="small" label={b} style={BIND_CHIP_STYLE} />
+                ))}
+            </span>
+        </div>
+    );
+}
+
+export const CommandPalette: React.FC<Props> = ({ shortcuts, externalCommands, dispatcher }) => {
+    const items = useMemo(() => {
+        const items: CommandItem[] = shortcuts
+            .filter(s => s.command !== 'CommandPalette')
+            .map(({ label, command, binds }) => ({
+                text: label.replace(/…$/, ''),
+                binds,
+                run: () => dispatcher.runCommand(command),
+            }));
+        for (const [index, { name, key }] of externalCommands.entries()) {
+            items.push({
+                text: `Run: ${name}`,
+                binds: key === null ? [] : [key],
+                run: () => runExternalCommand(index),
+            });
+        }
+        return items;
+    }, [shortcuts, externalCommands, dispatcher]);
+
+    const handleClose = useCallback(() => {
+        dispatcher.dispatch(closeCommandPalette());
+    }, [dispatcher]);
+
+    const handleSelect = useCallback(
+        (item: CommandItem) => {
+            // Close the palette before running the command since the command may open another dialog
+            dispatcher.dispatch(closeCommandPalette());
+            item.run();
+        },
+        [dispatcher],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search commands…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderCommandItem}
+        />
+    );
+};
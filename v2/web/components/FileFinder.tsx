@@ -0,0 +1,81 @@
+import * as React from 'react';
+import { useMemo, useCallback, useEffect } from 'react';
+import { Palette } from './Palette';
+import { type Dispatch, closeFinder } from '../reducer';
+import { sendMessage, type FoundFile } from '../ipc';
+import * as log from '../log';
+
+interface FinderItem {
+    text: string;
+    positions: number[];
+}
+
+const MATCH_STYLE: React.CSSProperties = {
+    fontWeight: 'bold',
+};
+
+function renderFinderItem(item: FinderItem): React.ReactNode {
+    if (item.positions.length === 0) {
+        return item.text;
+    }
+    // Positions are indices of characters. Split the text by code points rather than UTF-16 code units.
+    const chars = Array.from(item.text);
+    const nodes = [];
+    let start = 0;
+    for (const pos of item.positions) {
+        if (start < pos) {
+            nodes.push(chars.slice(start, pos).join(''));
+        }
+        nodes.push(
+            <span style={MATCH_STYLE} key={pos}>
+                {chars[pos]}
+            </span>,
+        );
+        start = pos + 1;
+    }
+    if (start < chars.length) {
+        nodes.push(chars.slice(start).join(''));
+    }
+    return nodes;
+}
+
+function findFiles(query: string): void {
+    sendMessage({ kind: 'find_files', query });
+}
+
+export interface Props {
+    files: FoundFile[];
+    dispatch: Dispatch;
+}
+
+export const FileFinder: React.FC<Props> = ({ files, dispatch }) => {
+    const items = useMemo(() => files.map(({ path, positions }) => ({ text: path, positions })), [files]);
+
+    useEffect(() => {
+        findFiles(''); // List candidates before the first input
+    }, []);
+
+    const handleClose = useCallback(() => {
+        dispatch(closeFinder());
+    }, [dispatch]);
+
+    const handleSelect = useCallback(
+        (item: FinderItem) => {
+            log.debug('Opening file via finder:', item.text);
+            sendMessage({ kind: 'open_file', path: item.text });
+            dispatch(closeFinder());
+        },
+        [dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Go to file…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderFinderItem}
+            onQuery={findFiles}
+        />
+    );
+};
@@ -14,7 +14,7 @@ import IconButton from '@mui/material/IconButton';
 import CloseIcon from '@mui/icons-material/Close';
 import Typography from '@mui/material/Typography';
 import Link from '@mui/material/Link';
-import type { BoundShortcut } from '../keymaps';
+import type { KeyShortcut } from '../keymaps';
 import type { GlobalDispatcher } from '../dispatcher';
 import { closeHelp } from '../reducer';
 
@@ -36,11 +36,12 @@ const BIND_CHIP_STYLE: React.CSSProperties = {
 };
 
 export interface Props {
-    shortcuts: BoundShortcut[];
+    shortcuts: KeyShortcut[];
     dispatcher: GlobalDispatcher;
 }
 
 export const Guide: React.FC<Props> = ({ shortcuts, dispatcher }) => {
+    const bound = shortcuts.filter(s => s.binds.length > 0);
     const handleClose = (): void => {
         dispatcher.dispatch(closeHelp());
     };
@@ -63,13 +64,13 @@ export const Guide: React.FC<Props> = ({ shortcuts, dispatcher }) => {
                             </TableRow>
                         </TableHead>
                         <TableBody>
-                            {shortcuts.map((shortcut, i) => (
+                            {bound.map((shortcut, i) => (
                                 <TableRow
                                     key={i}
                                     hover
                                     onClick={() => {
                                         handleClose();
-                                        shortcut.dispatch(dispatcher);
+                                        dispatcher.runCommand(shortcut.command);
                                     }}
                                     style={KEYBIND_ROW_STYLE}
                                 >
@@ -0,0 +1,56 @@
+import * as React from 'react';
+import { useMemo, useCallback } from 'react';
+import { Palette } from './Palette';
+import { type Dispatch, closeHighlightThemes } from '../reducer';
+import { sendMessage } from '../ipc';
+import * as log from '../log';
+
+interface ThemeItem {
+    text: string;
+}
+
+export interface Props {
+    themes: string[];
+    current: string;
+    dispatch: Dispatch;
+}
+
+function renderThemeItem(item: ThemeItem): React.ReactNode {
+    return item.text;
+}
+
+// The focused theme is previewed immediately. Closing the palette without selecting a theme restores the previous one.
+export const HighlightThemes: React.FC<Props> = ({ themes, current, dispatch }) => {
+    const items = useMemo(() => themes.map(text => ({ text })), [themes]);
+    const initialIndex = Math.max(themes.indexOf(current), 0);
+
+    const handleClose = useCallback(() => {
+        sendMessage({ kind: 'preview_highlight_theme', name: null });
+        dispatch(closeHighlightThemes());
+    }, [dispatch]);
+
+    const handleFocus = useCallback((item: ThemeItem) => {
+        sendMessage({ kind: 'preview_highlight_theme', name: item.text });
+    }, []);
+
+    const handleSelect = useCallback(
+        (item: ThemeItem) => {
+            log.debug('Selected highlight theme:', item.text);
+            sendMessage({ kind: 'preview_highlight_theme', name: item.text });
+            dispatch(closeHighlightThemes());
+        },
+        [dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search code highlight themes…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderThemeItem}
+            onFocus={handleFocus}
+            initialIndex={initialIndex}
+        />
+    );
+};
@@ -1,28 +1,57 @@
 import * as React from 'react';
 import { useMemo, useCallback } from 'react';
+import IconButton from '@mui/material/IconButton';
+import PushPinIcon from '@mui/icons-material/PushPin';
+import PushPinOutlinedIcon from '@mui/icons-material/PushPinOutlined';
 import { Palette } from './Palette';
 import { type Dispatch, closeHistory } from '../reducer';
-import { sendMessage } from '../ipc';
+import { sendMessage, type RecentFile } from '../ipc';
 import * as log from '../log';
 
+const ITEM_STYLE: React.CSSProperties = {
+    display: 'flex',
+    alignItems: 'center',
+    width: '100%',
+};
+const PATH_STYLE: React.CSSProperties = {
+    flexGrow: 1,
+};
+
 interface HistoryItem {
     text: string;
+    pinned: boolean;
 }
 
-export interface Props {
-    history: string[];
-    dispatch: Dispatch;
+function onPinClick(e: React.MouseEvent<HTMLElement>, item: HistoryItem): void {
+    e.stopPropagation(); // Do not open the file
+    sendMessage({ kind: 'toggle_pin_recent_file', path: item.text });
 }
 
 function renderHistoryItem(item: HistoryItem): React.ReactNode {
-    return item.text;
+    return (
+        <span style={ITEM_STYLE}>
+            <span style={PATH_STYLE}>{item.text}</span>
+            <IconButton
+                size="small"
+                title={item.pinned ? 'Unpin' : 'Pin'}
+                onClick={e => {
+                    onPinClick(e, item);
+                }}
+            >
+                {item.pinned ? <PushPinIcon fontSize="small" /> : <PushPinOutlinedIcon fontSize="small" />}
+            </IconButton>
+        </span>
+    );
+}
+
+export interface Props {
+    history: RecentFile[];
+    dispatch: Dispatch;
 }
 
 export const History: React.FC<Props> = ({ history, dispatch }) => {
-    const items = useMemo(() => {
-        const items = history.map(path => ({ text: path }));
-        return items.reverse();
-    }, [history]);
+    // Files are already ranked by frecency in the main process
+    const items = useMemo(() => history.map(({ path, pinned }) => ({ text: path, pinned })), [history]);
 
     const handleClose = useCallback(() => {
         dispatch(closeHistory());
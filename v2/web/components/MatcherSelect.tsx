@@ -18,6 +18,10 @@ const ALL_MATCHERS: [SearchMatcher, string][] = [
     ['CaseSensitive', 'case sensitive'],
     ['CaseInsensitive', 'case insensitive'],
     ['CaseSensitiveRegex', 'regular expression'],
+    ['CaseInsensitiveRegex', 'case insensitive regular expression'],
+    ['SmartCaseRegex', 'smart case regular expression'],
+    ['WholeWord', 'whole word'],
+    ['Fuzzy', 'fuzzy'],
 ];
 
 interface Props {
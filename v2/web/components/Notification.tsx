@@ -3,9 +3,11 @@ import Snackbar from '@mui/material/Snackbar';
 import IconButton from '@mui/material/IconButton';
 import ZoomInIcon from '@mui/icons-material/ZoomIn';
 import ZoomOutIcon from '@mui/icons-material/ZoomOut';
+import DeleteIcon from '@mui/icons-material/Delete';
 import Paper from '@mui/material/Paper';
+import Typography from '@mui/material/Typography';
 import { dismissNotification, type Dispatch, type NotificationContent } from '../reducer';
-import { sendMessage } from '../ipc';
+import { sendMessage, type Bookmark } from '../ipc';
 import * as log from '../log';
 
 const ORIGIN = { vertical: 'bottom', horizontal: 'right' } as const;
@@ -27,6 +29,16 @@ const BODY_STYLE: React.CSSProperties = {
 const MESSAGE_STYLE: React.CSSProperties = {
     marginRight: '8px',
 };
+const ERROR_STYLE: React.CSSProperties = {
+    ...MESSAGE_STYLE,
+    display: 'block',
+    maxWidth: '480px',
+    overflowWrap: 'anywhere',
+};
+const COMMAND_ERROR_STYLE: React.CSSProperties = {
+    ...ERROR_STYLE,
+    whiteSpace: 'pre-wrap', // Show stderr of the command as-is
+};
 
 function zoomIn(): void {
     sendMessage({ kind: 'zoom', zoom: 'In' });
@@ -36,6 +48,10 @@ function zoomOut(): void {
     sendMessage({ kind: 'zoom', zoom: 'Out' });
 }
 
+function removeBookmark({ path, heading }: Bookmark): void {
+    sendMessage({ kind: 'remove_bookmark', path, heading });
+}
+
 export interface Props {
     open: boolean;
     content: NotificationContent;
@@ -76,13 +92,67 @@ export const Notification: React.FC<Props> = ({ open, content, dispatch }) => {
                 </Paper>
             );
             break;
+        case 'bookmarked':
+            body = (
+                <Paper style={BODY_STYLE} variant="outlined">
+                    <span style={MESSAGE_STYLE}>{content.added ? 'Bookmark added' : 'Bookmark removed'}</span>
+                </Paper>
+            );
+            break;
+        case 'bookmarkNotFound': {
+            const { bookmark } = content;
+            const handleRemove = (): void => {
+                removeBookmark(bookmark);
+                dispatch(dismissNotification());
+            };
+            body = (
+                <Paper style={BODY_STYLE} variant="outlined">
+                    <Typography variant="body2" color="error" style={ERROR_STYLE} component="span">
+                        Bookmarked file was not found: {bookmark.path}
+                    </Typography>
+                    <IconButton onClick={handleRemove} size="small" aria-label="remove bookmark" color="info">
+                        <DeleteIcon fontSize="small" />
+                    </IconButton>
+                </Paper>
+            );
+            break;
+        }
+        case 'configError':
+            body = (
+                <Paper style={BODY_STYLE} variant="outlined">
+                    <Typography variant="body2" color="error" style={ERROR_STYLE}>
+                        Config error: {content.message}
+                    </Typography>
+                </Paper>
+            );
+            break;
+        case 'commandError':
+            body = (
+                <Paper style={BODY_STYLE} variant="outlined">
+                    <Typography variant="body2" color="error" style={COMMAND_ERROR_STYLE}>
+                        {content.message}
+                    </Typography>
+                </Paper>
+            );
+            break;
         default: // Should be unreachable
             log.debug('Unknown notification kind:', content);
             return <></>;
     }
 
+    // Errors are shown longer so that users can read the message
+    const duration =
+        content.kind === 'configError' || content.kind === 'commandError' || content.kind === 'bookmarkNotFound'
+            ? 10000
+            : 4000;
     return (
-        <Snackbar style={ROOT_STYLE} open={open} autoHideDuration={4000} onClose={handleClose} anchorOrigin={ORIGIN}>
+        <Snackbar
+            style={ROOT_STYLE}
+            open={open}
+            autoHideDuration={duration}
+            onClose={handleClose}
+            anchorOrigin={ORIGIN}
+        >
             {body}
         </Snackbar>
     );
@@ -32,6 +32,11 @@ export interface Props<T extends Item> {
     onClose: () => void;
     onSelect: (item: T) => void;
     renderItem: (item: T) => React.ReactNode;
+    // When this callback is set, items are not filtered by the palette since they are filtered by the caller
+    onQuery?: (query: string) => void;
+    // Called when the focused item is changed
+    onFocus?: (item: T) => void;
+    initialIndex?: number;
 }
 
 export function Palette<T extends Item>({
@@ -40,11 +45,16 @@ export function Palette<T extends Item>({
     onClose,
     onSelect,
     renderItem,
+    onQuery,
+    onFocus,
+    initialIndex,
 }: Props<T>): React.ReactElement {
     const [query, setQuery] = useState('');
-    const [unadjustedIndex, setIndex] = useState(0);
+    const [unadjustedIndex, setIndex] = useState(initialIndex ?? 0);
     const focusedItemRef = useRef<HTMLDivElement | null>(null);
-    items = query === '' ? items : items.filter(h => h.text.toLowerCase().includes(query));
+    if (onQuery === undefined && query !== '') {
+        items = items.filter(h => h.text.toLowerCase().includes(query));
+    }
     const index = unadjustedIndex < items.length ? unadjustedIndex : items.length > 0 ? items.length - 1 : 0;
 
     useEffect(() => {
@@ -59,8 +69,21 @@ export function Palette<T extends Item>({
         }
     }, [index, items]);
 
+    const focusedItem = index < items.length ? items[index] : null;
+    useEffect(() => {
+        if (onFocus !== undefined && focusedItem !== null) {
+            onFocus(focusedItem);
+        }
+    }, [onFocus, focusedItem]);
+
     const handleInput = (e: React.FormEvent<HTMLInputElement>): void => {
-        setQuery(e.currentTarget.value.toLowerCase());
+        const input = e.currentTarget.value;
+        if (onQuery === undefined) {
+            setQuery(input.toLowerCase());
+        } else {
+            setQuery(input);
+            onQuery(input);
+        }
         e.preventDefault();
     };
 
@@ -1,8 +1,49 @@
 import * as React from 'react';
 import { useEffect, useRef } from 'react';
-import type { MarkdownReactTree } from '../markdown';
+import type { MarkdownReactTree, ScrollPosition } from '../markdown';
+import { PREVIEW_HEADINGS } from '../keymaps';
+import { relayoutSearchHighlight } from '../search';
 import * as log from '../log';
 
+// Period to follow the layout changes after restoring the scroll position
+const RESTORE_PERIOD = 3000; // ms
+// Events which mean the user started scrolling by themselves
+const USER_SCROLL_EVENTS = ['wheel', 'keydown', 'mousedown', 'touchstart'];
+
+function findHeading(text: string): HTMLElement | null {
+    const elems: NodeListOf<HTMLElement> = document.querySelectorAll(PREVIEW_HEADINGS);
+    return Array.from(elems).find(e => e.textContent === text) ?? null;
+}
+
+// Returns the function to scroll to the position. The heading of the position is looked up first since the ratio to
+// the height of the document drifts when the heights of the contents above it change
+function scrollToRestore(heading: string | null, scroll: ScrollPosition | null): (() => void) | null {
+    if (heading !== null) {
+        const elem = findHeading(heading);
+        if (elem !== null) {
+            log.debug('Scrolling to heading:', heading);
+            return () => {
+                elem.scrollIntoView({ block: 'start', inline: 'start' });
+            };
+        }
+        log.debug('Heading to scroll was not found:', heading);
+    }
+    if (scroll === null) {
+        return null;
+    }
+    const anchor = scroll.heading === null ? null : findHeading(scroll.heading);
+    if (anchor !== null) {
+        log.debug('Restoring scroll position relative to heading:', scroll);
+        return () => {
+            window.scrollTo(0, anchor.offsetTop + scroll.offset);
+        };
+    }
+    log.debug('Restoring scroll position:', scroll);
+    return () => {
+        window.scrollTo(0, scroll.scroll * document.documentElement.scrollHeight);
+    };
+}
+
 function appearInViewport(elem: Element): boolean {
     const { top, left, bottom, right } = elem.getBoundingClientRect();
     const height = window.innerHeight;
@@ -16,9 +57,37 @@ export interface Props {
 }
 
 export const Preview: React.FC<Props> = ({ tree }) => {
-    const { root, lastModified } = tree;
+    const { root, lastModified, scroll, heading } = tree;
     const ref = useRef<HTMLElement>(null);
 
+    useEffect(() => {
+        const restore = scrollToRestore(heading, scroll);
+        const article = ref.current;
+        if (restore === null || article === null) {
+            return;
+        }
+        restore();
+
+        // Images and Mermaid diagrams are laid out after rendering and move the restored position. Scroll to the
+        // position again on resizing the document until the user starts scrolling
+        const observer = new ResizeObserver(() => {
+            restore();
+        });
+        observer.observe(article);
+        const stop = (): void => {
+            observer.disconnect();
+            window.clearTimeout(timer);
+            for (const event of USER_SCROLL_EVENTS) {
+                window.removeEventListener(event, stop);
+            }
+        };
+        const timer = window.setTimeout(stop, RESTORE_PERIOD);
+        for (const event of USER_SCROLL_EVENTS) {
+            window.addEventListener(event, stop, { passive: true });
+        }
+        return stop;
+    }, [root, scroll, heading]);
+
     useEffect(() => {
         const elem = lastModified?.current;
         if (!elem || appearInViewport(elem)) {
@@ -32,6 +101,11 @@ export const Preview: React.FC<Props> = ({ tree }) => {
         });
     }, [lastModified]);
 
+    useEffect(() => {
+        // The current match is set by the main when a search match in another file was opened
+        relayoutSearchHighlight();
+    }, [root]);
+
     useEffect(() => {
         const article = ref.current;
         if (article === null) {
@@ -0,0 +1,73 @@
+import * as React from 'react';
+import { useState } from 'react';
+import IconButton from '@mui/material/IconButton';
+import MenuItem from '@mui/material/MenuItem';
+import Menu from '@mui/material/Menu';
+import FilterListIcon from '@mui/icons-material/FilterList';
+import type { SearchScope } from '../ipc';
+import { type Dispatch, setSearchScope } from '../reducer';
+import * as log from '../log';
+
+const MENU_ITEM_STYLE: React.CSSProperties = {
+    fontFamily: 'inherit',
+    fontSize: '0.8rem',
+};
+
+const ALL_SCOPES: [SearchScope, string][] = [
+    ['All', 'all text'],
+    ['Prose', 'prose only'],
+    ['Code', 'code only'],
+    ['Everything', 'including URLs, alt text, math and HTML'],
+];
+
+interface Props {
+    scope: SearchScope;
+    dispatch: Dispatch;
+    onSelect?: (selected: SearchScope) => void;
+}
+
+export const ScopeSelect: React.FC<Props> = ({ scope, dispatch, onSelect }) => {
+    const [anchor, setAnchor] = useState<HTMLElement | null>(null);
+
+    const handleOpen = (e: React.MouseEvent<HTMLElement>): void => {
+        setAnchor(e.currentTarget);
+    };
+    const handleClose = (): void => {
+        setAnchor(null);
+    };
+    const handleSelect = (selected: SearchScope): void => {
+        log.debug('Search scope selected', selected);
+        if (selected !== scope) {
+            dispatch(setSearchScope(selected));
+        }
+        setAnchor(null);
+        onSelect?.(selected);
+    };
+
+    return (
+        <>
+            <IconButton
+                size="small"
+                title="Select search scope"
+                aria-label="select search scope"
+                onClick={handleOpen}
+            >
+                <FilterListIcon />
+            </IconButton>
+            <Menu anchorEl={anchor} open={anchor !== null} onClose={handleClose}>
+                {ALL_SCOPES.map(([s, desc]) => (
+                    <MenuItem
+                        key={s}
+                        style={MENU_ITEM_STYLE}
+                        selected={scope === s}
+                        onClick={() => {
+                            handleSelect(s);
+                        }}
+                    >
+                        {desc}
+                    </MenuItem>
+                ))}
+            </Menu>
+        </>
+    );
+};
@@ -7,10 +7,20 @@ import Divider from '@mui/material/Divider';
 import KeyboardArrowUpIcon from '@mui/icons-material/KeyboardArrowUp';
 import KeyboardArrowDownIcon from '@mui/icons-material/KeyboardArrowDown';
 import CloseIcon from '@mui/icons-material/Close';
+import ListIcon from '@mui/icons-material/List';
 import Typography from '@mui/material/Typography';
 import { MatcherSelect } from './MatcherSelect';
-import { type Dispatch, searchNext, searchPrevious, closeSearch } from '../reducer';
-import { type SearchMatcher, sendMessage } from '../ipc';
+import { ScopeSelect } from './ScopeSelect';
+import {
+    type Dispatch,
+    searchNext,
+    searchPrevious,
+    closeSearch,
+    openResultsPanel,
+    setSearchMatcher,
+    addSearchHistory,
+} from '../reducer';
+import { type SearchMatcher, type SearchScope, type SearchQuery, sendMessage } from '../ipc';
 
 const DEBOUNCE_TIMEOUT = 100; // 100ms
 const PAPER_STYLE: React.CSSProperties = {
@@ -40,15 +50,20 @@ const DIVIDER_STYLE: React.CSSProperties = {
 };
 
 interface Props {
+    query: string;
     index: number | null;
     total: number;
     matcher: SearchMatcher;
+    scope: SearchScope;
+    resultsPanel: boolean;
+    history: SearchQuery[];
     dispatch: Dispatch;
 }
 
-export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) => {
+export const Search: React.FC<Props> = ({ query, index, matcher, scope, resultsPanel, history, dispatch, total }) => {
     const counterElem = useRef<HTMLDivElement | null>(null);
     const inputElem = useRef<HTMLInputElement | null>(null);
+    const historyIndex = useRef<number | null>(null); // `null` means the query is not from history
     const [debId, setDebId] = useState<number | null>(null);
 
     useEffect(() => {
@@ -64,8 +79,37 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
     const handleNext = (): void => {
         dispatch(searchNext(index));
     };
+    const handleResults = (): void => {
+        dispatch(openResultsPanel(!resultsPanel));
+    };
+    const addHistory = (): void => {
+        const query = inputElem.current?.value ?? '';
+        if (query === '') {
+            return;
+        }
+        const item = { query, matcher };
+        sendMessage({ kind: 'add_search_history', ...item });
+        dispatch(addSearchHistory(item));
+        historyIndex.current = null;
+    };
+    // Move the position in the search history by `delta`. Moving forward from the latest query clears the input.
+    const cycleHistory = (delta: number): void => {
+        if (history.length === 0 || inputElem.current === null) {
+            return;
+        }
+        const current = historyIndex.current ?? history.length;
+        const next = Math.min(Math.max(current + delta, 0), history.length);
+        historyIndex.current = next;
+        const item = next < history.length ? history[next] : { query: '', matcher };
+        inputElem.current.value = item.query;
+        if (item.matcher !== matcher) {
+            dispatch(setSearchMatcher(item.matcher));
+        }
+        sendMessage({ kind: 'search', query: item.query, index, matcher: item.matcher, scope });
+    };
     const handleClose = (): void => {
-        sendMessage({ kind: 'search', query: '', index: null, matcher });
+        addHistory();
+        sendMessage({ kind: 'search', query: '', index: null, matcher, scope });
         dispatch(closeSearch());
     };
     const handleChange = (e: React.FormEvent<HTMLInputElement>): void => {
@@ -73,17 +117,26 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
             window.clearTimeout(debId);
         }
         const query = e.currentTarget.value;
+        historyIndex.current = null;
         const id = window.setTimeout(() => {
-            sendMessage({ kind: 'search', query, index, matcher });
+            sendMessage({ kind: 'search', query, index, matcher, scope });
             setDebId(null);
         }, DEBOUNCE_TIMEOUT);
         setDebId(id);
     };
     const handleKeydown = (e: React.KeyboardEvent<HTMLInputElement>): void => {
         if (e.key === 'Enter' && !e.shiftKey) {
+            addHistory();
             handleNext();
         } else if (e.key === 'Enter' && e.shiftKey) {
+            addHistory();
             handlePrev();
+        } else if (e.key === 'ArrowUp') {
+            e.preventDefault(); // Do not move the caret to the start of the input
+            cycleHistory(-1);
+        } else if (e.key === 'ArrowDown') {
+            e.preventDefault();
+            cycleHistory(1);
         } else if (e.key === 'Escape') {
             // Note: This `blur()` call is a workaround for Safari.
             // Safari has a bug to scroll the page to the input element automatically.
@@ -104,6 +157,7 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
     return (
         <Paper elevation={4} style={PAPER_STYLE}>
             <MatcherSelect matcher={matcher} dispatch={dispatch} onSelect={focusInputElem} />
+            <ScopeSelect scope={scope} dispatch={dispatch} onSelect={focusInputElem} />
             <InputBase
                 style={INPUT_STYLE}
                 inputProps={{
@@ -115,6 +169,7 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
                 }}
                 type="search"
                 placeholder="Search…"
+                defaultValue={query}
                 autoFocus
             />
             <Typography color="text.secondary" style={COUNTER_STYLE} ref={counterElem}></Typography>
@@ -125,6 +180,15 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
             <IconButton size="small" title="Find forward" aria-label="find forward" onClick={handleNext}>
                 <KeyboardArrowDownIcon fontSize="small" />
             </IconButton>
+            <IconButton
+                size="small"
+                title="Toggle search results"
+                aria-label="toggle search results"
+                color={resultsPanel ? 'primary' : 'default'}
+                onClick={handleResults}
+            >
+                <ListIcon fontSize="small" />
+            </IconButton>
             <IconButton size="small" title="Close search" aria-label="close search" onClick={handleClose}>
                 <CloseIcon fontSize="small" />
             </IconButton>
@@ -0,0 +1,80 @@
+import * as React from 'react';
+import { useEffect, useRef } from 'react';
+import Paper from '@mui/material/Paper';
+import List from '@mui/material/List';
+import ListItemButton from '@mui/material/ListItemButton';
+import Typography from '@mui/material/Typography';
+import { type Dispatch, searchJump } from '../reducer';
+import type { SearchResult } from '../ipc';
+
+const PAPER_STYLE: React.CSSProperties = {
+    position: 'fixed',
+    top: '56px', // Below the search input
+    right: 0,
+    width: '420px',
+    maxHeight: '50vh',
+    margin: '8px',
+    overflowY: 'auto',
+};
+const ITEM_STYLE: React.CSSProperties = {
+    display: 'flex',
+    flexDirection: 'column',
+    alignItems: 'stretch',
+    fontSize: '0.8rem',
+};
+const SNIPPET_STYLE: React.CSSProperties = {
+    whiteSpace: 'nowrap',
+    overflow: 'hidden',
+    textOverflow: 'ellipsis',
+};
+const MATCH_STYLE: React.CSSProperties = {
+    fontWeight: 'bold',
+};
+
+interface Props {
+    results: SearchResult[];
+    index: number | null;
+    dispatch: Dispatch;
+}
+
+export const SearchResults: React.FC<Props> = ({ results, index, dispatch }) => {
+    const currentItemRef = useRef<HTMLDivElement | null>(null);
+
+    useEffect(() => {
+        currentItemRef.current?.scrollIntoView({ block: 'nearest' });
+    }, [index]);
+
+    if (results.length === 0) {
+        return null;
+    }
+
+    return (
+        <Paper elevation={4} style={PAPER_STYLE}>
+            <List dense disablePadding>
+                {results.map(result => {
+                    const selected = result.index === index;
+                    return (
+                        <ListItemButton
+                            key={result.index}
+                            style={ITEM_STYLE}
+                            selected={selected}
+                            ref={selected ? currentItemRef : undefined}
+                            onClick={() => {
+                                dispatch(searchJump(index, result.index));
+                            }}
+                        >
+                            <Typography variant="caption" color="text.secondary" noWrap>
+                                {result.heading ?? '(No heading)'}
+                            </Typography>
+                            <div style={SNIPPET_STYLE}>
+                                {result.before}
+                                <span style={MATCH_STYLE}>{result.matched}</span>
+                                {result.after}
+                            </div>
+                        </ListItemButton>
+                    );
+                })}
+            </List>
+        </Paper>
+    );
+};
@@ -0,0 +1,103 @@
+import * as React from 'react';
+import { useState, useCallback } from 'react';
+import Drawer from '@mui/material/Drawer';
+import List from '@mui/material/List';
+import ListItemButton from '@mui/material/ListItemButton';
+import ListItemIcon from '@mui/material/ListItemIcon';
+import ListItemText from '@mui/material/ListItemText';
+import Collapse from '@mui/material/Collapse';
+import FolderIcon from '@mui/icons-material/Folder';
+import FolderOpenIcon from '@mui/icons-material/FolderOpen';
+import DescriptionIcon from '@mui/icons-material/Description';
+import { sendMessage, type FileTreeEntry } from '../ipc';
+import * as log from '../log';
+
+export const SIDEBAR_WIDTH = 260;
+
+const PAPER_PROPS = {
+    style: {
+        width: `${SIDEBAR_WIDTH}px`,
+    },
+};
+const ICON_STYLE: React.CSSProperties = {
+    minWidth: '28px',
+};
+const TEXT_PROPS = {
+    noWrap: true,
+    fontSize: '0.9em',
+};
+
+function openFile(path: string): void {
+    log.debug('Opening file via sidebar:', path);
+    sendMessage({ kind: 'open_file', path });
+}
+
+interface EntryProps {
+    entry: FileTreeEntry;
+    depth: number;
+    current: string | null;
+}
+
+const Entry: React.FC<EntryProps> = ({ entry, depth, current }) => {
+    // Expand directories containing the current file by default
+    const [open, setOpen] = useState(current !== null && current.startsWith(entry.path));
+    const handleToggle = useCallback(() => {
+        setOpen(!open);
+    }, [open]);
+    const indent = { paddingLeft: `${8 + depth * 12}px` };
+
+    if (entry.kind === 'file') {
+        return (
+            <ListItemButton
+                style={indent}
+                selected={entry.path === current}
+                title={entry.path}
+                onClick={() => {
+                    openFile(entry.path);
+                }}
+                dense
+            >
+                <ListItemIcon style={ICON_STYLE}>
+                    <DescriptionIcon fontSize="small" />
+                </ListItemIcon>
+                <ListItemText primary={entry.name} primaryTypographyProps={TEXT_PROPS} />
+            </ListItemButton>
+        );
+    }
+
+    return (
+        <>
+            <ListItemButton style={indent} title={entry.path} onClick={handleToggle} dense>
+                <ListItemIcon style={ICON_STYLE}>
+                    {open ? <FolderOpenIcon fontSize="small" /> : <FolderIcon fontSize="small" />}
+                </ListItemIcon>
+                <ListItemText primary={entry.name} primaryTypographyProps={TEXT_PROPS} />
+            </ListItemButton>
+            <Collapse in={open} timeout="auto" unmountOnExit>
+                <List disablePadding>
+                    {entry.children.map(child => (
+                        <Entry entry={child} depth={depth + 1} current={current} key={child.path} />
+                    ))}
+                </List>
+            </Collapse>
+        </>
+    );
+};
+
+export interface Props {
+    open: boolean;
+    roots: FileTreeEntry[];
+    current: string | null;
+}
+
+export const Sidebar: React.FC<Props> = ({ open, roots, current }) => {
+    return (
+        <Drawer variant="persistent" anchor="left" open={open} PaperProps={PAPER_PROPS}>
+            <List disablePadding>
+                {roots.map(root => (
+                    <Entry entry={root} depth={0} current={current} key={root.path} />
+                ))}
+            </List>
+        </Drawer>
+    );
+};
@@ -1,8 +1,14 @@
 import * as React from 'react';
-import { sendMessage } from '../ipc';
+import List from '@mui/material/List';
+import ListItemButton from '@mui/material/ListItemButton';
+import ListItemText from '@mui/material/ListItemText';
+import { sendMessage, type RecentFile } from '../ipc';
+
+const MAX_RECENT_FILES = 10;
 
 const BODY_STYLE: React.CSSProperties = {
     display: 'flex',
+    flexDirection: 'column',
     justifyContent: 'center',
     alignItems: 'center',
     width: '100vw',
@@ -16,16 +22,45 @@ const LOGO_STYLE: React.CSSProperties = {
     cursor: 'pointer',
     filter: 'grayscale(100%) opacity(0.4)',
 };
+const LIST_STYLE: React.CSSProperties = {
+    maxWidth: '60%',
+    marginTop: '16px',
+};
 
 function onClick(e: React.MouseEvent<HTMLElement>): void {
     e.preventDefault();
     sendMessage({ kind: 'file_dialog' });
 }
 
-export const Welcome: React.FC = () => {
+function fileName(path: string): string {
+    return path.split(/[\\/]/).pop() ?? path;
+}
+
+export interface Props {
+    files: RecentFile[];
+}
+
+export const Welcome: React.FC<Props> = ({ files }) => {
+    let recentFiles;
+    if (files.length > 0) {
+        const items = files.slice(0, MAX_RECENT_FILES).map(({ path }) => (
+            <ListItemButton
+                key={path}
+                dense
+                onClick={() => {
+                    sendMessage({ kind: 'open_file', path });
+                }}
+            >
+                <ListItemText primary={fileName(path)} secondary={path} />
+            </ListItemButton>
+        ));
+        recentFiles = <List style={LIST_STYLE}>{items}</List>;
+    }
+
     return (
         <div style={BODY_STYLE}>
             <img alt="Open file with dialog" src="/logo.png" style={LOGO_STYLE} onClick={onClick} />
+            {recentFiles}
         </div>
     );
 };
@@ -0,0 +1,96 @@
+import * as React from 'react';
+import { useMemo, useCallback, useRef } from 'react';
+import Typography from '@mui/material/Typography';
+import { Palette } from './Palette';
+import { type Dispatch, closeWorkspaceSearch, openSearch } from '../reducer';
+import { sendMessage, type SearchMatcher, type SearchScope, type WorkspaceMatch } from '../ipc';
+import * as log from '../log';
+
+const DEBOUNCE_TIMEOUT = 200; // 200ms
+const ITEM_STYLE: React.CSSProperties = {
+    display: 'flex',
+    flexDirection: 'column',
+    minWidth: 0,
+};
+const SNIPPET_STYLE: React.CSSProperties = {
+    whiteSpace: 'nowrap',
+    overflow: 'hidden',
+    textOverflow: 'ellipsis',
+};
+const MATCH_STYLE: React.CSSProperties = {
+    fontWeight: 'bold',
+};
+
+interface MatchItem extends WorkspaceMatch {
+    text: string;
+}
+
+function renderMatchItem(item: MatchItem): React.ReactNode {
+    const location = item.heading === null ? item.path : `${item.path} › ${item.heading}`;
+    return (
+        <div style={ITEM_STYLE}>
+            <Typography variant="caption" color="text.secondary" noWrap>
+                {location}
+            </Typography>
+            <div style={SNIPPET_STYLE}>
+                {item.before}
+                <span style={MATCH_STYLE}>{item.matched}</span>
+                {item.after}
+            </div>
+        </div>
+    );
+}
+
+export interface Props {
+    matches: WorkspaceMatch[];
+    matcher: SearchMatcher;
+    scope: SearchScope;
+    dispatch: Dispatch;
+}
+
+export const WorkspaceSearch: React.FC<Props> = ({ matches, matcher, scope, dispatch }) => {
+    const items = useMemo(() => matches.map(m => ({ ...m, text: m.path })), [matches]);
+    const queryRef = useRef('');
+    const debIdRef = useRef<number | null>(null);
+
+    const handleQuery = useCallback(
+        (query: string) => {
+            queryRef.current = query;
+            if (debIdRef.current !== null) {
+                window.clearTimeout(debIdRef.current);
+            }
+            debIdRef.current = window.setTimeout(() => {
+                sendMessage({ kind: 'search_workspace', query, matcher, scope });
+                debIdRef.current = null;
+            }, DEBOUNCE_TIMEOUT);
+        },
+        [matcher, scope],
+    );
+
+    const handleClose = useCallback(() => {
+        dispatch(closeWorkspaceSearch());
+    }, [dispatch]);
+
+    const handleSelect = useCallback(
+        (item: MatchItem) => {
+            const query = queryRef.current;
+            log.debug('Opening search match:', item.path, item.index, query);
+            const { path, index } = item;
+            sendMessage({ kind: 'open_search_match', path, query, index, matcher, scope });
+            // Continue the search in the opened file with the same query
+            dispatch(openSearch(query, item.index));
+        },
+        [matcher, scope, dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search in watched files…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderMatchItem}
+            onQuery={handleQuery}
+        />
+    );
+};
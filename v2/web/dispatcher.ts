@@ -7,22 +7,62 @@ import {
     searchNext,
     searchPrevious,
     setSearchMatcher,
-    openOutline,
     setTheme,
-    newFile,
-    openHistory,
-    openHelp,
+    openHighlightThemes,
     notifyZoom,
     notifyReload,
     notifyAlwaysOnTop,
+    notifyConfigError,
+    notifyCommandError,
+    notifyBookmarked,
+    notifyBookmarkNotFound,
+    setBookmarks,
     setRecentFiles,
+    setSearchHistory,
     welcome,
+    openSidebar,
+    closeSidebar,
+    setFileTree,
+    setCurrentFile,
+    setFoundFiles,
+    setWorkspaceMatches,
+    setSearchResults,
+    setSearchMatchCount,
 } from './reducer';
-import type { MessageFromMain } from './ipc';
-import { ReactMarkdownRenderer } from './markdown';
-import { KeyMapping } from './keymaps';
+import { sendMessage, type Command, type MessageFromMain, type RenderTreeElem, type SearchQuery } from './ipc';
+import { ReactMarkdownRenderer, type ScrollPosition } from './markdown';
+import { setSearchHighlight } from './search';
+import { KeyMapping, runCommand, headingAtTop, PREVIEW_HEADINGS } from './keymaps';
 import * as log from './log';
 
+const SCROLL_REPORT_DELAY = 500; // ms
+
+function stripQuery(url: string): string {
+    const idx = url.search(/[?#]/);
+    return idx >= 0 ? url.slice(0, idx) : url;
+}
+
+// Force the WebView to fetch the modified local files again by appending a cache-busting query to their URLs.
+// The query is ignored by the custom protocol handler of the main.
+function reloadResources(urls: string[]): void {
+    const targets = new Set(urls);
+    const query = `?t=${Date.now()}`;
+    for (const elem of document.querySelectorAll('[src]')) {
+        const src = elem.getAttribute('src');
+        if (src !== null && targets.has(stripQuery(src))) {
+            log.debug('Reloading resource:', src);
+            elem.setAttribute('src', stripQuery(src) + query);
+        }
+    }
+    for (const elem of document.querySelectorAll('link[rel="stylesheet"][href]')) {
+        const href = elem.getAttribute('href');
+        if (href !== null && targets.has(stripQuery(href))) {
+            log.debug('Reloading stylesheet:', href);
+            elem.setAttribute('href', stripQuery(href) + query);
+        }
+    }
+}
+
 // Global action dispatcher to handle IPC messages from the main and key shortcuts
 
 export class GlobalDispatcher {
@@ -30,6 +70,11 @@ export class GlobalDispatcher {
     public state: State; // This prop will be updated by `App` component
     public readonly keymap: KeyMapping;
     public readonly markdown: ReactMarkdownRenderer;
+    private tree: RenderTreeElem[];
+    private renderCount: number;
+    private restoreScroll: ScrollPosition | null;
+    private scrollHeading: string | null;
+    private scrollReportTimer: number | null;
 
     constructor() {
         this.dispatch = () => {
@@ -38,6 +83,25 @@ export class GlobalDispatcher {
         this.state = INITIAL_STATE;
         this.keymap = new KeyMapping();
         this.markdown = new ReactMarkdownRenderer();
+        this.tree = [];
+        this.renderCount = 0;
+        this.restoreScroll = null;
+        this.scrollHeading = null;
+        this.scrollReportTimer = null;
+    }
+
+    private async renderPreview(): Promise<void> {
+        // Rendering is asynchronous. Discard the result when newer tree was received while rendering
+        const count = ++this.renderCount;
+        const tree = await this.markdown.render(this.tree);
+        if (count === this.renderCount) {
+            // The scroll position sent by the main just before the new tree is applied on rendering the tree
+            tree.scroll = this.restoreScroll;
+            tree.heading = this.scrollHeading;
+            this.restoreScroll = null;
+            this.scrollHeading = null;
+            this.dispatch(previewContent(tree));
+        }
     }
 
     setDispatch(dispatch: Dispatch, state: State): void {
@@ -49,6 +113,12 @@ export class GlobalDispatcher {
         this.dispatch(openSearch());
     }
 
+    runSavedSearch({ query, matcher }: SearchQuery): void {
+        this.dispatch(setSearchMatcher(matcher));
+        this.dispatch(openSearch(query));
+        sendMessage({ kind: 'search', query, index: null, matcher, scope: this.state.scope });
+    }
+
     searchNext(): void {
         const { searching, searchIndex } = this.state;
         if (searching) {
@@ -56,6 +126,49 @@ export class GlobalDispatcher {
         }
     }
 
+    toggleSidebar(): void {
+        this.dispatch(this.state.sidebar ? closeSidebar() : openSidebar());
+    }
+
+    runCommand(command: Command): void {
+        log.debug('Running command:', command);
+        runCommand(command, this);
+    }
+
+    // The main saves the scroll position to restore it when the file is opened again. The position is reported after
+    // scrolling stops to avoid sending too many messages
+    scheduleScrollReport(): void {
+        if (this.scrollReportTimer !== null) {
+            window.clearTimeout(this.scrollReportTimer);
+        }
+        this.scrollReportTimer = window.setTimeout(() => {
+            this.scrollReportTimer = null;
+            this.reportScrollPosition();
+        }, SCROLL_REPORT_DELAY);
+    }
+
+    // Report the pending scroll position immediately before the document is replaced or the application quits
+    flushScrollReport(): void {
+        if (this.scrollReportTimer === null) {
+            return;
+        }
+        window.clearTimeout(this.scrollReportTimer);
+        this.scrollReportTimer = null;
+        this.reportScrollPosition();
+    }
+
+    private reportScrollPosition(): void {
+        const path = this.state.currentFile;
+        const height = document.documentElement.scrollHeight;
+        if (path === null || height === 0) {
+            return;
+        }
+        const elem = headingAtTop(PREVIEW_HEADINGS);
+        const heading = elem?.textContent ?? null;
+        const offset = elem ? window.scrollY - elem.offsetTop : 0;
+        sendMessage({ kind: 'scroll_position', path, scroll: window.scrollY / height, heading, offset });
+    }
+
     searchPrev(): void {
         const { searching, searchIndex } = this.state;
         if (searching) {
@@ -68,19 +181,46 @@ export class GlobalDispatcher {
         // This method must not throw exception since the main process call this method like `window.postShibaMessageFromMain(msg)`.
         try {
             switch (msg.kind) {
-                case 'render_tree': {
-                    const tree = await this.markdown.render(msg.tree);
-                    this.dispatch(previewContent(tree));
+                case 'command':
+                    this.runCommand(msg.command);
                     break;
-                }
-                case 'new_file':
-                    this.dispatch(newFile(msg.path));
+                case 'render_tree':
+                    // The pending scroll position belongs to the document being replaced
+                    this.flushScrollReport();
+                    // Span IDs of the previous search matches are no longer valid with the new tree
+                    this.tree = msg.tree;
+                    setSearchHighlight(null);
+                    await this.renderPreview();
+                    break;
+                case 'search_matches':
+                    // Matches are highlighted on the rendered document without rendering the tree again
+                    setSearchHighlight({ index: msg.index, count: msg.count, matches: msg.matches });
+                    this.dispatch(setSearchMatchCount(msg.count));
+                    break;
+                case 'recent_files':
+                    this.dispatch(setRecentFiles(msg.files));
+                    break;
+                case 'restore_scroll':
+                    this.restoreScroll = { scroll: msg.scroll, heading: msg.heading, offset: msg.offset };
+                    break;
+                case 'scroll_to_heading':
+                    this.scrollHeading = msg.heading;
+                    break;
+                case 'bookmarks':
+                    this.dispatch(setBookmarks(msg.bookmarks));
+                    break;
+                case 'bookmark_not_found':
+                    this.dispatch(notifyBookmarkNotFound(msg.bookmark));
+                    break;
+                case 'bookmarked':
+                    this.dispatch(notifyBookmarked(msg.added));
                     break;
                 case 'config':
-                    this.keymap.register(msg.keymaps, this);
+                    this.keymap.register(msg.keymaps, msg.commands, msg.external_commands, msg.search.saved, this);
                     this.dispatch(setTheme(msg.theme));
                     this.dispatch(setSearchMatcher(msg.search.matcher));
                     this.dispatch(setRecentFiles(msg.recent));
+                    this.dispatch(setSearchHistory(msg.search_history));
                     // `this.state.theme` is not available since it is updated *after* the first rendering of Markdown content.
                     //   1. Receive `config` IPC message
                     //   2. Dispatch `setTheme` action
@@ -89,27 +229,9 @@ export class GlobalDispatcher {
                     //   5. Dispatched `setTheme` action is handled and `this.state` is updated
                     this.markdown.theme = msg.theme;
                     break;
-                case 'search':
-                    this.openSearch();
-                    break;
-                case 'search_next':
-                    this.searchNext();
-                    break;
-                case 'search_previous':
-                    this.searchPrev();
-                    break;
-                case 'outline':
-                    this.dispatch(openOutline());
-                    break;
-                case 'history':
-                    this.dispatch(openHistory());
-                    break;
                 case 'welcome':
                     this.dispatch(welcome());
                     break;
-                case 'help':
-                    this.dispatch(openHelp());
-                    break;
                 case 'zoom':
                     this.dispatch(notifyZoom(msg.percent));
                     break;
@@ -119,6 +241,44 @@ export class GlobalDispatcher {
                 case 'always_on_top':
                     this.dispatch(notifyAlwaysOnTop(msg.pinned));
                     break;
+                case 'file_tree':
+                    this.dispatch(setFileTree(msg.roots));
+                    break;
+                case 'current_file':
+                    this.dispatch(setCurrentFile(msg.path));
+                    break;
+                case 'found_files':
+                    this.dispatch(setFoundFiles(msg.files));
+                    break;
+                case 'search_results':
+                    this.dispatch(setSearchResults(msg.results));
+                    break;
+                case 'workspace_matches':
+                    this.dispatch(setWorkspaceMatches(msg.matches));
+                    break;
+                case 'reload_resources':
+                    reloadResources(msg.urls);
+                    break;
+                case 'external_command_error':
+                    this.dispatch(notifyCommandError(msg.message));
+                    break;
+                case 'config_error':
+                    this.dispatch(notifyConfigError(msg.message));
+                    break;
+                case 'theme':
+                    // Only the highlight.js stylesheet is fetched again. Mermaid diagrams will follow the new theme
+                    // on the next rendering.
+                    this.dispatch(setTheme(msg.theme));
+                    this.markdown.theme = msg.theme;
+                    reloadResources(['/hljs-theme.css']);
+                    break;
+                case 'highlight_themes':
+                    this.dispatch(openHighlightThemes({ themes: msg.themes, current: msg.current }));
+                    break;
+                case 'quit':
+                    this.flushScrollReport();
+                    sendMessage({ kind: 'quit' });
+                    break;
                 case 'debug':
                     log.enableDebug();
                     log.debug('Debug log is enabled');
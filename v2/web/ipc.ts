@@ -8,35 +8,140 @@ declare global {
     }
 }
 
-export type KeyAction =
+// The order is the same as `Command` enum in command.rs
+export type Command =
+    | 'OpenFile'
+    | 'FileFinder'
+    | 'OpenDir'
+    | 'ClearRecent'
+    | 'Print'
+    | 'Search'
+    | 'NextSearch'
+    | 'PrevSearch'
+    | 'WorkspaceSearch'
+    | 'Outline'
+    | 'CommandPalette'
     | 'ScrollDown'
     | 'ScrollUp'
     | 'ScrollLeft'
     | 'ScrollRight'
     | 'ScrollPageDown'
     | 'ScrollPageUp'
-    | 'Forward'
-    | 'Back'
-    | 'Reload'
-    | 'OpenFile'
-    | 'OpenDir'
     | 'ScrollTop'
     | 'ScrollBottom'
-    | 'Search'
-    | 'SearchNext'
-    | 'SearchPrev'
     | 'ScrollNextSection'
     | 'ScrollPrevSection'
-    | 'Outline'
-    | 'History'
-    | 'Help'
+    | 'Reload'
     | 'ZoomIn'
     | 'ZoomOut'
+    | 'Sidebar'
+    | 'ToggleTheme'
+    | 'HighlightThemes'
+    | 'Forward'
+    | 'Back'
+    | 'History'
+    | 'PinRecent'
+    | 'BookmarkFile'
+    | 'BookmarkHeading'
+    | 'Bookmarks'
+    | 'ToggleAlwaysOnTop'
+    | 'Help'
+    | 'OpenRepo'
     | 'Quit';
 
-export type KeyMaps = Record<string, KeyAction>;
-export type SearchMatcher = 'SmartCase' | 'CaseSensitive' | 'CaseInsensitive' | 'CaseSensitiveRegex';
+export interface CommandSpec {
+    command: Command;
+    label: string;
+    description: string;
+    handler: 'Main' | 'Renderer';
+    keys: string[];
+    accelerator: string | null;
+}
+
+export type KeyMaps = Record<string, Command>;
+
+export interface ExternalCommand {
+    name: string;
+    run: string;
+    key: string | null;
+    reload: boolean;
+}
+export type SearchMatcher =
+    | 'SmartCase'
+    | 'CaseSensitive'
+    | 'CaseInsensitive'
+    | 'CaseSensitiveRegex'
+    | 'CaseInsensitiveRegex'
+    | 'SmartCaseRegex'
+    | 'WholeWord'
+    | 'Fuzzy';
+export type SearchScope = 'All' | 'Prose' | 'Code' | 'Everything';
+
+export interface RecentFile {
+    path: string;
+    count: number;
+    last_opened: number; // Unix time in seconds
+    pinned: boolean;
+}
+
+export interface Bookmark {
+    path: string;
+    heading: string | null;
+    label: string | null;
+}
+
+export interface SearchQuery {
+    query: string;
+    matcher: SearchMatcher;
+}
+export interface SavedSearch extends SearchQuery {
+    name: string;
+    key: string | null;
+}
 export type WindowTheme = 'Dark' | 'Light';
+export interface HighlightThemes {
+    themes: string[];
+    current: string;
+}
+
+export type FileTreeEntry =
+    | {
+          kind: 'file';
+          name: string;
+          path: string;
+      }
+    | {
+          kind: 'dir';
+          name: string;
+          path: string;
+          children: FileTreeEntry[];
+      };
+
+export interface FoundFile {
+    path: string;
+    positions: number[]; // Indices of matched characters (not UTF-16 code units)
+}
+
+// Piece of a search match in a text span. Text spans are strings in the render tree except for children of `img` and
+// `emoji`, and `hidden` elements. They are numbered in the order of appearance in the tree
+export interface SpanMatch {
+    index: number; // Index of the match
+    span: number; // ID of the text span
+    start: number; // Offset in the span (UTF-16 code units)
+    end: number;
+}
+
+export interface SearchResult {
+    index: number; // Index of the match in the file
+    heading: string | null;
+    before: string;
+    matched: string;
+    after: string;
+}
+
+export interface WorkspaceMatch extends SearchResult {
+    path: string;
+}
 
 export type RenderTreeTableAlign = 'left' | 'center' | 'right' | null;
 export interface RenderTreeFootNoteDef {
@@ -78,6 +183,7 @@ export type RenderTreeElem =
     | {
           t: 'h';
           level: number;
+          offset: number; // Offset in the source
           id?: string;
           c: RenderTreeElem[];
       }
@@ -172,60 +278,60 @@ export type RenderTreeElem =
           t: 'modified'; // Special token to indicate the last modified position
       }
     | {
-          t: 'match'; // Text search match tokens after match-start
-          c: RenderTreeElem[];
-      }
-    | {
-          t: 'match-current'; // Current text search match tokens after match-current-start
-          c: RenderTreeElem[];
-      }
-    | {
-          t: 'match-start'; // First text search match token
-          c: RenderTreeElem[];
-      }
-    | {
-          t: 'match-current-start'; // First current text search match token
-          c: RenderTreeElem[];
+          t: 'hidden'; // Text span which is not rendered such as link URL. Matches in it are shown here
       };
 
 export type MessageFromMain =
+    | {
+          kind: 'command';
+          command: Command;
+      }
     | {
           kind: 'render_tree';
           tree: RenderTreeElem[];
       }
     | {
-          kind: 'new_file';
-          path: string;
+          kind: 'recent_files';
+          files: RecentFile[]; // Ranked by frecency. Pinned files come first
       }
     | {
           kind: 'config';
           keymaps: KeyMaps;
+          commands: CommandSpec[];
+          external_commands: ExternalCommand[];
           search: {
               matcher: SearchMatcher;
+              ignore_accents: boolean;
+              saved: SavedSearch[];
           };
           theme: WindowTheme;
-          recent: string[];
+          recent: RecentFile[];
+          search_history: SearchQuery[]; // The latest query is at the end
       }
     | {
-          kind: 'search';
-      }
-    | {
-          kind: 'search_next';
+          kind: 'welcome';
       }
     | {
-          kind: 'search_previous';
+          kind: 'restore_scroll';
+          scroll: number;
+          heading: string | null;
+          offset: number;
       }
     | {
-          kind: 'outline';
+          kind: 'scroll_to_heading';
+          heading: string;
       }
     | {
-          kind: 'welcome';
+          kind: 'bookmarks';
+          bookmarks: Bookmark[];
       }
     | {
-          kind: 'history';
+          kind: 'bookmark_not_found';
+          bookmark: Bookmark;
       }
     | {
-          kind: 'help';
+          kind: 'bookmarked';
+          added: boolean;
       }
     | {
           kind: 'zoom';
@@ -239,47 +345,151 @@ export type MessageFromMain =
           pinned: boolean;
       }
     | {
-          kind: 'debug';
-      };
-export type MessageToMain =
+          kind: 'reload_resources';
+          urls: string[];
+      }
     | {
-          kind: 'init';
+          kind: 'external_command_error';
+          message: string;
       }
     | {
-          kind: 'quit';
+          kind: 'config_error';
+          message: string;
       }
     | {
-          kind: 'forward';
+          kind: 'theme';
+          theme: WindowTheme;
       }
+    | ({
+          kind: 'highlight_themes';
+      } & HighlightThemes)
     | {
-          kind: 'back';
+          kind: 'file_tree';
+          roots: FileTreeEntry[];
       }
     | {
-          kind: 'reload';
+          kind: 'current_file';
+          path: string;
       }
     | {
-          kind: 'file_dialog';
+          kind: 'found_files';
+          query: string;
+          files: FoundFile[];
       }
     | {
-          kind: 'dir_dialog';
+          kind: 'search_matches';
+          query: string;
+          index: number | null;
+          count: number;
+          matches: SpanMatch[];
+      }
+    | {
+          kind: 'search_results';
+          query: string;
+          results: SearchResult[];
+      }
+    | {
+          kind: 'workspace_matches';
+          query: string;
+          matches: WorkspaceMatch[];
+      }
+    | {
+          kind: 'quit';
+      }
+    | {
+          kind: 'debug';
+      };
+export type MessageToMain =
+    | {
+          kind: 'init';
+      }
+    | {
+          kind: 'command';
+          command: Command;
+      }
+    | {
+          kind: 'file_dialog';
       }
     | {
           kind: 'search';
           query: string;
           index: number | null;
           matcher: SearchMatcher;
+          scope: SearchScope;
       }
     | {
           kind: 'open_file';
           path: string;
       }
+    | {
+          kind: 'find_files';
+          query: string;
+      }
+    | {
+          kind: 'search_workspace';
+          query: string;
+          matcher: SearchMatcher;
+          scope: SearchScope;
+      }
+    | {
+          kind: 'open_search_match';
+          path: string;
+          query: string;
+          index: number;
+          matcher: SearchMatcher;
+          scope: SearchScope;
+      }
+    | {
+          kind: 'add_search_history';
+          query: string;
+          matcher: SearchMatcher;
+      }
     | {
           kind: 'zoom';
           zoom: 'In' | 'Out';
       }
+    | {
+          kind: 'scroll_position';
+          path: string;
+          scroll: number;
+          heading: string | null;
+          offset: number;
+      }
+    | {
+          kind: 'toggle_pin_recent_file';
+          path: string;
+      }
+    | {
+          kind: 'toggle_bookmark';
+          heading: string | null;
+          label: string | null;
+      }
+    | {
+          kind: 'open_bookmark';
+          path: string;
+          heading: string | null;
+      }
+    | {
+          kind: 'remove_bookmark';
+          path: string;
+          heading: string | null;
+      }
+    | {
+          kind: 'preview_highlight_theme';
+          name: string | null;
+      }
+    | {
+          kind: 'run_external_command';
+          index: number;
+          selection: string;
+          offset: number | null;
+      }
     | {
           kind: 'error';
           message: string;
+      }
+    | {
+          kind: 'quit';
       };
 
 export function sendMessage(m: MessageToMain): void {
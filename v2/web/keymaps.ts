@@ -1,7 +1,22 @@
-import { bind as bindKey } from 'mousetrap';
-import { openOutline, openHistory, openHelp } from './reducer';
+import { bind as bindKey, reset as resetKeys } from 'mousetrap';
+import {
+    openOutline,
+    openHistory,
+    openHelp,
+    openFinder,
+    openWorkspaceSearch,
+    openCommandPalette,
+    openBookmarks,
+} from './reducer';
 import type { GlobalDispatcher } from './dispatcher';
-import { sendMessage, type KeyMaps, type KeyAction } from './ipc';
+import {
+    sendMessage,
+    type KeyMaps,
+    type Command,
+    type CommandSpec,
+    type ExternalCommand,
+    type SavedSearch,
+} from './ipc';
 import * as log from './log';
 
 function scrollTo(
@@ -27,233 +42,215 @@ function scrollTo(
     }
 }
 
-export interface KeyShortcut {
-    dispatch(dispatcher: GlobalDispatcher): void;
-    description: string;
-}
+type CommandRunner = (dispatcher: GlobalDispatcher) => void;
 
-const KeyShortcuts: { [K in KeyAction]: KeyShortcut } = {
-    ScrollDown: {
-        description: 'Scroll down the page by half of window height.',
-        dispatch(): void {
-            window.scrollBy(0, window.innerHeight / 2);
-        },
+// Commands handled by the renderer. Other commands are sent to the main. Labels and descriptions of commands are
+// defined in command.rs and sent from the main with `config` message.
+const RendererCommands: { [K in Command]?: CommandRunner } = {
+    ScrollDown(): void {
+        window.scrollBy(0, window.innerHeight / 2);
     },
-
-    ScrollUp: {
-        description: 'Scroll up the page by half of window height.',
-        dispatch(): void {
-            window.scrollBy(0, -window.innerHeight / 2);
-        },
+    ScrollUp(): void {
+        window.scrollBy(0, -window.innerHeight / 2);
     },
-
-    ScrollLeft: {
-        description: 'Scroll the page to the left by half of window width.',
-        dispatch(): void {
-            window.scrollBy(-window.innerWidth / 2, 0);
-        },
+    ScrollLeft(): void {
+        window.scrollBy(-window.innerWidth / 2, 0);
     },
-
-    ScrollRight: {
-        description: 'Scroll the page to the right by half of window width.',
-        dispatch(): void {
-            window.scrollBy(window.innerWidth / 2, 0);
-        },
+    ScrollRight(): void {
+        window.scrollBy(window.innerWidth / 2, 0);
     },
-
-    ScrollPageDown: {
-        description: 'Scroll down the page by window height.',
-        dispatch(): void {
-            window.scrollBy(0, window.innerHeight);
-        },
+    ScrollPageDown(): void {
+        window.scrollBy(0, window.innerHeight);
     },
-
-    ScrollPageUp: {
-        description: 'Scroll up the page by window height.',
-        dispatch(): void {
-            window.scrollBy(0, -window.innerHeight);
-        },
+    ScrollPageUp(): void {
+        window.scrollBy(0, -window.innerHeight);
     },
-
-    ScrollTop: {
-        description: 'Scroll to the top of the page.',
-        dispatch(): void {
-            window.scrollTo(0, 0);
-        },
+    ScrollTop(): void {
+        window.scrollTo(0, 0);
     },
-
-    ScrollBottom: {
-        description: 'Scroll to the bottom of the page.',
-        dispatch(): void {
-            window.scrollTo(0, document.body.scrollHeight);
-        },
+    ScrollBottom(): void {
+        window.scrollTo(0, document.body.scrollHeight);
     },
-
-    ScrollNextSection: {
-        description: 'Scroll to the next section header.',
-        dispatch(): void {
-            const headings: NodeListOf<HTMLElement> = document.querySelectorAll('h1,h2,h3,h4,h5,h6');
-            scrollTo(headings, (elem, windowTop) => elem.offsetTop > windowTop);
-        },
+    ScrollNextSection(): void {
+        const headings: NodeListOf<HTMLElement> = document.querySelectorAll('h1,h2,h3,h4,h5,h6');
+        scrollTo(headings, (elem, windowTop) => elem.offsetTop > windowTop);
     },
-
-    ScrollPrevSection: {
-        description: 'Scroll to the previous section header.',
-        dispatch(): void {
-            const headings: HTMLElement[] = Array.from(document.querySelectorAll('h1,h2,h3,h4,h5,h6'));
-            headings.reverse();
-            scrollTo(headings, (elem, windowTop) => elem.offsetTop < windowTop);
-        },
+    ScrollPrevSection(): void {
+        const headings: HTMLElement[] = Array.from(document.querySelectorAll('h1,h2,h3,h4,h5,h6'));
+        headings.reverse();
+        scrollTo(headings, (elem, windowTop) => elem.offsetTop < windowTop);
     },
-
-    Forward: {
-        description: 'Go forawrd to the next document in preview history.',
-        dispatch(): void {
-            sendMessage({ kind: 'forward' });
-        },
-    },
-
-    Back: {
-        description: 'Go backward to the previous document in preview history.',
-        dispatch(): void {
-            sendMessage({ kind: 'back' });
-        },
+    Search(dispatcher: GlobalDispatcher): void {
+        dispatcher.openSearch();
     },
-
-    Reload: {
-        description: 'Reload the current document preview.',
-        dispatch(): void {
-            sendMessage({ kind: 'reload' });
-        },
+    NextSearch(dispatcher: GlobalDispatcher): void {
+        dispatcher.searchNext();
     },
-
-    OpenFile: {
-        description: 'Open a dialog to choose a file to preview.',
-        dispatch(): void {
-            sendMessage({ kind: 'file_dialog' });
-        },
+    PrevSearch(dispatcher: GlobalDispatcher): void {
+        dispatcher.searchPrev();
     },
-
-    OpenDir: {
-        description: 'Open a dialog to choose a directory to watch file changes.',
-        dispatch(): void {
-            sendMessage({ kind: 'dir_dialog' });
-        },
+    Outline(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openOutline());
     },
-
-    Search: {
-        description: 'Open an in-page search box.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.openSearch();
-        },
+    History(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openHistory());
     },
-
-    SearchNext: {
-        description: 'Focus the next match of ongoing text search.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.searchNext();
-        },
+    BookmarkFile(): void {
+        toggleBookmark(false);
     },
-
-    SearchPrev: {
-        description: 'Focus the previous match of the ongoing text search.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.searchPrev();
-        },
+    BookmarkHeading(): void {
+        toggleBookmark(true);
     },
-
-    Outline: {
-        description: 'Open a palette to incrementally search the section outline.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.dispatch(openOutline());
-        },
+    Bookmarks(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openBookmarks());
     },
-
-    History: {
-        description: 'Open a palette to incrementally search files in history.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.dispatch(openHistory());
-        },
+    FileFinder(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openFinder());
     },
-
-    Help: {
-        description: 'Show this help.',
-        dispatch(dispatcher: GlobalDispatcher): void {
-            dispatcher.dispatch(openHelp());
-        },
+    WorkspaceSearch(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openWorkspaceSearch());
     },
-
-    ZoomIn: {
-        description: 'Zoom in on the page.',
-        dispatch(): void {
-            sendMessage({ kind: 'zoom', zoom: 'In' });
-        },
+    CommandPalette(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openCommandPalette());
     },
-
-    ZoomOut: {
-        description: 'Zoom out on the page.',
-        dispatch(): void {
-            sendMessage({ kind: 'zoom', zoom: 'Out' });
-        },
+    Sidebar(dispatcher: GlobalDispatcher): void {
+        dispatcher.toggleSidebar();
     },
-
-    Quit: {
-        description: 'Quit the application.',
-        dispatch(): void {
-            sendMessage({ kind: 'quit' });
-        },
+    Help(dispatcher: GlobalDispatcher): void {
+        dispatcher.dispatch(openHelp());
     },
 };
 
-export interface BoundShortcut extends KeyShortcut {
-    action: KeyAction;
+export function runCommand(command: Command, dispatcher: GlobalDispatcher): void {
+    const run = RendererCommands[command];
+    if (run === undefined) {
+        sendMessage({ kind: 'command', command });
+    } else {
+        run(dispatcher);
+    }
+}
+
+export const PREVIEW_HEADINGS = ['h1', 'h2', 'h3', 'h4', 'h5', 'h6'].map(h => `.markdown-body ${h}`).join(',');
+
+// Headings rendered from Markdown. Headings in raw HTML are not included since they have no source offset
+const MARKDOWN_HEADINGS = ['h1', 'h2', 'h3', 'h4', 'h5', 'h6'].map(h => `.markdown-body ${h}[data-offset]`).join(',');
+
+// The last heading above the top of the window
+export function headingAtTop(selector: string): HTMLElement | null {
+    const headings: NodeListOf<HTMLElement> = document.querySelectorAll(selector);
+    let heading = null;
+    for (const elem of headings) {
+        if (elem.offsetTop > window.scrollY + 1) {
+            break;
+        }
+        heading = elem;
+    }
+    return heading;
+}
+
+// The selected text is used as the label of the bookmark
+function toggleBookmark(section: boolean): void {
+    let heading = null;
+    if (section) {
+        const elem = headingAtTop(PREVIEW_HEADINGS);
+        if (elem === null) {
+            log.debug('No section to bookmark at the top of the window');
+            return;
+        }
+        heading = elem.textContent ?? '';
+    }
+    const label = window.getSelection()?.toString().trim() || null;
+    sendMessage({ kind: 'toggle_bookmark', heading, label });
+}
+
+export function runExternalCommand(index: number): void {
+    const selection = window.getSelection()?.toString() ?? '';
+    // The main converts the source offset of the heading into the line number
+    const offset = headingAtTop(MARKDOWN_HEADINGS)?.dataset.offset;
+    sendMessage({ kind: 'run_external_command', index, selection, offset: offset !== undefined ? Number(offset) : null });
+}
+
+export interface KeyShortcut {
+    command: Command;
+    label: string;
+    description: string;
     binds: string[];
 }
 
 export class KeyMapping {
-    private sortedShortcuts: BoundShortcut[] = [];
+    private allShortcuts: KeyShortcut[] = [];
+    private allExternalCommands: ExternalCommand[] = [];
+
+    // All commands in the order of the command registry. `binds` is empty when no key is mapped to the command
+    get shortcuts(): KeyShortcut[] {
+        return this.allShortcuts;
+    }
 
-    get shortcuts(): BoundShortcut[] {
-        return this.sortedShortcuts;
+    get externalCommands(): ExternalCommand[] {
+        return this.allExternalCommands;
     }
 
-    register(maps: KeyMaps, dispatcher: GlobalDispatcher): void {
-        const bounds = new Map<KeyAction, BoundShortcut>();
+    register(
+        maps: KeyMaps,
+        commands: CommandSpec[],
+        externalCommands: ExternalCommand[],
+        saved: SavedSearch[],
+        dispatcher: GlobalDispatcher,
+    ): void {
+        resetKeys(); // Key mappings are registered again when the config file is reloaded
+        const shortcuts = new Map<Command, KeyShortcut>();
+        for (const { command, label, description } of commands) {
+            shortcuts.set(command, { command, label, description, binds: [] });
+        }
 
         for (const keybind of Object.keys(maps)) {
-            const action: KeyAction = maps[keybind];
-            if (!(action in KeyShortcuts)) {
-                log.error('Unknown key action in keymaps:', keybind, action);
+            const command: Command = maps[keybind];
+            const shortcut = shortcuts.get(command);
+            if (shortcut === undefined) {
+                log.error('Unknown command in keymaps:', keybind, command);
                 continue;
             }
 
-            const shortcut = KeyShortcuts[action];
-            const method = shortcut.dispatch.bind(undefined, dispatcher);
             bindKey(keybind, event => {
                 event.preventDefault();
                 event.stopPropagation();
-                log.debug('Triggered key shortcut:', action, keybind);
+                log.debug('Triggered key shortcut:', command, keybind);
                 try {
-                    method();
+                    runCommand(command, dispatcher);
                 } catch (err) {
-                    log.error('Error while handling key action', action, err);
+                    log.error('Error while running command', command, err);
                 }
             });
 
-            const s = bounds.get(action);
-            if (s === undefined) {
-                bounds.set(action, {
-                    ...shortcut,
-                    action,
-                    binds: [keybind],
-                });
-            } else {
-                s.binds.push(keybind);
+            shortcut.binds.push(keybind);
+        }
+
+        for (const [index, { key, name }] of externalCommands.entries()) {
+            if (key === null) {
+                continue;
             }
+            bindKey(key, event => {
+                event.preventDefault();
+                event.stopPropagation();
+                log.debug('Triggered external command:', name, key);
+                runExternalCommand(index);
+            });
+        }
+
+        for (const search of saved) {
+            const { key, name } = search;
+            if (key === null) {
+                continue;
+            }
+            bindKey(key, event => {
+                event.preventDefault();
+                event.stopPropagation();
+                log.debug('Triggered saved search:', name, key);
+                dispatcher.runSavedSearch(search);
+            });
         }
 
-        const shortcuts = [...bounds.values()];
-        shortcuts.sort((l, r) => l.action.localeCompare(r.action));
-        this.sortedShortcuts = shortcuts;
+        this.allShortcuts = [...shortcuts.values()];
+        this.allExternalCommands = externalCommands;
     }
 }
@@ -155,10 +155,21 @@ const FOOTNOTE_BACKREF_STYLE: React.CSSProperties = {
     margin: '0 0.25em',
 };
 
+// Scroll position to restore. `scroll` is the ratio to the height of the document and is used when the heading is
+// not found. `offset` is the distance from the top of the heading in pixels
+export interface ScrollPosition {
+    scroll: number;
+    heading: string | null;
+    offset: number;
+}
+
 export interface MarkdownReactTree {
     root: ReactNode;
     lastModified: React.RefObject<HTMLSpanElement> | null;
-    matchCount: number;
+    // Scroll position to restore after rendering the tree
+    scroll: ScrollPosition | null;
+    // Text of the heading to scroll to after rendering the tree. This takes precedence over `scroll`
+    heading: string | null;
 }
 
 function rawText(elem: RenderTreeElem): string {
@@ -180,7 +191,7 @@ function childrenText(children: RenderTreeElem[]): null | [string, boolean] {
         } else if (child.t === 'modified') {
             modified = true;
         } else {
-            return null; // Reaches here when search highlight is included
+            return null; // Reaches here when unexpected element is included
         }
     }
 
@@ -199,6 +210,30 @@ function lastElementOf(nodes: ReactNode[]): ReactElement | null {
     return isReactElement(last) ? last : null;
 }
 
+// IDs of text spans in the render tree. The same numbering as the main process is done by traversing the tree in order
+type SpanIds = Map<RenderTreeElem[], (number | undefined)[]>;
+
+function collectSpanIds(tree: RenderTreeElem[]): SpanIds {
+    const ret: SpanIds = new Map();
+    let next = 0;
+    function collect(elems: RenderTreeElem[]): void {
+        const ids: (number | undefined)[] = [];
+        for (const elem of elems) {
+            if (typeof elem === 'string' || elem.t === 'hidden') {
+                ids.push(next++);
+                continue;
+            }
+            ids.push(undefined);
+            if ('c' in elem && elem.t !== 'img' && elem.t !== 'emoji') {
+                collect(elem.c);
+            }
+        }
+        ret.set(elems, ids);
+    }
+    collect(tree);
+    return ret;
+}
+
 interface TableState {
     aligns: RenderTreeTableAlign[];
     index: number;
@@ -219,22 +254,23 @@ class RenderTreeToReact {
     private table: TableState | null;
     private lastModifiedRef: React.RefObject<HTMLSpanElement> | null;
     private readonly footNotes: RenderTreeFootNoteDef[];
-    private matchCount: number;
     private readonly fence: FenceRenderer;
     private readonly mathjax: MathJaxRenderer;
+    private spanIds: SpanIds;
 
     constructor(mermaid: MermaidRenderer, mathjax: MathJaxRenderer) {
         this.table = null;
         this.footNotes = [];
         this.lastModifiedRef = null;
-        this.matchCount = 0;
         this.render = this.render.bind(this);
         this.fence = new FenceRenderer(mermaid, mathjax);
         this.mathjax = mathjax;
+        this.spanIds = new Map();
     }
 
     async run(tree: RenderTreeElem[]): Promise<MarkdownReactTree> {
         log.debug('Rendering preview tree', tree);
+        this.spanIds = collectSpanIds(tree);
         const blocks = await this.renderAll(tree);
         const footNotes = await this.renderFootnotes();
         const root = (
@@ -246,7 +282,8 @@ class RenderTreeToReact {
         return {
             root,
             lastModified: this.lastModifiedRef,
-            matchCount: this.matchCount,
+            scroll: null,
+            heading: null,
         };
     }
 
@@ -295,12 +332,48 @@ class RenderTreeToReact {
     }
 
     private renderAll(elems: RenderTreeElem[]): Promise<ReactNode[]> {
-        return Promise.all(elems.map((elem, idx) => this.render(elem, idx)));
+        const ids = this.spanIds.get(elems);
+        return Promise.all(elems.map((elem, idx) => this.render(elem, idx, ids?.[idx])));
+    }
+
+    // Text spans are marked with their IDs so that search matches can be highlighted on the rendered DOM
+    private renderSpan(text: string, key: number | undefined, id: number | undefined): ReactNode {
+        if (id === undefined) {
+            return text;
+        }
+        return (
+            <span key={key} data-span={id}>
+                {text}
+            </span>
+        );
+    }
+
+    // Matches in hidden text are highlighted at the position of this empty element
+    private renderHidden(key: number | undefined, id: number | undefined): ReactNode {
+        if (id === undefined) {
+            return null;
+        }
+        return <span key={key} data-span={id} />;
+    }
+
+    // Code fence is rendered as one element so the offsets of its text spans in the element are marked instead
+    private codeFenceSpans(elems: RenderTreeElem[]): [string, number] {
+        const ids = this.spanIds.get(elems) ?? [];
+        const spans = [];
+        let offset = 0;
+        for (const [idx, elem] of elems.entries()) {
+            const id = ids[idx];
+            if (typeof elem === 'string' && id !== undefined) {
+                spans.push(`${id}:${offset}`);
+                offset += elem.length;
+            }
+        }
+        return [spans.join(','), offset];
     }
 
-    private async render(elem: RenderTreeElem, key?: number): Promise<ReactNode> {
+    private async render(elem: RenderTreeElem, key?: number, id?: number): Promise<ReactNode> {
         if (typeof elem === 'string') {
-            return elem;
+            return this.renderSpan(elem, key, id);
         }
 
         switch (elem.t) {
@@ -308,7 +381,10 @@ class RenderTreeToReact {
                 return <p key={key}>{await this.renderAll(elem.c)}</p>;
             case 'h': {
                 const tag = `h${elem.level}`;
-                const props: JSX.IntrinsicElements['h1'] = { key };
+                const props: JSX.IntrinsicElements['h1'] & { 'data-offset': number } = {
+                    key,
+                    'data-offset': elem.offset,
+                };
                 if (elem.id) {
                     props.id = elem.id; // TODO?: Clobber IDs
                 }
@@ -355,14 +431,12 @@ class RenderTreeToReact {
                     return <code key={key}>{await this.renderAll(elem.c)}</code>;
                 }
                 const [node, modified] = rendered;
-                if (!modified) {
-                    return node;
-                }
+                const [spans, length] = this.codeFenceSpans(elem.c);
                 return (
-                    <React.Fragment key={key}>
-                        {this.lastModified()}
+                    <span key={key} className="code-fence-spans" data-spans={spans} data-length={length}>
+                        {modified && this.lastModified()}
                         {node}
-                    </React.Fragment>
+                    </span>
                 );
             }
             case 'ol':
@@ -468,32 +542,8 @@ class RenderTreeToReact {
             }
             case 'modified':
                 return this.lastModified(key);
-            case 'match':
-                return (
-                    <span key={key} className="search-text">
-                        {await this.renderAll(elem.c)}
-                    </span>
-                );
-            case 'match-current':
-                return (
-                    <span key={key} className="search-text-current">
-                        {await this.renderAll(elem.c)}
-                    </span>
-                );
-            case 'match-start':
-                this.matchCount++;
-                return (
-                    <span key={key} className="search-text-start">
-                        {await this.renderAll(elem.c)}
-                    </span>
-                );
-            case 'match-current-start':
-                this.matchCount++;
-                return (
-                    <span key={key} className="search-text-current-start">
-                        {await this.renderAll(elem.c)}
-                    </span>
-                );
+            case 'hidden':
+                return this.renderHidden(key, id);
             default:
                 log.error('Unknown render tree element:', JSON.stringify(elem));
                 return null;
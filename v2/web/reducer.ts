@@ -1,6 +1,18 @@
 import * as log from './log';
-import type { SearchMatcher, WindowTheme } from './ipc';
-import { searchNextIndex, searchPreviousIndex } from './search';
+import type {
+    SearchMatcher,
+    SearchScope,
+    SearchResult,
+    SearchQuery,
+    WindowTheme,
+    FileTreeEntry,
+    FoundFile,
+    WorkspaceMatch,
+    HighlightThemes,
+    Bookmark,
+    RecentFile,
+} from './ipc';
+import { searchNextIndex, searchPreviousIndex, searchJumpIndex } from './search';
 import type { MarkdownReactTree } from './markdown';
 
 export type Theme = 'light' | 'dark';
@@ -16,32 +28,72 @@ export type NotificationContent =
     | {
           kind: 'alwaysOnTop';
           pinned: boolean;
+      }
+    | {
+          kind: 'configError';
+          message: string;
+      }
+    | {
+          kind: 'commandError';
+          message: string;
+      }
+    | {
+          kind: 'bookmarked';
+          added: boolean;
+      }
+    | {
+          kind: 'bookmarkNotFound';
+          bookmark: Bookmark;
       };
 
 export interface State {
     previewTree: MarkdownReactTree;
     searching: boolean;
+    searchQuery: string;
     searchIndex: number | null;
+    searchMatchCount: number;
     matcher: SearchMatcher;
+    scope: SearchScope;
+    searchResults: SearchResult[];
+    resultsPanel: boolean;
+    searchHistory: SearchQuery[];
     outline: boolean;
     theme: Theme;
     history: boolean;
-    files: string[];
+    files: RecentFile[];
     help: boolean;
     notifying: boolean;
     notification: NotificationContent;
     welcome: boolean;
+    sidebar: boolean;
+    fileTree: FileTreeEntry[];
+    currentFile: string | null;
+    finder: boolean;
+    foundFiles: FoundFile[];
+    workspaceSearch: boolean;
+    workspaceMatches: WorkspaceMatch[];
+    highlightThemes: HighlightThemes | null;
+    commandPalette: boolean;
+    bookmarks: Bookmark[];
+    bookmarkPalette: boolean;
 }
 
 export const INITIAL_STATE: State = {
     previewTree: {
         root: null,
         lastModified: null,
-        matchCount: 0,
+        scroll: null,
+        heading: null,
     },
     searching: false,
+    searchQuery: '',
     searchIndex: null,
+    searchMatchCount: 0,
     matcher: 'SmartCase',
+    scope: 'All',
+    searchResults: [],
+    resultsPanel: false,
+    searchHistory: [],
     outline: false,
     theme: 'light',
     history: false,
@@ -50,9 +102,20 @@ export const INITIAL_STATE: State = {
     notifying: false,
     notification: { kind: 'reload' },
     welcome: false,
+    sidebar: false,
+    fileTree: [],
+    currentFile: null,
+    finder: false,
+    foundFiles: [],
+    workspaceSearch: false,
+    workspaceMatches: [],
+    highlightThemes: null,
+    commandPalette: false,
+    bookmarks: [],
+    bookmarkPalette: false,
 };
 
-const MAX_HISTORIES = 50;
+const MAX_SEARCH_HISTORY = 100;
 
 type Action =
     | {
@@ -61,6 +124,8 @@ type Action =
       }
     | {
           kind: 'open_search';
+          query: string;
+          index: number | null;
       }
     | {
           kind: 'close_search';
@@ -77,6 +142,30 @@ type Action =
           kind: 'search_matcher';
           matcher: SearchMatcher;
       }
+    | {
+          kind: 'search_scope';
+          scope: SearchScope;
+      }
+    | {
+          kind: 'search_match_count';
+          count: number;
+      }
+    | {
+          kind: 'search_results';
+          results: SearchResult[];
+      }
+    | {
+          kind: 'results_panel';
+          open: boolean;
+      }
+    | {
+          kind: 'search_history';
+          history: SearchQuery[];
+      }
+    | {
+          kind: 'add_search_history';
+          item: SearchQuery;
+      }
     | {
           kind: 'outline';
           open: boolean;
@@ -89,10 +178,6 @@ type Action =
           kind: 'history';
           open: boolean;
       }
-    | {
-          kind: 'new_file';
-          path: string;
-      }
     | {
           kind: 'help';
           open: boolean;
@@ -103,10 +188,54 @@ type Action =
       }
     | {
           kind: 'recent_files';
-          paths: string[];
+          files: RecentFile[];
       }
     | {
           kind: 'welcome';
+      }
+    | {
+          kind: 'sidebar';
+          open: boolean;
+      }
+    | {
+          kind: 'file_tree';
+          roots: FileTreeEntry[];
+      }
+    | {
+          kind: 'current_file';
+          path: string;
+      }
+    | {
+          kind: 'finder';
+          open: boolean;
+      }
+    | {
+          kind: 'found_files';
+          files: FoundFile[];
+      }
+    | {
+          kind: 'workspace_search';
+          open: boolean;
+      }
+    | {
+          kind: 'workspace_matches';
+          matches: WorkspaceMatch[];
+      }
+    | {
+          kind: 'highlight_themes';
+          themes: HighlightThemes | null;
+      }
+    | {
+          kind: 'command_palette';
+          open: boolean;
+      }
+    | {
+          kind: 'bookmark_palette';
+          open: boolean;
+      }
+    | {
+          kind: 'bookmarks';
+          bookmarks: Bookmark[];
       };
 export type Dispatch = React.Dispatch<Action>;
 
@@ -115,35 +244,26 @@ export function reducer(state: State, action: Action): State {
     switch (action.kind) {
         case 'preview_content':
             return { ...state, previewTree: action.tree, welcome: false };
-        case 'new_file': {
-            const index = state.files.indexOf(action.path);
-            if (index >= 0) {
-                const files = state.files.slice(0, index);
-                for (let i = index + 1; i < state.files.length; i++) {
-                    files.push(state.files[i]);
-                }
-                files.push(state.files[index]);
-                return { ...state, files };
-            } else if (state.files.length >= MAX_HISTORIES) {
-                state.files.push(action.path);
-                return {
-                    ...state,
-                    files: state.files.slice(1),
-                };
-            } else {
-                return {
-                    ...state,
-                    files: [...state.files, action.path],
-                };
-            }
-        }
         case 'open_search':
-            if (state.searching) {
+            if (state.searching && action.query === '') {
                 return state;
             }
-            return { ...state, searching: true, searchIndex: null, outline: false, history: false, help: false };
+            return {
+                ...state,
+                searching: true,
+                searchQuery: action.query,
+                searchIndex: action.index,
+                outline: false,
+                history: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
         case 'close_search':
-            return { ...state, searching: false, searchIndex: null };
+            return { ...state, searching: false, searchQuery: '', searchIndex: null, searchResults: [] };
         case 'search_index':
             if (!state.searching) {
                 return state;
@@ -154,12 +274,146 @@ export function reducer(state: State, action: Action): State {
             };
         case 'search_matcher':
             return { ...state, matcher: action.matcher };
+        case 'search_scope':
+            return { ...state, scope: action.scope };
+        case 'search_match_count':
+            return { ...state, searchMatchCount: action.count };
+        case 'search_results':
+            if (!state.searching) {
+                return state;
+            }
+            return { ...state, searchResults: action.results };
+        case 'results_panel':
+            return { ...state, resultsPanel: action.open };
+        case 'search_history':
+            return { ...state, searchHistory: action.history };
+        case 'add_search_history': {
+            const { query, matcher } = action.item;
+            const history = state.searchHistory.filter(h => h.query !== query || h.matcher !== matcher);
+            history.push(action.item);
+            if (history.length > MAX_SEARCH_HISTORY) {
+                history.splice(0, history.length - MAX_SEARCH_HISTORY);
+            }
+            return { ...state, searchHistory: history };
+        }
         case 'outline':
-            return { ...state, outline: action.open, searching: false, history: false, help: false };
+            return {
+                ...state,
+                outline: action.open,
+                searching: false,
+                history: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
         case 'history':
-            return { ...state, history: action.open, searching: false, outline: false, help: false };
+            return {
+                ...state,
+                history: action.open,
+                searching: false,
+                outline: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
         case 'help':
-            return { ...state, help: action.open, searching: false, outline: false, history: false };
+            return {
+                ...state,
+                help: action.open,
+                searching: false,
+                outline: false,
+                history: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
+        case 'finder':
+            return {
+                ...state,
+                finder: action.open,
+                foundFiles: [],
+                searching: false,
+                outline: false,
+                history: false,
+                help: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
+        case 'found_files':
+            if (!state.finder) {
+                return state;
+            }
+            return { ...state, foundFiles: action.files };
+        case 'workspace_search':
+            return {
+                ...state,
+                workspaceSearch: action.open,
+                workspaceMatches: [],
+                searching: false,
+                outline: false,
+                history: false,
+                help: false,
+                finder: false,
+                highlightThemes: null,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
+        case 'workspace_matches':
+            if (!state.workspaceSearch) {
+                return state;
+            }
+            return { ...state, workspaceMatches: action.matches };
+        case 'highlight_themes':
+            return {
+                ...state,
+                highlightThemes: action.themes,
+                searching: false,
+                outline: false,
+                history: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                commandPalette: false,
+                bookmarkPalette: false,
+            };
+        case 'command_palette':
+            return {
+                ...state,
+                commandPalette: action.open,
+                searching: false,
+                outline: false,
+                history: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                bookmarkPalette: false,
+            };
+        case 'bookmark_palette':
+            return {
+                ...state,
+                bookmarkPalette: action.open,
+                searching: false,
+                outline: false,
+                history: false,
+                help: false,
+                finder: false,
+                workspaceSearch: false,
+                highlightThemes: null,
+                commandPalette: false,
+            };
+        case 'bookmarks':
+            return { ...state, bookmarks: action.bookmarks };
         case 'notification':
             if (action.notification === null) {
                 return { ...state, notifying: false };
@@ -169,9 +423,15 @@ export function reducer(state: State, action: Action): State {
         case 'theme':
             return { ...state, theme: action.theme };
         case 'recent_files':
-            return { ...state, files: action.paths };
+            return { ...state, files: action.files };
         case 'welcome':
             return { ...state, welcome: true };
+        case 'sidebar':
+            return { ...state, sidebar: action.open };
+        case 'file_tree':
+            return { ...state, fileTree: action.roots };
+        case 'current_file':
+            return { ...state, currentFile: action.path };
         default:
             throw new Error(`Unknown action: ${action}`);
     }
@@ -183,8 +443,8 @@ export function previewContent(tree: MarkdownReactTree): Action {
     return { kind: 'preview_content', tree };
 }
 
-export function openSearch(): Action {
-    return { kind: 'open_search' };
+export function openSearch(query = '', index: number | null = null): Action {
+    return { kind: 'open_search', query, index };
 }
 
 export function closeSearch(): Action {
@@ -207,6 +467,34 @@ export function setSearchMatcher(matcher: SearchMatcher): Action {
     return { kind: 'search_matcher', matcher };
 }
 
+export function setSearchScope(scope: SearchScope): Action {
+    return { kind: 'search_scope', scope };
+}
+
+export function searchJump(index: number | null, next: number): Action {
+    return searchIndex(searchJumpIndex(index, next));
+}
+
+export function setSearchResults(results: SearchResult[]): Action {
+    return { kind: 'search_results', results };
+}
+
+export function setSearchMatchCount(count: number): Action {
+    return { kind: 'search_match_count', count };
+}
+
+export function openResultsPanel(open: boolean): Action {
+    return { kind: 'results_panel', open };
+}
+
+export function setSearchHistory(history: SearchQuery[]): Action {
+    return { kind: 'search_history', history };
+}
+
+export function addSearchHistory(item: SearchQuery): Action {
+    return { kind: 'add_search_history', item };
+}
+
 export function openOutline(): Action {
     return { kind: 'outline', open: true };
 }
@@ -230,10 +518,6 @@ export function closeHistory(): Action {
     return { kind: 'history', open: false };
 }
 
-export function newFile(path: string): Action {
-    return { kind: 'new_file', path };
-}
-
 export function openHelp(): Action {
     return { kind: 'help', open: true };
 }
@@ -258,10 +542,94 @@ export function notifyAlwaysOnTop(pinned: boolean): Action {
     return { kind: 'notification', notification: { kind: 'alwaysOnTop', pinned } };
 }
 
-export function setRecentFiles(paths: string[]): Action {
-    return { kind: 'recent_files', paths };
+export function notifyConfigError(message: string): Action {
+    return { kind: 'notification', notification: { kind: 'configError', message } };
+}
+
+export function notifyCommandError(message: string): Action {
+    return { kind: 'notification', notification: { kind: 'commandError', message } };
+}
+
+export function notifyBookmarked(added: boolean): Action {
+    return { kind: 'notification', notification: { kind: 'bookmarked', added } };
+}
+
+export function notifyBookmarkNotFound(bookmark: Bookmark): Action {
+    return { kind: 'notification', notification: { kind: 'bookmarkNotFound', bookmark } };
+}
+
+export function openHighlightThemes(themes: HighlightThemes): Action {
+    return { kind: 'highlight_themes', themes };
+}
+
+export function closeHighlightThemes(): Action {
+    return { kind: 'highlight_themes', themes: null };
+}
+
+export function setRecentFiles(files: RecentFile[]): Action {
+    return { kind: 'recent_files', files };
 }
 
 export function welcome(): Action {
     return { kind: 'welcome' };
 }
+
+export function openSidebar(): Action {
+    return { kind: 'sidebar', open: true };
+}
+
+export function closeSidebar(): Action {
+    return { kind: 'sidebar', open: false };
+}
+
+export function setFileTree(roots: FileTreeEntry[]): Action {
+    return { kind: 'file_tree', roots };
+}
+
+export function setCurrentFile(path: string): Action {
+    return { kind: 'current_file', path };
+}
+
+export function openFinder(): Action {
+    return { kind: 'finder', open: true };
+}
+
+export function closeFinder(): Action {
+    return { kind: 'finder', open: false };
+}
+
+export function setFoundFiles(files: FoundFile[]): Action {
+    return { kind: 'found_files', files };
+}
+
+export function openWorkspaceSearch(): Action {
+    return { kind: 'workspace_search', open: true };
+}
+
+export function closeWorkspaceSearch(): Action {
+    return { kind: 'workspace_search', open: false };
+}
+
+export function setWorkspaceMatches(matches: WorkspaceMatch[]): Action {
+    return { kind: 'workspace_matches', matches };
+}
+
+export function openCommandPalette(): Action {
+    return { kind: 'command_palette', open: true };
+}
+
+export function closeCommandPalette(): Action {
+    return { kind: 'command_palette', open: false };
+}
+
+export function openBookmarks(): Action {
+    return { kind: 'bookmark_palette', open: true };
+}
+
+export function closeBookmarks(): Action {
+    return { kind: 'bookmark_palette', open: false };
+}
+
+export function setBookmarks(bookmarks: Bookmark[]): Action {
+    return { kind: 'bookmarks', bookmarks };
+}
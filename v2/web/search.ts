@@ -1,5 +1,168 @@
+import type { SpanMatch } from './ipc';
 import * as log from './log';
 
+export interface SearchHighlight {
+    index: number | null; // Current match index
+    count: number;
+    matches: SpanMatch[];
+}
+
+const OVERLAY_ID = 'search-highlights';
+
+// Search matches are highlighted with overlay elements put on the rendered document so that the document does not need
+// to be rendered again on every search. The overlays are laid out again when the size of the document is changed.
+let highlight: SearchHighlight | null = null;
+let observer: ResizeObserver | null = null;
+
+// Element containing the text span, and the offset of the span in the text of the element. Code fences are rendered as
+// one element by highlight.js so spans in them have offsets. `null` means the element only contains the span.
+type SpanLocations = Map<number, [HTMLElement, number | null]>;
+
+function collectSpanLocations(root: ParentNode): SpanLocations {
+    const ret: SpanLocations = new Map();
+    for (const elem of root.querySelectorAll<HTMLElement>('[data-span]')) {
+        ret.set(Number(elem.dataset.span), [elem, null]);
+    }
+    for (const elem of root.querySelectorAll<HTMLElement>('[data-spans]')) {
+        for (const span of elem.dataset.spans?.split(',') ?? []) {
+            const [id, offset] = span.split(':');
+            ret.set(Number(id), [elem, Number(offset)]);
+        }
+    }
+    return ret;
+}
+
+// Find the text node and the offset in it from the offset in the text content of the element
+function textPosition(elem: Node, offset: number): [Node, number] | null {
+    const walker = document.createTreeWalker(elem, NodeFilter.SHOW_TEXT);
+    for (let node = walker.nextNode(); node !== null; node = walker.nextNode()) {
+        const len = node.textContent?.length ?? 0;
+        if (offset <= len) {
+            return [node, offset];
+        }
+        offset -= len;
+    }
+    return null;
+}
+
+function spanRange(m: SpanMatch, [elem, offset]: [HTMLElement, number | null]): Range | null {
+    const range = document.createRange();
+    if (offset === null) {
+        const text = elem.firstChild;
+        if (text === null || text.nodeType !== Node.TEXT_NODE) {
+            return null; // Hidden text such as link URLs
+        }
+        range.setStart(text, m.start);
+        range.setEnd(text, m.end);
+        return range;
+    }
+
+    // Diagrams and math expressions don't contain the text of code fence
+    if (elem.textContent?.length !== Number(elem.dataset.length)) {
+        return null;
+    }
+    const start = textPosition(elem, offset + m.start);
+    const end = textPosition(elem, offset + m.end);
+    if (start === null || end === null) {
+        return null;
+    }
+    range.setStart(...start);
+    range.setEnd(...end);
+    return range;
+}
+
+// Rectangles of the piece of match in the viewport. When the text is not rendered, a marker is put at the element
+function pieceRects(m: SpanMatch, spans: SpanLocations): DOMRect[] {
+    const location = spans.get(m.span);
+    if (location === undefined) {
+        return [];
+    }
+    const range = spanRange(m, location);
+    if (range !== null) {
+        return Array.from(range.getClientRects()).filter(r => r.width > 0);
+    }
+    const [elem] = location;
+    const rect = (elem.firstElementChild ?? elem).getBoundingClientRect();
+    return [new DOMRect(rect.left, rect.top, 0, rect.height)];
+}
+
+function overlayElem(className: string, rect: DOMRect | null): HTMLElement {
+    const elem = document.createElement('div');
+    elem.className = className;
+    if (rect === null) {
+        elem.style.display = 'none'; // Keep the number of matches even if the match is not rendered
+        return elem;
+    }
+    elem.style.left = `${rect.left + window.scrollX}px`;
+    elem.style.top = `${rect.top + window.scrollY}px`;
+    elem.style.height = `${rect.height || 16}px`;
+    elem.style.width = rect.width > 0 ? `${rect.width}px` : '0.3em';
+    return elem;
+}
+
+function layoutOverlays(scroll: boolean): void {
+    document.getElementById(OVERLAY_ID)?.remove();
+    if (highlight === null || highlight.matches.length === 0) {
+        return;
+    }
+
+    const preview = document.querySelector('.markdown-body');
+    if (preview === null) {
+        return;
+    }
+    const spans = collectSpanLocations(preview);
+    const container = document.createElement('div');
+    container.id = OVERLAY_ID;
+
+    const { matches, index } = highlight;
+    let start = 0;
+    while (start < matches.length) {
+        const current = matches[start].index;
+        let end = start;
+        while (end < matches.length && matches[end].index === current) {
+            end++;
+        }
+
+        const className = current === index ? 'search-text-current' : 'search-text';
+        const rects = matches.slice(start, end).flatMap(m => pieceRects(m, spans));
+        if (rects.length === 0) {
+            container.appendChild(overlayElem(`${className}-start`, null));
+        }
+        rects.forEach((rect, i) => {
+            container.appendChild(overlayElem(i === 0 ? `${className}-start` : className, rect));
+        });
+        start = end;
+    }
+
+    document.body.appendChild(container);
+    log.debug('Laid out search highlights:', container.childElementCount);
+
+    if (scroll) {
+        const elem = container.querySelector('.search-text-current-start');
+        if (elem !== null) {
+            scrollIntoView(elem);
+        }
+    }
+}
+
+// Highlight the matches sent by the main. The current match is scrolled into view
+export function setSearchHighlight(next: SearchHighlight | null): void {
+    highlight = next;
+    const preview = document.querySelector('.markdown-body');
+    if (observer === null && preview !== null) {
+        observer = new ResizeObserver(() => {
+            layoutOverlays(false);
+        });
+        observer.observe(preview);
+    }
+    layoutOverlays(true);
+}
+
+// Lay out the highlights again since the document was rendered again
+export function relayoutSearchHighlight(): void {
+    layoutOverlays(true);
+}
+
 function isInViewport(elem: Element): boolean {
     const rect = elem.getBoundingClientRect();
     const height = window.innerHeight;
@@ -37,14 +200,41 @@ function updateMatchClassNames(className: string, startIndex: number, elems: HTM
     }
 }
 
-export function searchNextIndex(index: number | null): number | null {
-    const all = findAllSearchMatchElems();
+function findStartIndices(all: HTMLElement[]): number[] {
     const startIndices: number[] = [];
     for (let i = 0; i < all.length; i++) {
         if (all[i].className.endsWith('-start')) {
             startIndices.push(i);
         }
     }
+    return startIndices;
+}
+
+function moveCurrentMatch(
+    index: number | null,
+    next: number | null,
+    startIndices: number[],
+    all: HTMLElement[],
+): void {
+    if (index === next) {
+        return;
+    }
+    if (index !== null && index < startIndices.length) {
+        updateMatchClassNames('search-text', startIndices[index], all);
+    }
+    if (highlight !== null) {
+        highlight.index = next; // Keep the current match on laying out the highlights again
+    }
+    if (next !== null) {
+        const startIndex = startIndices[next];
+        updateMatchClassNames('search-text-current', startIndex, all);
+        scrollIntoView(all[startIndex]);
+    }
+}
+
+export function searchNextIndex(index: number | null): number | null {
+    const all = findAllSearchMatchElems();
+    const startIndices = findStartIndices(all);
 
     let next;
     if (startIndices.length === 0) {
@@ -64,28 +254,13 @@ export function searchNextIndex(index: number | null): number | null {
     }
 
     log.debug('Search next index:', index, next);
-    if (index !== next) {
-        if (index !== null && index < startIndices.length) {
-            updateMatchClassNames('search-text', startIndices[index], all);
-        }
-        if (next !== null) {
-            const startIndex = startIndices[next];
-            updateMatchClassNames('search-text-current', startIndex, all);
-            scrollIntoView(all[startIndex]);
-        }
-    }
-
+    moveCurrentMatch(index, next, startIndices, all);
     return next;
 }
 
 export function searchPreviousIndex(index: number | null): number | null {
     const all = findAllSearchMatchElems();
-    const startIndices: number[] = [];
-    for (let i = 0; i < all.length; i++) {
-        if (all[i].className.endsWith('-start')) {
-            startIndices.push(i);
-        }
-    }
+    const startIndices = findStartIndices(all);
 
     let next;
     if (startIndices.length === 0) {
@@ -107,16 +282,19 @@ export function searchPreviousIndex(index: number | null): number | null {
     }
 
     log.debug('Search previous index:', index, next);
-    if (index !== next) {
-        if (index !== null && index < startIndices.length) {
-            updateMatchClassNames('search-text', startIndices[index], all);
-        }
-        if (next !== null) {
-            const startIndex = startIndices[next];
-            updateMatchClassNames('search-text-current', startIndex, all);
-            scrollIntoView(all[startIndex]);
-        }
+    moveCurrentMatch(index, next, startIndices, all);
+    return next;
+}
+
+export function searchJumpIndex(index: number | null, next: number): number | null {
+    const all = findAllSearchMatchElems();
+    const startIndices = findStartIndices(all);
+    if (next >= startIndices.length) {
+        log.error('Search index to jump is out of range:', next, startIndices.length);
+        return index;
     }
 
+    log.debug('Search jump index:', index, next);
+    moveCurrentMatch(index, next, startIndices, all);
     return next;
 }